@@ -1,6 +1,9 @@
 use monte_carlo_options_simd::{
+    bs,
+    bs_simd,
     mc,
-    mc_simd
+    mc_simd,
+    params::OptionType
 };
 use wide::*;
 use criterion::{criterion_group, criterion_main, Criterion};
@@ -99,5 +102,30 @@ fn criterion_benchmark_20000x100(c: &mut Criterion) {
     }));
 }
 
-criterion_group!(benches, criterion_benchmark_80x100, criterion_benchmark_1000x100, criterion_benchmark_10000x100, criterion_benchmark_20000x100);
+fn criterion_benchmark_bs_chain(c: &mut Criterion) {
+    let strikes : Vec<f32> = (0..5000).map(|i| 50.0 + 0.02 * i as f32).collect();
+    let spots : Vec<f32> = vec![100.0; strikes.len()];
+    let volatilities : Vec<f32> = vec![VOLATILITY; strikes.len()];
+    let risk_free_rates : Vec<f32> = vec![RISK_FREE_RATE; strikes.len()];
+    let years_to_expiries : Vec<f32> = vec![YEARS_TO_EXPIRY; strikes.len()];
+    let dividend_yields : Vec<f32> = vec![DIVIDEND_YIELD; strikes.len()];
+
+    c.bench_function("black scholes chain 5000", |b| b.iter(|| {
+        for &strike in &strikes {
+            _ = bs::call_price(100.0, strike, VOLATILITY, RISK_FREE_RATE, YEARS_TO_EXPIRY, DIVIDEND_YIELD);
+            _ = bs::call_delta(100.0, strike, VOLATILITY, RISK_FREE_RATE, YEARS_TO_EXPIRY, DIVIDEND_YIELD);
+            _ = bs::gamma(100.0, strike, VOLATILITY, RISK_FREE_RATE, YEARS_TO_EXPIRY, DIVIDEND_YIELD);
+            _ = bs::vega(100.0, strike, VOLATILITY, RISK_FREE_RATE, YEARS_TO_EXPIRY, DIVIDEND_YIELD);
+            _ = bs::call_rho(100.0, strike, VOLATILITY, RISK_FREE_RATE, YEARS_TO_EXPIRY, DIVIDEND_YIELD);
+            _ = bs::call_theta(100.0, strike, VOLATILITY, RISK_FREE_RATE, YEARS_TO_EXPIRY, DIVIDEND_YIELD);
+            _ = bs::call_dividend_rho(100.0, strike, VOLATILITY, RISK_FREE_RATE, YEARS_TO_EXPIRY, DIVIDEND_YIELD);
+        }
+    }));
+
+    c.bench_function("black scholes fast chain 5000", |b| b.iter(|| {
+        _ = bs_simd::batch_greeks(OptionType::Call, &spots, &strikes, &volatilities, &risk_free_rates, &years_to_expiries, &dividend_yields);
+    }));
+}
+
+criterion_group!(benches, criterion_benchmark_80x100, criterion_benchmark_1000x100, criterion_benchmark_10000x100, criterion_benchmark_20000x100, criterion_benchmark_bs_chain);
 criterion_main!(benches);
\ No newline at end of file
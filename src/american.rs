@@ -0,0 +1,399 @@
+// American options, priced with the Longstaff-Schwartz least-squares Monte Carlo method. At
+// every exercise date the value of holding on is estimated by regressing the discounted future
+// cash flows of the in-the-money paths on basis functions of the spot. A Cox-Ross-Rubinstein
+// binomial tree is also provided as a reference.
+
+use crate::error::PricingError;
+use crate::float::Float;
+use crate::mc_simd::simulate_paths;
+use crate::params::{ MarketData, OptionContract, OptionType, SimulationConfig, MIN_STEPS };
+
+/// Highest degree of basis functions the continuation value can be regressed on
+pub const MAX_BASIS_DEGREE: usize = 8;
+
+/// Functions of the moneyness, the spot divided by the strike, that the continuation value is
+/// regressed on. The degree is the highest power of the moneyness.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Basis {
+    /// Laguerre polynomials L_0 to L_degree, as in the Longstaff-Schwartz paper
+    Laguerre(usize),
+    /// Monomials 1, x, ..., x^degree
+    Polynomial(usize),
+}
+
+impl Basis {
+    fn degree(&self) -> usize {
+        match *self {
+            Basis::Laguerre(degree) | Basis::Polynomial(degree) => degree,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), PricingError> {
+        let degree = self.degree();
+        if degree == 0 || degree > MAX_BASIS_DEGREE {
+            return Err(PricingError::InvalidBasisDegree { degree, max_degree: MAX_BASIS_DEGREE });
+        }
+        Ok(())
+    }
+
+    // Writes the degree + 1 basis functions at x to values
+    fn evaluate(&self, x: f64, values: &mut [f64]) {
+        values[0] = 1.0;
+        match *self {
+            Basis::Laguerre(degree) => {
+                values[1] = 1.0 - x;
+                // (n + 1) L_{n+1}(x) = (2n + 1 - x) L_n(x) - n L_{n-1}(x)
+                for n in 1..degree {
+                    let n_f64 = n as f64;
+                    values[n + 1] =
+                        ((2.0 * n_f64 + 1.0 - x) * values[n] - n_f64 * values[n - 1]) / (n_f64 + 1.0);
+                }
+            }
+            Basis::Polynomial(degree) => {
+                for n in 1..=degree {
+                    values[n] = values[n - 1] * x;
+                }
+            }
+        }
+    }
+}
+
+impl Default for Basis {
+    /// Laguerre polynomials up to degree 3
+    fn default() -> Basis {
+        Basis::Laguerre(3)
+    }
+}
+
+/// An American option price from the Longstaff-Schwartz method
+#[derive(Clone, Debug, PartialEq)]
+pub struct AmericanEstimate<F: Float = f32> {
+    /// The price, at least the value of exercising immediately
+    pub price: F,
+    /// Sample standard error of the price
+    pub std_error: F,
+    /// The early exercise boundary as (years from now, spot) at every exercise date before
+    /// expiry. The spot is the highest spot a put, or the lowest spot a call, was exercised at
+    /// on that date, and NaN if no path was exercised.
+    pub exercise_boundary: Vec<(F, F)>,
+    /// Number of simulated paths that went into the price
+    pub num_paths: usize,
+}
+
+// Solves the least squares problem for the coefficients of the basis functions through the
+// normal equations, which are small enough for Gaussian elimination with partial pivoting.
+// Returns None if the system is singular, e.g. when all in-the-money paths share one spot.
+// The pivots are compared to the largest diagonal entry of the Gram matrix before elimination,
+// since after it a singular system only has rounding errors left to pivot on.
+fn solve_normal_equations(mut gram: Vec<Vec<f64>>, mut rhs: Vec<f64>) -> Option<Vec<f64>> {
+    let n = rhs.len();
+    let scale = (0..n).map(|i| gram[i][i].abs()).fold(0.0, f64::max);
+    for column in 0..n {
+        let pivot = (column..n).max_by(|&a, &b| {
+            gram[a][column].abs().total_cmp(&gram[b][column].abs())
+        })?;
+        if gram[pivot][column].abs() <= 1e-12 * scale {
+            return None;
+        }
+        gram.swap(column, pivot);
+        rhs.swap(column, pivot);
+        let (pivot_rows, rows_below) = gram.split_at_mut(column + 1);
+        let pivot_row = &pivot_rows[column];
+        for (offset, row) in rows_below.iter_mut().enumerate() {
+            let factor = row[column] / pivot_row[column];
+            for (value, pivot_value) in row[column..].iter_mut().zip(&pivot_row[column..]) {
+                *value -= factor * pivot_value;
+            }
+            rhs[column + 1 + offset] -= factor * rhs[column];
+        }
+    }
+
+    let mut coefficients = vec![0.0; n];
+    for row in (0..n).rev() {
+        let known: f64 = (row + 1..n).map(|k| gram[row][k] * coefficients[k]).sum();
+        coefficients[row] = (rhs[row] - known) / gram[row][row];
+    }
+    Some(coefficients)
+}
+
+/// Price an American option with the Longstaff-Schwartz method. The option can be exercised
+/// at the end of each of the `config.steps` steps, so more steps get closer to continuous
+/// exercise. All paths are kept in memory for the regressions, and `config.antithetic` and
+/// `config.bumps` are ignored.
+pub fn price<F: Float>(
+    contract: &OptionContract<F>,
+    market: &MarketData<F>,
+    config: &SimulationConfig,
+    basis: Basis
+) -> Result<AmericanEstimate<F>, PricingError> {
+    contract.validate()?;
+    market.validate()?;
+    config.validate()?;
+    basis.validate()?;
+
+    let steps = config.steps;
+    let num_trials = config.num_trials;
+    let strike = contract.strike.to_f64();
+    let years_to_expiry = contract.years_to_expiry.to_f64();
+    let risk_free_rate = market.risk_free_rate.to_f64();
+    let call_mult = contract.option_type.call_mult();
+    let payoff = |spot: f64| (call_mult * (spot - strike)).max(0.0);
+
+    let paths = simulate_paths::<F::Lanes>(
+        market.spot.to_f64(),
+        market.volatility.to_f64(),
+        risk_free_rate,
+        years_to_expiry,
+        market.carry_yield().to_f64(),
+        steps,
+        num_trials,
+        config.rng_source
+    );
+    let spots_at = |step: usize| &paths[(step - 1) * num_trials..step * num_trials];
+
+    let dt = years_to_expiry / (steps as f64);
+    let step_discount = (-risk_free_rate * dt).exp();
+    let num_basis = basis.degree() + 1;
+
+    // The cash flow of every path, discounted to the exercise date being looked at
+    let mut cash_flows: Vec<f64> = spots_at(steps).iter().map(|&spot| payoff(spot)).collect();
+    let mut exercise_boundary = vec![(F::from_f64(0.0), F::from_f64(f64::NAN)); steps - 1];
+    let mut values = vec![0.0; num_basis];
+
+    for step in (1..steps).rev() {
+        for cash_flow in cash_flows.iter_mut() {
+            *cash_flow *= step_discount;
+        }
+        let spots = spots_at(step);
+
+        // Regress the cash flows of the in-the-money paths on the basis functions, since only
+        // those paths face an exercise decision
+        let mut gram = vec![vec![0.0; num_basis]; num_basis];
+        let mut rhs = vec![0.0; num_basis];
+        let mut num_in_the_money = 0;
+        for (&spot, &cash_flow) in spots.iter().zip(&cash_flows) {
+            if payoff(spot) > 0.0 {
+                basis.evaluate(spot / strike, &mut values);
+                for i in 0..num_basis {
+                    for j in 0..num_basis {
+                        gram[i][j] += values[i] * values[j];
+                    }
+                    rhs[i] += values[i] * cash_flow;
+                }
+                num_in_the_money += 1;
+            }
+        }
+
+        let mut boundary = f64::NAN;
+        let coefficients = if num_in_the_money > num_basis {
+            solve_normal_equations(gram, rhs)
+        } else {
+            None
+        };
+        if let Some(coefficients) = coefficients {
+            for (&spot, cash_flow) in spots.iter().zip(cash_flows.iter_mut()) {
+                let exercise = payoff(spot);
+                if exercise <= 0.0 {
+                    continue;
+                }
+                basis.evaluate(spot / strike, &mut values);
+                let continuation: f64 = values
+                    .iter()
+                    .zip(&coefficients)
+                    .map(|(value, coefficient)| value * coefficient)
+                    .sum();
+                if exercise > continuation {
+                    *cash_flow = exercise;
+                    // The boundary is the exercised spot closest to the money
+                    boundary = match contract.option_type {
+                        OptionType::Call => boundary.min(spot),
+                        OptionType::Put => boundary.max(spot),
+                    };
+                }
+            }
+        }
+        exercise_boundary[step - 1] = (F::from_f64((step as f64) * dt), F::from_f64(boundary));
+    }
+
+    let n = num_trials as f64;
+    let mean = cash_flows.iter().sum::<f64>() / n;
+    let variance = if num_trials > 1 {
+        cash_flows.iter().map(|cash_flow| (cash_flow - mean) * (cash_flow - mean)).sum::<f64>() /
+            (n - 1.0)
+    } else {
+        f64::NAN
+    };
+
+    Ok(AmericanEstimate {
+        price: F::from_f64((mean * step_discount).max(payoff(market.spot.to_f64()))),
+        std_error: F::from_f64((variance / n).sqrt() * step_discount),
+        exercise_boundary,
+        num_paths: num_trials,
+    })
+}
+
+/// Price an American option on a Cox-Ross-Rubinstein binomial tree with `steps` steps, which
+/// converges to the continuous exercise price and is used as a reference for [`price`]
+pub fn binomial_price<F: Float>(
+    contract: &OptionContract<F>,
+    market: &MarketData<F>,
+    steps: usize
+) -> Result<F, PricingError> {
+    contract.validate()?;
+    market.validate()?;
+    if steps < MIN_STEPS {
+        return Err(PricingError::TooFewSteps { steps, min_steps: MIN_STEPS });
+    }
+
+    let spot = market.spot.to_f64();
+    let strike = contract.strike.to_f64();
+    let volatility = market.volatility.to_f64();
+    let risk_free_rate = market.risk_free_rate.to_f64();
+    let dividend_yield = market.carry_yield().to_f64();
+    let call_mult = contract.option_type.call_mult();
+
+    let dt = contract.years_to_expiry.to_f64() / (steps as f64);
+    let up = (volatility * dt.sqrt()).exp();
+    let up_probability = (((risk_free_rate - dividend_yield) * dt).exp() - 1.0 / up) / (up - 1.0 / up);
+    let step_discount = (-risk_free_rate * dt).exp();
+    // The spot after `ups` up moves out of `step` moves
+    let spot_at = |step: usize, ups: usize| spot * up.powi(2 * (ups as i32) - (step as i32));
+
+    let mut values: Vec<f64> = (0..=steps)
+        .map(|ups| (call_mult * (spot_at(steps, ups) - strike)).max(0.0))
+        .collect();
+    for step in (0..steps).rev() {
+        for ups in 0..=step {
+            let continuation =
+                step_discount *
+                (up_probability * values[ups + 1] + (1.0 - up_probability) * values[ups]);
+            let exercise = call_mult * (spot_at(step, ups) - strike);
+            values[ups] = continuation.max(exercise);
+        }
+    }
+    Ok(F::from_f64(values[0]))
+}
+
+#[cfg(test)]
+use crate::bs;
+
+#[test]
+fn binomial_matches_bs_without_early_exercise() {
+    // Without dividends an American call is never exercised early
+    let contract = OptionContract::<f64>::new(OptionType::Call, 110.0, 0.5);
+    let market = MarketData::<f64>::new(100.0, 0.25, 0.05, 0.0);
+    let binomial = binomial_price(&contract, &market, 2000).unwrap();
+    let actual_price = bs::call_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.0);
+    println!("binomial call {} vs {}", binomial, actual_price);
+    assert!((binomial - actual_price).abs() < 0.01);
+}
+
+#[test]
+fn american_put_matches_binomial() {
+    let contract = OptionContract::<f64>::new(OptionType::Put, 110.0, 0.5);
+    let market = MarketData::<f64>::new(100.0, 0.25, 0.05, 0.02);
+    let config = SimulationConfig::builder().steps(50).num_trials(20000).seed(10).build().unwrap();
+
+    let binomial = binomial_price(&contract, &market, 2000).unwrap();
+    let european = bs::put_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    for basis in [Basis::Laguerre(3), Basis::Polynomial(3)] {
+        let estimate = price(&contract, &market, &config, basis).unwrap();
+        println!("american put {:?} {:?} vs {} (european {})", basis, estimate.price, binomial, european);
+        assert_eq!(estimate.num_paths, 20000);
+        assert!(estimate.price > european);
+        // Exercising on 50 dates instead of continuously is worth slightly less
+        assert!((estimate.price - binomial).abs() <= 4.0 * estimate.std_error + 0.05);
+    }
+}
+
+#[test]
+fn american_put_f32() {
+    let contract = OptionContract::<f32>::new(OptionType::Put, 110.0, 0.5);
+    let market = MarketData::<f32>::new(100.0, 0.25, 0.05, 0.02);
+    let config = SimulationConfig::builder().steps(25).num_trials(10000).seed(11).build().unwrap();
+    let estimate = price(&contract, &market, &config, Basis::default()).unwrap();
+    let binomial = binomial_price(&contract, &market, 1000).unwrap();
+    println!("american put f32 {:?} vs {}", estimate.price, binomial);
+    assert!((estimate.price - binomial).abs() <= 4.0 * estimate.std_error + 0.1);
+}
+
+#[test]
+fn american_call_without_dividends_is_european() {
+    let contract = OptionContract::<f64>::new(OptionType::Call, 110.0, 0.5);
+    let market = MarketData::<f64>::new(100.0, 0.25, 0.05, 0.0);
+    let config = SimulationConfig::builder().steps(20).num_trials(20000).seed(12).build().unwrap();
+    let estimate = price(&contract, &market, &config, Basis::default()).unwrap();
+    let actual_price = bs::call_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.0);
+    println!("american call {:?} vs {}", estimate.price, actual_price);
+    assert!((estimate.price - actual_price).abs() <= 4.0 * estimate.std_error + 0.02);
+}
+
+#[test]
+fn exercise_boundary_rises_toward_strike() {
+    let contract = OptionContract::<f64>::new(OptionType::Put, 100.0, 1.0);
+    let market = MarketData::<f64>::new(100.0, 0.3, 0.08, 0.0);
+    let config = SimulationConfig::builder().steps(20).num_trials(10000).seed(13).build().unwrap();
+    let estimate = price(&contract, &market, &config, Basis::default()).unwrap();
+    println!("american put boundary {:?}", estimate.exercise_boundary);
+
+    assert_eq!(estimate.exercise_boundary.len(), 19);
+    let (first_time, _) = estimate.exercise_boundary[0];
+    let (last_time, _) = estimate.exercise_boundary[18];
+    assert!((first_time - 0.05).abs() < 1e-12);
+    assert!((last_time - 0.95).abs() < 1e-12);
+
+    // A 4000 step binomial tree puts the boundary between 74 and 77 over the first half year
+    // and between 83 and 88 over the last four dates. The highest exercised spot lies a little
+    // above it, since exercising on 20 dates only is worth it at higher spots.
+    let mean_boundary = |dates: &[(f64, f64)]| {
+        let spots: Vec<f64> = dates.iter().map(|&(_, spot)| spot).filter(|spot| !spot.is_nan()).collect();
+        spots.iter().sum::<f64>() / (spots.len() as f64)
+    };
+    let early = mean_boundary(&estimate.exercise_boundary[..10]);
+    let late = mean_boundary(&estimate.exercise_boundary[15..]);
+    println!("american put boundary early {} late {}", early, late);
+    assert!(early > 70.0 && early < 82.0);
+    assert!(late > 82.0 && late < 95.0);
+    assert!(late - early > 5.0);
+}
+
+#[test]
+fn singular_normal_equations_are_rejected() {
+    // In-the-money paths at only two spots give a Gram matrix of rank two for four functions,
+    // which elimination leaves with rounding errors rather than zeros to pivot on
+    let mut gram = vec![vec![0.0; 4]; 4];
+    let mut rhs = vec![0.0; 4];
+    let mut values = vec![0.0; 4];
+    for (spot, cash_flow) in [(0.83, 5.0), (0.91, 2.0)] {
+        Basis::Laguerre(3).evaluate(spot, &mut values);
+        for i in 0..4 {
+            for j in 0..4 {
+                gram[i][j] += 500.0 * values[i] * values[j];
+            }
+            rhs[i] += 500.0 * values[i] * cash_flow;
+        }
+    }
+    assert_eq!(solve_normal_equations(gram, rhs), None);
+
+    let gram = vec![vec![4.0, 2.0], vec![2.0, 3.0]];
+    assert_eq!(solve_normal_equations(gram, vec![2.0, 5.0]), Some(vec![-0.5, 2.0]));
+}
+
+#[test]
+fn invalid_basis_is_rejected() {
+    let contract = OptionContract::<f64>::new(OptionType::Put, 110.0, 0.5);
+    let market = MarketData::<f64>::new(100.0, 0.25, 0.05, 0.02);
+    let config = SimulationConfig::new(10, 100);
+    assert_eq!(
+        price(&contract, &market, &config, Basis::Laguerre(0)),
+        Err(PricingError::InvalidBasisDegree { degree: 0, max_degree: MAX_BASIS_DEGREE })
+    );
+    assert_eq!(
+        price(&contract, &market, &config, Basis::Polynomial(MAX_BASIS_DEGREE + 1)).map(|estimate| estimate.price),
+        Err(PricingError::InvalidBasisDegree { degree: MAX_BASIS_DEGREE + 1, max_degree: MAX_BASIS_DEGREE })
+    );
+    assert_eq!(
+        binomial_price(&contract, &market, 0),
+        Err(PricingError::TooFewSteps { steps: 0, min_steps: MIN_STEPS })
+    );
+}
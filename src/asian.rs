@@ -0,0 +1,356 @@
+// Asian options, whose payoff depends on the average spot over a set of averaging dates
+// instead of the spot at expiry. Arithmetic averages have no closed form, so they are priced
+// with the geometric average of the same paths as a control variate, whose price is known.
+
+use crate::bs::normal_cdf;
+use crate::error::PricingError;
+use crate::float::{ Float, SimdFloat };
+use crate::mc_simd::{ self, PathPayoff, PriceEstimate };
+use crate::params::{ MarketData, OptionType, SimulationConfig };
+
+/// How the spots on the averaging dates are averaged
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Averaging {
+    Arithmetic,
+    Geometric,
+}
+
+/// What the average spot is compared with at expiry
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AsianStrike<F: Float = f32> {
+    /// The average is compared with a fixed strike, paying max(average - strike, 0) for a call
+    Fixed(F),
+    /// The average is the strike, paying max(spot at expiry - average, 0) for a call
+    Floating,
+}
+
+/// An Asian option
+#[derive(Clone, Debug, PartialEq)]
+pub struct AsianOption<F: Float = f32> {
+    pub option_type: OptionType,
+    pub averaging: Averaging,
+    pub strike: AsianStrike<F>,
+    pub years_to_expiry: F,
+    /// Years from now of the averaging dates, each moved to the closest step of the simulation.
+    /// Every step is an averaging date if empty.
+    pub averaging_dates: Vec<F>,
+}
+
+impl<F: Float> AsianOption<F> {
+    /// An option averaging over every step of the simulation
+    pub fn new(
+        option_type: OptionType,
+        averaging: Averaging,
+        strike: AsianStrike<F>,
+        years_to_expiry: F
+    ) -> AsianOption<F> {
+        AsianOption { option_type, averaging, strike, years_to_expiry, averaging_dates: Vec::new() }
+    }
+
+    /// The same option, averaging over the given dates in years from now
+    pub fn with_averaging_dates(self, averaging_dates: Vec<F>) -> AsianOption<F> {
+        AsianOption { averaging_dates, ..self }
+    }
+
+    pub fn validate(&self) -> Result<(), PricingError> {
+        if let AsianStrike::Fixed(strike) = self.strike {
+            if !(strike >= F::from_f64(0.0) && strike.is_finite()) {
+                return Err(PricingError::InvalidStrike(strike.to_f64()));
+            }
+        }
+        if !(self.years_to_expiry > F::from_f64(0.0) && self.years_to_expiry.is_finite()) {
+            return Err(PricingError::InvalidYearsToExpiry(self.years_to_expiry.to_f64()));
+        }
+        for &date in &self.averaging_dates {
+            if !(date > F::from_f64(0.0) && date <= self.years_to_expiry) {
+                return Err(PricingError::InvalidAveragingDate(date.to_f64()));
+            }
+        }
+        Ok(())
+    }
+
+    // How many averaging dates fall on each step of a simulation with `steps` steps
+    fn date_counts(&self, steps: usize) -> Vec<f64> {
+        if self.averaging_dates.is_empty() {
+            return vec![1.0; steps];
+        }
+        let dt = self.years_to_expiry.to_f64() / (steps as f64);
+        let mut counts = vec![0.0; steps];
+        for date in &self.averaging_dates {
+            let step = ((date.to_f64() / dt).round() as usize).clamp(1, steps);
+            counts[step - 1] += 1.0;
+        }
+        counts
+    }
+}
+
+// The payoff of an Asian option on V::LANES paths. The state is the sum of the spots, or of
+// their logarithms for a geometric average, weighted by the number of averaging dates on each
+// step, together with the last spot for a floating strike.
+struct AsianPayoff<V> {
+    call_mult: V,
+    strike: Option<V>,
+    geometric: bool,
+    date_counts: Vec<f64>,
+    inverse_num_dates: V,
+}
+
+impl<V: SimdFloat> AsianPayoff<V> {
+    fn new<F: Float>(option: &AsianOption<F>, averaging: Averaging, steps: usize) -> AsianPayoff<V> {
+        let date_counts = option.date_counts(steps);
+        let num_dates: f64 = date_counts.iter().sum();
+        AsianPayoff {
+            call_mult: V::splat(option.option_type.call_mult()),
+            strike: match option.strike {
+                AsianStrike::Fixed(strike) => Some(V::splat(strike.to_f64())),
+                AsianStrike::Floating => None,
+            },
+            geometric: averaging == Averaging::Geometric,
+            date_counts,
+            inverse_num_dates: V::splat(1.0 / num_dates),
+        }
+    }
+}
+
+impl<V: SimdFloat> PathPayoff<V> for AsianPayoff<V> {
+    type State = (V, V);
+
+    fn start(&self) -> (V, V) {
+        (V::splat(0.0), V::splat(0.0))
+    }
+
+    fn observe(&self, (sum, last_spot): &mut (V, V), step: usize, spot: V) {
+        let count = self.date_counts[step];
+        if count > 0.0 {
+            let value = if self.geometric { spot.ln() } else { spot };
+            *sum = V::mul_add(value, V::splat(count), *sum);
+        }
+        *last_spot = spot;
+    }
+
+    fn payoff(&self, (sum, last_spot): (V, V)) -> V {
+        let average = if self.geometric {
+            (sum * self.inverse_num_dates).exp()
+        } else {
+            sum * self.inverse_num_dates
+        };
+        let intrinsic = match self.strike {
+            Some(strike) => average - strike,
+            None => last_spot - average,
+        };
+        V::fast_max(self.call_mult * intrinsic, V::splat(0.0))
+    }
+}
+
+// Undiscounted price of an option paying max(call_mult * (X - Y), 0), where log X - log Y is
+// normal with variance `variance` and X and Y have expectations expected_x and expected_y
+fn lognormal_exchange(expected_x: f64, expected_y: f64, variance: f64, call_mult: f64) -> f64 {
+    if variance <= 0.0 {
+        return (call_mult * (expected_x - expected_y)).max(0.0);
+    }
+    let std_dev = variance.sqrt();
+    let d1 = ((expected_x / expected_y).ln() + 0.5 * variance) / std_dev;
+    let d2 = d1 - std_dev;
+    call_mult *
+        (expected_x * normal_cdf(call_mult * d1) - expected_y * normal_cdf(call_mult * d2))
+}
+
+/// Closed-form price of the geometric average version of `option`, with the averaging dates
+/// moved to the closest of `steps` steps as in [`price`]. The logarithm of the geometric
+/// average is normal, which makes fixed and floating strikes a Black-Scholes style formula.
+pub fn geometric_price<F: Float>(
+    option: &AsianOption<F>,
+    market: &MarketData<F>,
+    steps: usize
+) -> Result<F, PricingError> {
+    option.validate()?;
+    market.validate()?;
+    SimulationConfig::new(steps, 1).validate()?;
+
+    let spot = market.spot.to_f64();
+    let volatility = market.volatility.to_f64();
+    let risk_free_rate = market.risk_free_rate.to_f64();
+    let dividend_yield = market.carry_yield().to_f64();
+    let years_to_expiry = option.years_to_expiry.to_f64();
+    let call_mult = option.option_type.call_mult();
+
+    // Mean and variance of the log of the geometric average, from the times of the dates
+    let dt = years_to_expiry / (steps as f64);
+    let date_counts = option.date_counts(steps);
+    let num_dates: f64 = date_counts.iter().sum();
+    let mean_time = date_counts
+        .iter()
+        .enumerate()
+        .map(|(step, count)| count * ((step + 1) as f64) * dt)
+        .sum::<f64>() / num_dates;
+    // The covariance of the log spots on two dates is the variance on the earlier one, so the
+    // sum over all pairs of dates weighs every date by the number of dates on or after it
+    let mut dates_after = num_dates;
+    let mut sum_min_times = 0.0;
+    for (step, count) in date_counts.iter().enumerate() {
+        sum_min_times += count * (2.0 * dates_after - count) * ((step + 1) as f64) * dt;
+        dates_after -= count;
+    }
+    let variance_per_year = volatility * volatility;
+    let log_mean = spot.ln() + (risk_free_rate - dividend_yield - 0.5 * variance_per_year) * mean_time;
+    let log_variance = variance_per_year * sum_min_times / (num_dates * num_dates);
+    let expected_average = (log_mean + 0.5 * log_variance).exp();
+
+    let price = match option.strike {
+        AsianStrike::Fixed(strike) =>
+            lognormal_exchange(expected_average, strike.to_f64(), log_variance, call_mult),
+        AsianStrike::Floating => {
+            let expected_spot = spot * ((risk_free_rate - dividend_yield) * years_to_expiry).exp();
+            let variance =
+                variance_per_year * years_to_expiry + log_variance -
+                2.0 * variance_per_year * mean_time;
+            lognormal_exchange(expected_spot, expected_average, variance, call_mult)
+        }
+    };
+    Ok(F::from_f64((-risk_free_rate * years_to_expiry).exp() * price))
+}
+
+/// Price an Asian option, with the standard error and confidence interval of the price. The
+/// steps of the simulation are the dates the averaging dates are moved to. Arithmetic averages
+/// use the geometric average of the same paths as a control variate.
+pub fn price<F: Float>(
+    option: &AsianOption<F>,
+    market: &MarketData<F>,
+    config: &SimulationConfig
+) -> Result<PriceEstimate<F>, PricingError> {
+    option.validate()?;
+    config.validate()?;
+
+    let payoff = AsianPayoff::<F::Lanes>::new(option, option.averaging, config.steps);
+    match option.averaging {
+        Averaging::Geometric => mc_simd::price_path(market, option.years_to_expiry, config, &payoff),
+        Averaging::Arithmetic => {
+            let control = AsianPayoff::<F::Lanes>::new(option, Averaging::Geometric, config.steps);
+            let control_price = geometric_price(option, market, config.steps)?;
+            mc_simd::price_path_with_control(
+                market,
+                option.years_to_expiry,
+                config,
+                &payoff,
+                &control,
+                control_price
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+use crate::bs;
+
+#[test]
+fn geometric_price_matches_reference_values() {
+    // Kemna and Vorst's continuous geometric average call, approximated by 10000 dates
+    let market = MarketData::<f64>::new(100.0, 0.2, 0.05, 0.0);
+    let option = AsianOption::new(OptionType::Call, Averaging::Geometric, AsianStrike::Fixed(100.0), 1.0);
+    let adjusted_volatility = 0.2 / 3.0f64.sqrt();
+    let adjusted_dividend = 0.5 * (0.05 + 0.2 * 0.2 / 6.0);
+    let actual_price =
+        bs::call_price(100.0, 100.0, adjusted_volatility, 0.05, 1.0, adjusted_dividend);
+    let price = geometric_price(&option, &market, 10000).unwrap();
+    println!("geometric asian call {} vs {}", price, actual_price);
+    assert!((price - actual_price).abs() < 0.005);
+
+    // With a single averaging date at expiry a fixed strike option is European
+    let european = option.clone().with_averaging_dates(vec![1.0]);
+    let price = geometric_price(&european, &market, 50).unwrap();
+    let actual_price = bs::call_price(100.0, 100.0, 0.2, 0.05, 1.0, 0.0);
+    println!("single date asian call {} vs {}", price, actual_price);
+    assert!((price - actual_price).abs() < 1e-10);
+}
+
+#[test]
+fn geometric_mc_matches_closed_form() {
+    let market = MarketData::<f64>::new(100.0, 0.3, 0.05, 0.02);
+    let config = SimulationConfig::builder().steps(12).num_trials(40000).seed(30).build().unwrap();
+    for (option_type, strike) in [
+        (OptionType::Call, AsianStrike::Fixed(95.0)),
+        (OptionType::Put, AsianStrike::Fixed(105.0)),
+        (OptionType::Call, AsianStrike::Floating),
+        (OptionType::Put, AsianStrike::Floating),
+    ] {
+        let option = AsianOption::new(option_type, Averaging::Geometric, strike, 1.0);
+        let estimate = price(&option, &market, &config).unwrap();
+        let actual_price = geometric_price(&option, &market, 12).unwrap();
+        println!("geometric {:?} {:?} {:?} vs {}", option_type, strike, estimate, actual_price);
+        assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+    }
+}
+
+#[test]
+fn arithmetic_control_variate_reduces_std_error() {
+    let market = MarketData::<f64>::new(100.0, 0.3, 0.05, 0.02);
+    let config = SimulationConfig::builder().steps(12).num_trials(20000).seed(31).build().unwrap();
+    for strike in [AsianStrike::Fixed(100.0), AsianStrike::Floating] {
+        let option = AsianOption::new(OptionType::Call, Averaging::Arithmetic, strike, 1.0);
+        let estimate = price(&option, &market, &config).unwrap();
+        let plain_estimate = mc_simd::price_path(
+            &market,
+            1.0,
+            &config,
+            &AsianPayoff::new(&option, Averaging::Arithmetic, 12)
+        ).unwrap();
+        println!("arithmetic {:?} {:?} vs without control {:?}", strike, estimate, plain_estimate);
+        assert!(estimate.std_error < 0.2 * plain_estimate.std_error);
+        assert!((estimate.price - plain_estimate.price).abs() < 4.0 * plain_estimate.std_error);
+        // The arithmetic average is never below the geometric average
+        let geometric = geometric_price(
+            &AsianOption { averaging: Averaging::Geometric, ..option.clone() },
+            &market,
+            12
+        ).unwrap();
+        if strike == AsianStrike::Fixed(100.0) {
+            assert!(estimate.price > geometric);
+        } else {
+            assert!(estimate.price < geometric);
+        }
+    }
+}
+
+#[test]
+fn arithmetic_call_matches_reference_value() {
+    // Monthly averaging, compared with a reference simulation of 1 million paths
+    let market = MarketData::<f32>::new(100.0, 0.2, 0.05, 0.0);
+    let option = AsianOption::new(OptionType::Call, Averaging::Arithmetic, AsianStrike::Fixed(100.0), 1.0);
+    let config = SimulationConfig::builder().steps(12).num_trials(20000).seed(32).build().unwrap();
+    let estimate = price(&option, &market, &config).unwrap();
+    let actual_price = 6.1562;
+    println!("arithmetic asian call {:?} vs {}", estimate, actual_price);
+    assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error + 0.002);
+}
+
+#[test]
+fn averaging_dates_are_moved_to_steps() {
+    let market = MarketData::<f64>::new(100.0, 0.3, 0.05, 0.02);
+    let option = AsianOption::new(OptionType::Call, Averaging::Geometric, AsianStrike::Fixed(100.0), 1.0);
+    // Quarterly dates fall on every third of 12 steps
+    let quarterly = option.clone().with_averaging_dates(vec![0.25, 0.5, 0.75, 1.0]);
+    assert_eq!(quarterly.date_counts(12), vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0]);
+    assert_eq!(
+        geometric_price(&quarterly, &market, 12).unwrap(),
+        geometric_price(&option, &market, 4).unwrap()
+    );
+
+    let config = SimulationConfig::builder().steps(12).num_trials(20000).seed(33).build().unwrap();
+    let estimate = price(&quarterly, &market, &config).unwrap();
+    let actual_price = geometric_price(&quarterly, &market, 12).unwrap();
+    println!("quarterly geometric asian call {:?} vs {}", estimate, actual_price);
+    assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+
+    assert_eq!(
+        option.clone().with_averaging_dates(vec![0.5, 1.5]).validate(),
+        Err(PricingError::InvalidAveragingDate(1.5))
+    );
+    assert_eq!(
+        price(&option.clone().with_averaging_dates(vec![0.0]), &market, &config),
+        Err(PricingError::InvalidAveragingDate(0.0))
+    );
+    assert_eq!(
+        price(&option.with_averaging_dates(vec![0.5]), &market, &SimulationConfig::new(0, 100)),
+        Err(PricingError::TooFewSteps { steps: 0, min_steps: 1 })
+    );
+}
@@ -0,0 +1,201 @@
+// The Bachelier model, in which the forward to expiry follows an arithmetic Brownian motion
+// with a normal volatility in units of the underlying. The underlying can go negative, as
+// spreads and rates do, so the spot and the strike may be zero or negative, and the market is
+// checked here instead of by MarketData::validate, which needs a positive spot.
+
+use crate::bs;
+use crate::error::PricingError;
+use crate::float::{ Float, SimdFloat };
+use crate::mc_simd::{ self, PathPayoff, PriceEstimate, SpotModel, Terminal, Vanilla };
+use crate::params::{ MarketData, OptionContract, SimulationConfig };
+
+// Checks the market, with the volatility being the normal volatility and the spot any finite
+// number
+fn validate_market<F: Float>(market: &MarketData<F>) -> Result<(), PricingError> {
+    if !market.spot.is_finite() {
+        return Err(PricingError::InvalidSpot(market.spot.to_f64()));
+    }
+    if !(market.volatility > F::from_f64(0.0) && market.volatility.is_finite()) {
+        return Err(PricingError::InvalidVolatility(market.volatility.to_f64()));
+    }
+    if !market.risk_free_rate.is_finite() {
+        return Err(PricingError::InvalidRiskFreeRate(market.risk_free_rate.to_f64()));
+    }
+    if !market.dividend_yield.is_finite() {
+        return Err(PricingError::InvalidDividendYield(market.dividend_yield.to_f64()));
+    }
+    Ok(())
+}
+
+// Checks the contract, whose strike may be any finite number
+fn validate_contract<F: Float>(contract: &OptionContract<F>) -> Result<(), PricingError> {
+    if !contract.strike.is_finite() {
+        return Err(PricingError::InvalidStrike(contract.strike.to_f64()));
+    }
+    if !(contract.years_to_expiry > F::from_f64(0.0) && contract.years_to_expiry.is_finite()) {
+        return Err(PricingError::InvalidYearsToExpiry(contract.years_to_expiry.to_f64()));
+    }
+    Ok(())
+}
+
+// The constants of a step of the Bachelier model, splatted to V once for all the bundles. The
+// state of every path is the forward to expiry, which is stepped exactly.
+struct BachelierSteps<V> {
+    forward: V,
+    // sigma sqrt(dt), the standard deviation of the move of the forward over a step
+    sidt: V,
+    // exp(-(r - q)(T - t)) after every step, which turns the forward into the spot
+    spot_factors: Vec<V>,
+}
+
+impl<V: SimdFloat> SpotModel<V> for BachelierSteps<V> {
+    type State = V;
+
+    fn draws_per_step(&self) -> (usize, usize) {
+        (1, 0)
+    }
+
+    fn start(&self) -> V {
+        self.forward
+    }
+
+    fn step(&self, forward: &mut V, step: usize, normals: &[V], _uniforms: &[V]) -> V {
+        *forward = V::mul_add(normals[0], self.sidt, *forward);
+        *forward * self.spot_factors[step]
+    }
+}
+
+/// Price a path-dependent option expiring in `years_to_expiry` years in the Bachelier model,
+/// with the standard error and confidence interval of the price. `market.volatility` is the
+/// normal volatility of the forward, and the spot may be zero or negative. The payoff is shown
+/// the spot after each of the `config.steps` steps, the forward discounted by the carry to
+/// expiry, as in [`mc_simd::price_path`].
+pub fn price_path<F: Float, P: PathPayoff<F::Lanes>>(
+    market: &MarketData<F>,
+    years_to_expiry: F,
+    config: &SimulationConfig,
+    payoff: &P
+) -> Result<PriceEstimate<F>, PricingError> {
+    if !(years_to_expiry > F::from_f64(0.0) && years_to_expiry.is_finite()) {
+        return Err(PricingError::InvalidYearsToExpiry(years_to_expiry.to_f64()));
+    }
+    validate_market(market)?;
+    config.validate()?;
+
+    let risk_free_rate = market.risk_free_rate.to_f64();
+    let carry = risk_free_rate - market.carry_yield().to_f64();
+    let years_to_expiry = years_to_expiry.to_f64();
+    let dt = years_to_expiry / (config.steps as f64);
+    let steps = BachelierSteps {
+        forward: F::Lanes::splat(market.spot.to_f64() * (carry * years_to_expiry).exp()),
+        sidt: F::Lanes::splat(market.volatility.to_f64() * dt.sqrt()),
+        spot_factors: (1..=config.steps)
+            .map(|step| F::Lanes::splat((-carry * (years_to_expiry - (step as f64) * dt)).exp()))
+            .collect(),
+    };
+    Ok(mc_simd::monte_carlo_model_pricing(
+        &steps,
+        risk_free_rate,
+        years_to_expiry,
+        config,
+        payoff
+    ))
+}
+
+/// Price a European option in the Bachelier model, with the standard error and confidence
+/// interval of the price. The strike may be zero or negative.
+pub fn price<F: Float>(
+    contract: &OptionContract<F>,
+    market: &MarketData<F>,
+    config: &SimulationConfig
+) -> Result<PriceEstimate<F>, PricingError> {
+    validate_contract(contract)?;
+
+    let payoff = Vanilla::<F::Lanes>::new(contract.strike.to_f64(), contract.option_type.call_mult());
+    price_path(market, contract.years_to_expiry, config, &Terminal(payoff))
+}
+
+/// Price a European option in the Bachelier model in closed form with [`bs::bachelier_price`]
+pub fn analytic_price<F: Float>(contract: &OptionContract<F>, market: &MarketData<F>) -> Result<F, PricingError> {
+    validate_contract(contract)?;
+    validate_market(market)?;
+
+    Ok(bs::bachelier_price(
+        contract.option_type,
+        market.spot,
+        contract.strike,
+        market.volatility,
+        market.risk_free_rate,
+        contract.years_to_expiry,
+        market.carry_yield()
+    ))
+}
+
+#[cfg(test)]
+use wide::f64x4;
+#[cfg(test)]
+use crate::params::OptionType;
+
+#[test]
+fn bachelier_matches_analytic_price() {
+    // A spread around zero, with strikes on both sides of it. The steps of an arithmetic
+    // Brownian motion are exact, so a single step is as good as many.
+    let market = MarketData::new(-0.5, 2.0, 0.04, 0.01);
+    for steps in [1, 50] {
+        let config = SimulationConfig::builder().steps(steps).num_trials(100000).seed(141).antithetic(true).build().unwrap();
+        for (option_type, strike) in [(OptionType::Put, -2.0), (OptionType::Call, 1.5)] {
+            let contract = OptionContract::new(option_type, strike, 1.0);
+            let estimate = price(&contract, &market, &config).unwrap();
+            let actual_price = analytic_price(&contract, &market).unwrap();
+            println!("bachelier {} steps {:?} {} {:?} vs {}", steps, option_type, strike, estimate, actual_price);
+            assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+        }
+    }
+}
+
+#[test]
+fn f32_bachelier_futures() {
+    // A futures has no drift, so the price is the Bachelier price with a yield of the rate
+    let market = MarketData::futures(0.02f32, 0.01, 0.03);
+    let config = SimulationConfig::builder().steps(10).num_trials(100000).seed(142).build().unwrap();
+    let contract = OptionContract::new(OptionType::Put, 0.015f32, 2.0);
+    let estimate = price(&contract, &market, &config).unwrap();
+    let actual_price = bs::bachelier_put_price(0.02f32, 0.015, 0.01, 0.03, 2.0, 0.03);
+    println!("f32 bachelier futures {:?} vs {}", estimate, actual_price);
+    assert_eq!(analytic_price(&contract, &market), Ok(actual_price));
+    assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+}
+
+#[test]
+fn bachelier_paths_go_negative() {
+    // The mean spot at expiry is the forward, even though many paths end below zero
+    let market = MarketData::new(1.0, 1.5, 0.05, 0.02);
+    let config = SimulationConfig::builder().steps(20).num_trials(100000).seed(143).build().unwrap();
+    let estimate = price_path(&market, 2.0, &config, &Terminal(|spot: f64x4| spot)).unwrap();
+    let forward_value = (-0.02f64 * 2.0).exp();
+    assert!((estimate.price - forward_value).abs() < 4.0 * estimate.std_error);
+    let below_zero = price_path(&market, 2.0, &config, &Terminal(|spot: f64x4| {
+        spot.cmp_lt(f64x4::splat(0.0)).blend(f64x4::splat(1.0), f64x4::splat(0.0))
+    })).unwrap();
+    assert!(below_zero.price > 0.2);
+}
+
+#[test]
+fn invalid_bachelier_parameters() {
+    let contract = OptionContract::new(OptionType::Call, -1.0, 1.0);
+    let config = SimulationConfig::new(10, 100);
+    let cases = [
+        (MarketData::new(f64::INFINITY, 0.5, 0.05, 0.0), PricingError::InvalidSpot(f64::INFINITY)),
+        (MarketData::new(-1.0, 0.0, 0.05, 0.0), PricingError::InvalidVolatility(0.0)),
+        (MarketData::new(-1.0, 0.5, f64::INFINITY, 0.0), PricingError::InvalidRiskFreeRate(f64::INFINITY)),
+    ];
+    for (market, error) in cases {
+        assert_eq!(price(&contract, &market, &config), Err(error));
+        assert_eq!(analytic_price(&contract, &market), Err(error));
+    }
+    let market = MarketData::new(-1.0, 0.5, 0.05, 0.0);
+    assert_eq!(
+        price(&OptionContract::new(OptionType::Put, -1.0, 0.0), &market, &config),
+        Err(PricingError::InvalidYearsToExpiry(0.0))
+    );
+}
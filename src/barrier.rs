@@ -0,0 +1,300 @@
+// Barrier options, which are knocked in or out when the spot hits a barrier. The spot is only
+// simulated at the end of every step, so a continuously monitored barrier also has to catch
+// the paths that cross it and come back within a step. Instead of drawing whether they did,
+// every path is weighted by its Brownian bridge probability of never having hit the barrier.
+
+use crate::bs;
+use crate::error::PricingError;
+use crate::float::{ Float, SimdFloat };
+use crate::mc_simd::{ self, PathPayoff, PriceEstimate };
+use crate::params::{
+    BarrierType,
+    MarketData,
+    Monitoring,
+    OptionContract,
+    OptionType,
+    SimulationConfig,
+};
+
+/// A barrier option
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BarrierOption<F: Float = f32> {
+    pub option_type: OptionType,
+    pub barrier_type: BarrierType,
+    pub strike: F,
+    pub barrier: F,
+    /// Paid at the end of the step the barrier is hit in for knock-out options, and at expiry
+    /// if the barrier is never hit for knock-in options
+    pub rebate: F,
+    pub years_to_expiry: F,
+    pub monitoring: Monitoring,
+}
+
+impl<F: Float> BarrierOption<F> {
+    pub fn validate(&self) -> Result<(), PricingError> {
+        OptionContract::new(self.option_type, self.strike, self.years_to_expiry).validate()?;
+        if !(self.barrier > F::from_f64(0.0) && self.barrier.is_finite()) {
+            return Err(PricingError::InvalidBarrier(self.barrier.to_f64()));
+        }
+        if !(self.rebate >= F::from_f64(0.0) && self.rebate.is_finite()) {
+            return Err(PricingError::InvalidRebate(self.rebate.to_f64()));
+        }
+        Ok(())
+    }
+}
+
+// The payoff of a barrier option on V::LANES paths. The state is the previous spot and its log
+// distance from the barrier, the probability that the path has not hit the barrier yet, and
+// for knock-out options the value of the rebate so far, grown to expiry.
+struct BarrierPayoff<V> {
+    call_mult: V,
+    strike: V,
+    rebate: V,
+    knock_in: bool,
+    continuous: bool,
+    spot: V,
+    // Sign of the log distance of the barrier from the spot, so that it is positive until hit
+    direction: V,
+    log_barrier: V,
+    // -2 / (volatility^2 dt), for the Brownian bridge probability of hitting the barrier
+    bridge_mult: V,
+    // Growth from the end of every step to expiry, for rebates paid when the barrier is hit
+    rebate_growth: Vec<f64>,
+}
+
+impl<V: SimdFloat> PathPayoff<V> for BarrierPayoff<V> {
+    type State = (V, V, V, V);
+
+    fn start(&self) -> (V, V, V, V) {
+        let distance = self.direction * (self.spot.ln() - self.log_barrier);
+        (self.spot, distance, V::splat(1.0), V::splat(0.0))
+    }
+
+    fn observe(
+        &self,
+        (last_spot, last_distance, survival, rebate_value): &mut (V, V, V, V),
+        step: usize,
+        spot: V
+    ) {
+        let zeros = V::splat(0.0);
+        let distance = self.direction * (spot.ln() - self.log_barrier);
+        let stays = if self.continuous {
+            // A Brownian bridge between two points on the same side of the barrier hits it
+            // with probability exp(-2 * distance_1 * distance_2 / (volatility^2 dt))
+            let exponent =
+                self.bridge_mult * V::fast_max(*last_distance, zeros) * V::fast_max(distance, zeros);
+            V::splat(1.0) - exponent.exp()
+        } else {
+            zeros.cmp_lt(distance).blend(V::splat(1.0), zeros)
+        };
+
+        if !self.knock_in {
+            let knocked_out = *survival - *survival * stays;
+            let rebate = self.rebate * V::splat(self.rebate_growth[step]);
+            *rebate_value = V::mul_add(knocked_out, rebate, *rebate_value);
+        }
+        *survival = *survival * stays;
+        *last_spot = spot;
+        *last_distance = distance;
+    }
+
+    fn payoff(&self, (last_spot, _, survival, rebate_value): (V, V, V, V)) -> V {
+        let vanilla = V::fast_max(self.call_mult * (last_spot - self.strike), V::splat(0.0));
+        if self.knock_in {
+            V::mul_add(vanilla - self.rebate, V::splat(1.0) - survival, self.rebate)
+        } else {
+            V::mul_add(vanilla, survival, rebate_value)
+        }
+    }
+}
+
+/// Price a barrier option, with the standard error and confidence interval of the price. A
+/// continuous barrier is priced with the Brownian bridge probability of hitting it between
+/// steps, so it does not need many steps, while a discrete barrier is checked at the end of
+/// each of the `config.steps` steps.
+pub fn price<F: Float>(
+    option: &BarrierOption<F>,
+    market: &MarketData<F>,
+    config: &SimulationConfig
+) -> Result<PriceEstimate<F>, PricingError> {
+    option.validate()?;
+    market.validate()?;
+    config.validate()?;
+
+    let up = option.barrier_type.is_up();
+    let hit = if up { market.spot >= option.barrier } else { market.spot <= option.barrier };
+    if hit {
+        // The barrier has already been hit, so this is a vanilla option or a paid rebate
+        if option.barrier_type.is_knock_in() {
+            let contract =
+                OptionContract::new(option.option_type, option.strike, option.years_to_expiry);
+            return mc_simd::price(&contract, market, config);
+        }
+        let rebate = option.rebate;
+        return Ok(PriceEstimate {
+            price: rebate,
+            std_error: F::from_f64(0.0),
+            confidence_interval: (rebate, rebate),
+            num_paths: 0,
+        });
+    }
+
+    let volatility = market.volatility.to_f64();
+    let risk_free_rate = market.risk_free_rate.to_f64();
+    let years_to_expiry = option.years_to_expiry.to_f64();
+    let dt = years_to_expiry / (config.steps as f64);
+    let payoff = BarrierPayoff {
+        call_mult: F::Lanes::splat(option.option_type.call_mult()),
+        strike: F::Lanes::splat(option.strike.to_f64()),
+        rebate: F::Lanes::splat(option.rebate.to_f64()),
+        knock_in: option.barrier_type.is_knock_in(),
+        continuous: option.monitoring == Monitoring::Continuous,
+        spot: F::Lanes::splat(market.spot.to_f64()),
+        direction: F::Lanes::splat(if up { -1.0 } else { 1.0 }),
+        log_barrier: F::Lanes::splat(option.barrier.to_f64().ln()),
+        bridge_mult: F::Lanes::splat(-2.0 / (volatility * volatility * dt)),
+        rebate_growth: (1..=config.steps)
+            .map(|step| (risk_free_rate * (years_to_expiry - (step as f64) * dt)).exp())
+            .collect(),
+    };
+    mc_simd::price_path(market, option.years_to_expiry, config, &payoff)
+}
+
+/// Price a barrier option in closed form with [`bs::barrier_price`]. A discrete barrier is
+/// monitored every `years_to_expiry / steps` years, and priced as a continuous one with the
+/// barrier shifted by [`bs::discrete_barrier_shift`].
+pub fn analytic_price<F: Float>(
+    option: &BarrierOption<F>,
+    market: &MarketData<F>,
+    steps: usize
+) -> Result<F, PricingError> {
+    option.validate()?;
+    market.validate()?;
+    SimulationConfig::new(steps, 1).validate()?;
+
+    let barrier = match option.monitoring {
+        Monitoring::Continuous => option.barrier,
+        Monitoring::Discrete => {
+            let monitoring_interval = option.years_to_expiry.to_f64() / (steps as f64);
+            bs::discrete_barrier_shift(
+                option.barrier_type,
+                option.barrier,
+                market.volatility,
+                F::from_f64(monitoring_interval)
+            )
+        }
+    };
+    Ok(bs::barrier_price(
+        option.option_type,
+        option.barrier_type,
+        market.spot,
+        option.strike,
+        barrier,
+        option.rebate,
+        market.volatility,
+        market.risk_free_rate,
+        option.years_to_expiry,
+        market.carry_yield()
+    ))
+}
+
+#[cfg(test)]
+fn test_option(
+    option_type: OptionType,
+    barrier_type: BarrierType,
+    strike: f64,
+    barrier: f64
+) -> BarrierOption<f64> {
+    BarrierOption {
+        option_type,
+        barrier_type,
+        strike,
+        barrier,
+        rebate: 3.0,
+        years_to_expiry: 0.5,
+        monitoring: Monitoring::Continuous,
+    }
+}
+
+#[test]
+fn continuous_barrier_matches_reiner_rubinstein() {
+    let market = MarketData::<f64>::new(100.0, 0.25, 0.08, 0.04);
+    // Few steps, which the Brownian bridge makes up for
+    let config = SimulationConfig::builder().steps(10).num_trials(40000).seed(40).build().unwrap();
+    for option in [
+        test_option(OptionType::Call, BarrierType::DownAndOut, 90.0, 95.0),
+        test_option(OptionType::Call, BarrierType::DownAndIn, 100.0, 95.0),
+        test_option(OptionType::Call, BarrierType::UpAndOut, 90.0, 105.0),
+        test_option(OptionType::Put, BarrierType::UpAndIn, 100.0, 105.0),
+        test_option(OptionType::Put, BarrierType::DownAndOut, 100.0, 95.0),
+    ] {
+        let estimate = price(&option, &market, &config).unwrap();
+        let actual_price = analytic_price(&option, &market, 10).unwrap();
+        println!("{:?} {:?} {:?} vs {}", option.option_type, option.barrier_type, estimate, actual_price);
+        // Rebates paid at the end of the step they are due in are worth slightly less
+        assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error + 0.01);
+    }
+}
+
+#[test]
+fn discrete_barrier_matches_shifted_barrier() {
+    let market = MarketData::<f64>::new(100.0, 0.25, 0.08, 0.04);
+    let config = SimulationConfig::builder().steps(20).num_trials(40000).seed(41).build().unwrap();
+    for option in [
+        test_option(OptionType::Call, BarrierType::DownAndOut, 100.0, 95.0),
+        test_option(OptionType::Call, BarrierType::UpAndIn, 100.0, 110.0),
+    ] {
+        let option = BarrierOption { monitoring: Monitoring::Discrete, rebate: 0.0, ..option };
+        let estimate = price(&option, &market, &config).unwrap();
+        let actual_price = analytic_price(&option, &market, 20).unwrap();
+        let continuous_price =
+            analytic_price(&BarrierOption { monitoring: Monitoring::Continuous, ..option }, &market, 20).unwrap();
+        println!("discrete {:?} {:?} vs {} (continuous {})", option.barrier_type, estimate, actual_price, continuous_price);
+        assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error + 0.02);
+    }
+}
+
+#[test]
+fn f32_barrier_matches_reiner_rubinstein() {
+    let market = MarketData::<f32>::new(100.0, 0.25, 0.08, 0.04);
+    let option = BarrierOption {
+        option_type: OptionType::Call,
+        barrier_type: BarrierType::DownAndOut,
+        strike: 100.0,
+        barrier: 95.0,
+        rebate: 0.0,
+        years_to_expiry: 0.5,
+        monitoring: Monitoring::Continuous,
+    };
+    let config = SimulationConfig::builder().steps(10).num_trials(40000).seed(42).build().unwrap();
+    let estimate = price(&option, &market, &config).unwrap();
+    let actual_price = analytic_price(&option, &market, 10).unwrap();
+    println!("f32 down and out call {:?} vs {}", estimate, actual_price);
+    assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+}
+
+#[test]
+fn hit_barrier_and_invalid_parameters() {
+    let market = MarketData::<f64>::new(100.0, 0.25, 0.08, 0.04);
+    let config = SimulationConfig::builder().steps(10).num_trials(1000).seed(43).build().unwrap();
+
+    let knocked_out = test_option(OptionType::Call, BarrierType::DownAndOut, 90.0, 100.0);
+    assert_eq!(price(&knocked_out, &market, &config).unwrap().price, 3.0);
+    assert_eq!(analytic_price(&knocked_out, &market, 10).unwrap(), 3.0);
+    assert_eq!(
+        price(&knocked_out, &market, &SimulationConfig::new(0, 0)),
+        Err(PricingError::TooFewSteps { steps: 0, min_steps: 1 })
+    );
+
+    let knocked_in = test_option(OptionType::Call, BarrierType::UpAndIn, 90.0, 100.0);
+    let contract = OptionContract::new(OptionType::Call, 90.0, 0.5);
+    assert_eq!(price(&knocked_in, &market, &config), mc_simd::price(&contract, &market, &config));
+
+    assert_eq!(
+        price(&test_option(OptionType::Call, BarrierType::DownAndOut, 90.0, -95.0), &market, &config),
+        Err(PricingError::InvalidBarrier(-95.0))
+    );
+    let negative_rebate = BarrierOption { rebate: -1.0, ..knocked_out };
+    assert_eq!(price(&negative_rebate, &market, &config), Err(PricingError::InvalidRebate(-1.0)));
+}
+
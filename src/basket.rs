@@ -0,0 +1,480 @@
+// Basket and spread options on several correlated underlyings. A weighted sum of lognormal
+// spots is not lognormal, so baskets have no closed form. Levy's approximation matches the
+// first two moments of the basket with a lognormal, and the simulation uses that lognormal as
+// a control variate, drawn on every path from the geometric basket of the same spots so that
+// it moves with the basket. Spreads use Margrabe's exchange option as their control.
+
+use crate::bs;
+use crate::bs::normal_cdf;
+use crate::error::PricingError;
+use crate::float::{ Float, SimdFloat };
+use crate::mc_simd::{ self, MultiAssetPayoff, PriceEstimate };
+use crate::params::{ MultiAssetMarket, OptionType, SimulationConfig };
+
+/// A call or put on a weighted sum of the spots of the assets of a [`MultiAssetMarket`],
+/// paying max(sum of weight * spot - strike, 0) for a call
+#[derive(Clone, Debug, PartialEq)]
+pub struct BasketOption<F: Float = f32> {
+    pub option_type: OptionType,
+    /// The number of units of every asset in the basket, in the order of the assets in the market
+    pub weights: Vec<F>,
+    pub strike: F,
+    pub years_to_expiry: F,
+}
+
+impl<F: Float> BasketOption<F> {
+    pub fn new(
+        option_type: OptionType,
+        weights: Vec<F>,
+        strike: F,
+        years_to_expiry: F
+    ) -> BasketOption<F> {
+        BasketOption { option_type, weights, strike, years_to_expiry }
+    }
+
+    /// Checks the option, and that there is a weight for every asset of `market`
+    pub fn validate(&self, market: &MultiAssetMarket<F>) -> Result<(), PricingError> {
+        if !(self.strike >= F::from_f64(0.0) && self.strike.is_finite()) {
+            return Err(PricingError::InvalidStrike(self.strike.to_f64()));
+        }
+        if !(self.years_to_expiry > F::from_f64(0.0) && self.years_to_expiry.is_finite()) {
+            return Err(PricingError::InvalidYearsToExpiry(self.years_to_expiry.to_f64()));
+        }
+        if self.weights.len() != market.num_assets() {
+            return Err(PricingError::AssetCountMismatch {
+                expected: market.num_assets(),
+                actual: self.weights.len(),
+            });
+        }
+        for &weight in &self.weights {
+            if !(weight >= F::from_f64(0.0) && weight.is_finite()) {
+                return Err(PricingError::InvalidWeight(weight.to_f64()));
+            }
+        }
+        if self.weights.iter().all(|&weight| weight == F::from_f64(0.0)) {
+            return Err(PricingError::InvalidWeight(0.0));
+        }
+        market.validate()
+    }
+}
+
+/// A call or put on the difference of the spots of the two assets of a [`MultiAssetMarket`],
+/// paying max(first spot - second spot - strike, 0) for a call
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpreadOption<F: Float = f32> {
+    pub option_type: OptionType,
+    /// The strike of the spread, which may be negative
+    pub strike: F,
+    pub years_to_expiry: F,
+}
+
+impl<F: Float> SpreadOption<F> {
+    pub fn new(option_type: OptionType, strike: F, years_to_expiry: F) -> SpreadOption<F> {
+        SpreadOption { option_type, strike, years_to_expiry }
+    }
+
+    /// Checks the option, and that `market` has exactly two assets
+    pub fn validate(&self, market: &MultiAssetMarket<F>) -> Result<(), PricingError> {
+        if !self.strike.is_finite() {
+            return Err(PricingError::InvalidStrike(self.strike.to_f64()));
+        }
+        if !(self.years_to_expiry > F::from_f64(0.0) && self.years_to_expiry.is_finite()) {
+            return Err(PricingError::InvalidYearsToExpiry(self.years_to_expiry.to_f64()));
+        }
+        if market.num_assets() != 2 {
+            return Err(PricingError::AssetCountMismatch { expected: 2, actual: market.num_assets() });
+        }
+        market.validate()
+    }
+}
+
+// The payoff of a basket option on V::LANES paths, on the weighted sum of the spots at expiry,
+// or for the control variate on scale times the product of the spots to the power of the
+// weights. The paths are simulated in a single step, so the spots seen are those at expiry.
+struct BasketPayoff<V> {
+    call_mult: V,
+    strike: V,
+    weights: Vec<V>,
+    geometric: bool,
+    scale: V,
+}
+
+impl<V: SimdFloat> MultiAssetPayoff<V> for BasketPayoff<V> {
+    type State = V;
+
+    fn start(&self) -> V {
+        V::splat(0.0)
+    }
+
+    fn observe(&self, basket: &mut V, _step: usize, spots: &[V]) {
+        let sum = self.weights
+            .iter()
+            .zip(spots)
+            .fold(V::splat(0.0), |sum, (&weight, &spot)| {
+                V::mul_add(weight, if self.geometric { spot.ln() } else { spot }, sum)
+            });
+        *basket = if self.geometric { self.scale * sum.exp() } else { sum };
+    }
+
+    fn payoff(&self, basket: V) -> V {
+        V::fast_max(self.call_mult * (basket - self.strike), V::splat(0.0))
+    }
+}
+
+// The payoff of a spread option on V::LANES paths, on the difference of the spots at expiry,
+// simulated in a single step
+struct SpreadPayoff<V> {
+    call_mult: V,
+    strike: V,
+}
+
+impl<V: SimdFloat> MultiAssetPayoff<V> for SpreadPayoff<V> {
+    type State = V;
+
+    fn start(&self) -> V {
+        V::splat(0.0)
+    }
+
+    fn observe(&self, spread: &mut V, _step: usize, spots: &[V]) {
+        *spread = spots[0] - spots[1];
+    }
+
+    fn payoff(&self, spread: V) -> V {
+        V::fast_max(self.call_mult * (spread - self.strike), V::splat(0.0))
+    }
+}
+
+// Undiscounted price of an option paying max(call_mult * (X - strike), 0), where log X is
+// normal with variance `variance` and X has expectation expected_x
+fn lognormal_price(expected_x: f64, strike: f64, variance: f64, call_mult: f64) -> f64 {
+    if variance <= 0.0 || strike <= 0.0 {
+        return (call_mult * (expected_x - strike)).max(0.0);
+    }
+    let std_dev = variance.sqrt();
+    let d1 = ((expected_x / strike).ln() + 0.5 * variance) / std_dev;
+    let d2 = d1 - std_dev;
+    call_mult * (expected_x * normal_cdf(call_mult * d1) - strike * normal_cdf(call_mult * d2))
+}
+
+// The forward of every asset at expiry, weighted by the weight of the asset
+fn weighted_forwards<F: Float>(option: &BasketOption<F>, market: &MultiAssetMarket<F>) -> Vec<f64> {
+    let years_to_expiry = option.years_to_expiry.to_f64();
+    let risk_free_rate = market.risk_free_rate.to_f64();
+    (0..market.num_assets())
+        .map(|asset| {
+            let carry = risk_free_rate - market.dividend_yields[asset].to_f64();
+            option.weights[asset].to_f64() *
+                market.spots[asset].to_f64() *
+                (carry * years_to_expiry).exp()
+        })
+        .collect()
+}
+
+// The covariance of the log spots of two assets at expiry
+fn log_covariance<F: Float>(market: &MultiAssetMarket<F>, i: usize, j: usize, years_to_expiry: f64) -> f64 {
+    market.correlation[i][j].to_f64() *
+        market.volatilities[i].to_f64() *
+        market.volatilities[j].to_f64() *
+        years_to_expiry
+}
+
+// The variance of the log of Levy's lognormal, which has the same first two moments as the
+// basket whose weighted forwards are `forwards`
+fn levy_variance<F: Float>(forwards: &[f64], market: &MultiAssetMarket<F>, years_to_expiry: f64) -> f64 {
+    let first_moment: f64 = forwards.iter().sum();
+    let mut second_moment = 0.0;
+    for (i, forward_i) in forwards.iter().enumerate() {
+        for (j, forward_j) in forwards.iter().enumerate() {
+            second_moment +=
+                forward_i * forward_j * log_covariance(market, i, j, years_to_expiry).exp();
+        }
+    }
+    (second_moment / (first_moment * first_moment)).ln()
+}
+
+/// Levy's approximation of the price of a basket option, which prices the basket as a
+/// lognormal with the same mean and variance. It is accurate to a few cents for baskets of
+/// similar volatilities, and is not a bound on the price.
+pub fn levy_price<F: Float>(
+    option: &BasketOption<F>,
+    market: &MultiAssetMarket<F>
+) -> Result<F, PricingError> {
+    option.validate(market)?;
+
+    let years_to_expiry = option.years_to_expiry.to_f64();
+    let forwards = weighted_forwards(option, market);
+    let price = lognormal_price(
+        forwards.iter().sum(),
+        option.strike.to_f64(),
+        levy_variance(&forwards, market, years_to_expiry),
+        option.option_type.call_mult()
+    );
+    Ok(F::from_f64((-market.risk_free_rate.to_f64() * years_to_expiry).exp() * price))
+}
+
+/// Price a basket option, with the standard error and confidence interval of the price. Only
+/// the spots at expiry matter, so the paths are simulated in a single step whatever
+/// `config.steps` is. Levy's lognormal is used as a control variate, whose price is
+/// [`levy_price`]. It is drawn on every path by standardizing the log of the geometric
+/// basket, weighted by the share of every asset in the forward of the basket, and rescaling
+/// it to the mean and variance of the basket.
+pub fn price<F: Float>(
+    option: &BasketOption<F>,
+    market: &MultiAssetMarket<F>,
+    config: &SimulationConfig
+) -> Result<PriceEstimate<F>, PricingError> {
+    option.validate(market)?;
+    config.validate()?;
+    let config = SimulationConfig { steps: 1, ..*config };
+
+    let years_to_expiry = option.years_to_expiry.to_f64();
+    let risk_free_rate = market.risk_free_rate.to_f64();
+    let call_mult = option.option_type.call_mult();
+    let forwards = weighted_forwards(option, market);
+    let first_moment: f64 = forwards.iter().sum();
+    let shares: Vec<f64> = forwards
+        .iter()
+        .map(|forward| forward / first_moment)
+        .collect();
+
+    // Mean and variance of the log of the geometric basket
+    let mut log_mean = 0.0;
+    let mut log_variance = 0.0;
+    for (i, share_i) in shares.iter().enumerate() {
+        let volatility = market.volatilities[i].to_f64();
+        let drift = risk_free_rate - market.dividend_yields[i].to_f64() - 0.5 * volatility * volatility;
+        log_mean += share_i * (market.spots[i].to_f64().ln() + drift * years_to_expiry);
+        for (j, share_j) in shares.iter().enumerate() {
+            log_variance += share_i * share_j * log_covariance(market, i, j, years_to_expiry);
+        }
+    }
+    // exp(levy_std_dev * Z) with Z the standardized log of the geometric basket, scaled to
+    // the mean of the basket, is Levy's lognormal
+    let levy_variance = levy_variance(&forwards, market, years_to_expiry);
+    let exponent = (levy_variance / log_variance).sqrt();
+    let scale = first_moment * (-exponent * log_mean - 0.5 * levy_variance).exp();
+    let control_price =
+        (-risk_free_rate * years_to_expiry).exp() *
+        lognormal_price(first_moment, option.strike.to_f64(), levy_variance, call_mult);
+
+    let payoff = BasketPayoff {
+        call_mult: F::Lanes::splat(call_mult),
+        strike: F::Lanes::splat(option.strike.to_f64()),
+        weights: option.weights
+            .iter()
+            .map(|weight| F::Lanes::splat(weight.to_f64()))
+            .collect(),
+        geometric: false,
+        scale: F::Lanes::splat(1.0),
+    };
+    let control = BasketPayoff {
+        weights: shares
+            .iter()
+            .map(|&share| F::Lanes::splat(exponent * share))
+            .collect(),
+        geometric: true,
+        scale: F::Lanes::splat(scale),
+        ..payoff
+    };
+    mc_simd::price_multi_asset_with_control(
+        market,
+        option.years_to_expiry,
+        &config,
+        &payoff,
+        &control,
+        F::from_f64(control_price)
+    )
+}
+
+/// Price a spread option, with the standard error and confidence interval of the price. The
+/// option to exchange the second asset for the first, priced with [`bs::exchange_price`], is
+/// used as a control variate, which is the spread option itself when the strike is 0. The
+/// paths are simulated in a single step whatever `config.steps` is.
+pub fn spread_price<F: Float>(
+    option: &SpreadOption<F>,
+    market: &MultiAssetMarket<F>,
+    config: &SimulationConfig
+) -> Result<PriceEstimate<F>, PricingError> {
+    option.validate(market)?;
+    config.validate()?;
+    let config = SimulationConfig { steps: 1, ..*config };
+
+    // A put on the spread is a call on the reversed spread
+    let (first, second) = match option.option_type {
+        OptionType::Call => (0, 1),
+        OptionType::Put => (1, 0),
+    };
+    let control_price = bs::exchange_price(
+        market.spots[first],
+        market.spots[second],
+        market.volatilities[first],
+        market.volatilities[second],
+        market.correlation[0][1],
+        option.years_to_expiry,
+        market.dividend_yields[first],
+        market.dividend_yields[second]
+    );
+
+    let payoff = SpreadPayoff {
+        call_mult: F::Lanes::splat(option.option_type.call_mult()),
+        strike: F::Lanes::splat(option.strike.to_f64()),
+    };
+    let control = SpreadPayoff { strike: F::Lanes::splat(0.0), ..payoff };
+    mc_simd::price_multi_asset_with_control(
+        market,
+        option.years_to_expiry,
+        &config,
+        &payoff,
+        &control,
+        control_price
+    )
+}
+
+#[cfg(test)]
+fn test_market(correlation: f64) -> MultiAssetMarket<f64> {
+    MultiAssetMarket::new(
+        vec![100.0, 95.0, 105.0],
+        vec![0.2, 0.25, 0.3],
+        vec![0.01, 0.0, 0.02],
+        0.05,
+        vec![
+            vec![1.0, correlation, correlation],
+            vec![correlation, 1.0, correlation],
+            vec![correlation, correlation, 1.0]
+        ]
+    )
+}
+
+#[test]
+fn single_asset_basket_matches_bs() {
+    let market = MultiAssetMarket::new(vec![100.0], vec![0.25], vec![0.02], 0.05, vec![vec![1.0]]);
+    let config = SimulationConfig::builder().steps(1).num_trials(20000).seed(90).build().unwrap();
+    for option_type in [OptionType::Call, OptionType::Put] {
+        let option = BasketOption::new(option_type, vec![2.0], 220.0, 0.5);
+        let actual_price = 2.0 * bs::price(option_type, 100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+        // The lognormal of a single asset is exact
+        let levy = levy_price(&option, &market).unwrap();
+        assert!((levy - actual_price).abs() < 1e-10);
+        // and so is the control variate, which leaves no error
+        let estimate = price(&option, &market, &config).unwrap();
+        println!("single asset basket {:?} {:?} vs {}", option_type, estimate, actual_price);
+        assert!((estimate.price - actual_price).abs() < 1e-8);
+    }
+}
+
+#[test]
+fn basket_matches_quadrature_and_levy() {
+    // Prices from Gauss-Hermite quadrature over the first two normals of the Cholesky factor,
+    // with the last asset, the only one that depends on the third normal, integrated in closed
+    // form, converged to 1e-9
+    let market = test_market(0.5);
+    let config = SimulationConfig::builder().steps(1).num_trials(40000).seed(91).build().unwrap();
+    for (option_type, strike, actual_price) in [
+        (OptionType::Call, 100.0, 9.842402327588823),
+        (OptionType::Put, 105.0, 8.419566011548199),
+    ] {
+        let option = BasketOption::new(option_type, vec![0.4, 0.3, 0.3], strike, 1.0);
+        let estimate = price(&option, &market, &config).unwrap();
+        let levy = levy_price(&option, &market).unwrap();
+        println!("basket {:?} {:?} vs {}, levy {}", option_type, estimate, actual_price, levy);
+        assert!(estimate.std_error < 0.01);
+        assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+        // Levy's approximation is off by a couple of cents for baskets of similar assets
+        assert!((levy - actual_price).abs() < 0.02);
+        // The paths are simulated in one step however many steps are asked for
+        assert_eq!(price(&option, &market, &SimulationConfig { steps: 50, ..config }), Ok(estimate));
+    }
+}
+
+#[test]
+fn basket_control_variate_reduces_std_error() {
+    let market = test_market(0.3);
+    let config = SimulationConfig::builder().steps(1).num_trials(20000).seed(92).build().unwrap();
+    let option = BasketOption::new(OptionType::Call, vec![1.0, 1.0, 1.0], 300.0, 1.0);
+    let estimate = price(&option, &market, &config).unwrap();
+    let basket_call = |spots: &[f64x4]| {
+        f64x4::fast_max(spots[0] + spots[1] + spots[2] - f64x4::splat(300.0), f64x4::splat(0.0))
+    };
+    let plain_estimate = mc_simd::price_multi_asset(&market, 1.0, &config, &basket_call).unwrap();
+    println!("basket {:?} vs without control {:?}", estimate, plain_estimate);
+    assert!(estimate.std_error < 0.3 * plain_estimate.std_error);
+    assert!((estimate.price - plain_estimate.price).abs() < 4.0 * plain_estimate.std_error);
+}
+
+#[test]
+fn spread_matches_margrabe() {
+    let market = MultiAssetMarket::new(
+        vec![100.0, 95.0],
+        vec![0.25, 0.3],
+        vec![0.02, 0.01],
+        0.05,
+        vec![vec![1.0, 0.4], vec![0.4, 1.0]]
+    );
+    let config = SimulationConfig::builder().steps(1).num_trials(20000).seed(93).build().unwrap();
+
+    let exchange = SpreadOption::new(OptionType::Call, 0.0, 1.0);
+    let estimate = spread_price(&exchange, &market, &config).unwrap();
+    let actual_price = bs::exchange_price(100.0, 95.0, 0.25, 0.3, 0.4, 1.0, 0.02, 0.01);
+    println!("exchange {:?} vs {}", estimate, actual_price);
+    assert!((estimate.price - actual_price).abs() < 1e-8);
+
+    let spread_call = |spots: &[f64x4]| f64x4::fast_max(spots[0] - spots[1] - f64x4::splat(5.0), f64x4::splat(0.0));
+    let plain_estimate = mc_simd::price_multi_asset(&market, 1.0, &config, &spread_call).unwrap();
+    let estimate = spread_price(&SpreadOption::new(OptionType::Call, 5.0, 1.0), &market, &config).unwrap();
+    println!("spread {:?} vs without control {:?}", estimate, plain_estimate);
+    assert!(estimate.std_error < 0.5 * plain_estimate.std_error);
+    assert!((estimate.price - plain_estimate.price).abs() < 4.0 * plain_estimate.std_error);
+
+    // Put-call parity of the spread with the forwards of the two assets
+    let put = spread_price(&SpreadOption::new(OptionType::Put, 5.0, 1.0), &market, &config).unwrap();
+    let forward = 100.0 * (-0.02f64).exp() - 95.0 * (-0.01f64).exp() - 5.0 * (-0.05f64).exp();
+    println!("spread put {:?}, parity {} vs {}", put, estimate.price - put.price, forward);
+    assert!((estimate.price - put.price - forward).abs() < 4.0 * (estimate.std_error + put.std_error));
+}
+
+#[test]
+fn f32_basket_and_invalid_parameters() {
+    let market = MultiAssetMarket::<f32>::new(
+        vec![100.0, 95.0],
+        vec![0.25, 0.3],
+        vec![0.02, 0.01],
+        0.05,
+        vec![vec![1.0, 0.4], vec![0.4, 1.0]]
+    );
+    let config = SimulationConfig::builder().steps(1).num_trials(20000).seed(94).build().unwrap();
+    let option = BasketOption::new(OptionType::Call, vec![0.5, 0.5], 100.0, 1.0);
+    let estimate = price(&option, &market, &config).unwrap();
+    let levy = levy_price(&option, &market).unwrap();
+    // From Gauss-Hermite quadrature over the first asset, with the second in closed form
+    let actual_price = 9.229262345550945f64;
+    println!("f32 basket {:?} vs {}, levy {}", estimate, actual_price, levy);
+    assert!((estimate.price as f64 - actual_price).abs() < 4.0 * (estimate.std_error as f64));
+    assert!((levy as f64 - actual_price).abs() < 0.01);
+
+    assert_eq!(
+        price(&BasketOption::new(OptionType::Call, vec![0.5], 100.0, 1.0), &market, &config),
+        Err(PricingError::AssetCountMismatch { expected: 2, actual: 1 })
+    );
+    assert_eq!(
+        levy_price(&BasketOption::new(OptionType::Call, vec![0.5, -0.5], 100.0, 1.0), &market),
+        Err(PricingError::InvalidWeight(-0.5))
+    );
+    assert_eq!(
+        levy_price(&BasketOption::new(OptionType::Call, vec![0.0, 0.0], 100.0, 1.0), &market),
+        Err(PricingError::InvalidWeight(0.0))
+    );
+    let three_assets = MultiAssetMarket::<f32>::new(
+        vec![100.0; 3],
+        vec![0.2; 3],
+        vec![0.0; 3],
+        0.05,
+        vec![vec![1.0, 0.0, 0.0], vec![0.0, 1.0, 0.0], vec![0.0, 0.0, 1.0]]
+    );
+    assert_eq!(
+        spread_price(&SpreadOption::new(OptionType::Call, 0.0, 1.0), &three_assets, &config),
+        Err(PricingError::AssetCountMismatch { expected: 2, actual: 3 })
+    );
+}
+
+#[cfg(test)]
+use wide::f64x4;
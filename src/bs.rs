@@ -1,184 +1,1758 @@
-// Black-scholes pricer, used to test the Monte-carlo simulation pricers
+// Black-scholes prices and Greeks of European options, used for quoting, as control variates
+// and to test the Monte-carlo simulation pricers. The formulas are evaluated in f64 whatever
+// the precision of the parameters.
 
-fn erf(x: f32) -> f32 {
-    let t = x.signum();
-    let e = x.abs();
-    const N: f32 = 0.3275911;
-    const A: f32 = 0.254829592;
-    const R: f32 = -0.284496736;
-    const I: f32 = 1.421413741;
-    const L: f32 = -1.453152027;
-    const D: f32 = 1.061405429;
-    let u = 1.0 / (1.0 + N * e);
-    let m = 1.0 - ((((D * u + L) * u + I) * u + R) * u + A) * u * (-e * e).exp();
-    t * m
+use crate::float::Float;
+use crate::params::{ BarrierType, OptionType, RainbowType };
+
+// Coefficients of Cody's rational Chebyshev approximations of the normal CDF, for |x| <= 0.66291
+const CODY_A: [f64; 5] = [
+    2.2352520354606837,
+    161.02823106855587,
+    1067.6894854603709,
+    18154.98125334356,
+    0.06568233791820745,
+];
+const CODY_B: [f64; 4] = [
+    47.202581904688245,
+    976.0985517377767,
+    10260.932208618979,
+    45507.78933502673,
+];
+// for 0.66291 < |x| <= sqrt(32)
+const CODY_C: [f64; 9] = [
+    0.39894151208813466,
+    8.883149794388377,
+    93.50665613217785,
+    597.2702763948002,
+    2494.5375852903726,
+    6848.190450536283,
+    11602.65143764735,
+    9842.714838383978,
+    1.0765576773720192e-08,
+];
+const CODY_D: [f64; 8] = [
+    22.266688044328117,
+    235.387901782625,
+    1519.3775994075547,
+    6485.558298266761,
+    18615.571640885097,
+    34900.95272114598,
+    38912.00328609327,
+    19685.429676859992,
+];
+// and for |x| > sqrt(32)
+const CODY_P: [f64; 6] = [
+    0.215898534057957,
+    0.12740116116024736,
+    0.022235277870649807,
+    0.0014216191932278934,
+    2.9112874951168793e-05,
+    0.023073441764940174,
+];
+const CODY_Q: [f64; 5] = [
+    1.284260096144911,
+    0.4682382124808651,
+    0.06598813786892856,
+    0.0037823963320275824,
+    7.297515550839662e-05,
+];
+
+/// Cumulative distribution function of the standard normal distribution, using W. J. Cody's
+/// rational Chebyshev approximations. The relative error is around 1e-15, also in the tails.
+pub fn normal_cdf(x: f64) -> f64 {
+    let y = x.abs();
+    if y <= 0.66291 {
+        let x_squared = x * x;
+        let mut numerator = CODY_A[4] * x_squared;
+        let mut denominator = x_squared;
+        for (a, b) in CODY_A[..3].iter().zip(&CODY_B[..3]) {
+            numerator = (numerator + a) * x_squared;
+            denominator = (denominator + b) * x_squared;
+        }
+        return 0.5 + (x * (numerator + CODY_A[3])) / (denominator + CODY_B[3]);
+    }
+
+    let tail = if y <= (32.0f64).sqrt() {
+        let mut numerator = CODY_C[8] * y;
+        let mut denominator = y;
+        for (c, d) in CODY_C[..7].iter().zip(&CODY_D[..7]) {
+            numerator = (numerator + c) * y;
+            denominator = (denominator + d) * y;
+        }
+        (numerator + CODY_C[7]) / (denominator + CODY_D[7])
+    } else {
+        let inv_x_squared = 1.0 / (x * x);
+        let mut numerator = CODY_P[5] * inv_x_squared;
+        let mut denominator = inv_x_squared;
+        for (p, q) in CODY_P[..4].iter().zip(&CODY_Q[..4]) {
+            numerator = (numerator + p) * inv_x_squared;
+            denominator = (denominator + q) * inv_x_squared;
+        }
+        let correction = (inv_x_squared * (numerator + CODY_P[4])) / (denominator + CODY_Q[4]);
+        (1.0 / (2.0 * std::f64::consts::PI).sqrt() - correction) / y
+    };
+
+    // exp(-y^2 / 2) split in two so that the rounding of y^2 does not cost any digits
+    let y_rounded = (y * 16.0).trunc() / 16.0;
+    let delta = (y - y_rounded) * (y + y_rounded);
+    let tail = (-y_rounded * y_rounded * 0.5).exp() * (-delta * 0.5).exp() * tail;
+
+    if x > 0.0 { 1.0 - tail } else { tail }
 }
 
-fn normal_cdf(x: f32) -> f32 {
-    0.5 * (1.0 + erf(x / (2.0f32).sqrt()))
+/// Probability density function of the standard normal distribution
+pub fn normal_pdf(x: f64) -> f64 {
+    (-0.5 * x * x).exp() / (2.0 * std::f64::consts::PI).sqrt()
 }
 
-fn pdf(x: f32, mu: f32, sigma: f32) -> f32 {
-    ((-1.0 * (x - mu) * (x - mu)) / (2.0 * sigma * sigma)).exp() /
-        (sigma * (2.0 * 3.14159f32).sqrt())
+// Widens the parameters of a pricing function to f64
+fn to_f64<F: Float>(
+    spot: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> (f64, f64, f64, f64, f64, f64) {
+    (
+        spot.to_f64(),
+        strike.to_f64(),
+        volatility.to_f64(),
+        risk_free_rate.to_f64(),
+        years_to_expiry.to_f64(),
+        dividend_yield.to_f64(),
+    )
 }
 
 fn d(
-    spot: f32,
-    strike: f32,
-    volatility: f32,
-    risk_free_rate: f32,
-    years_to_expiry: f32,
-    dividend_yield: f32
-) -> (f32, f32) {
-    let d1: f32 =
+    spot: f64,
+    strike: f64,
+    volatility: f64,
+    risk_free_rate: f64,
+    years_to_expiry: f64,
+    dividend_yield: f64
+) -> (f64, f64) {
+    let d1: f64 =
         ((spot / strike).ln() +
             (risk_free_rate - dividend_yield + (volatility * volatility) / 2.0) * years_to_expiry) /
         (volatility * years_to_expiry.sqrt());
-    let d2: f32 = d1 - volatility * years_to_expiry.sqrt();
+    let d2: f64 = d1 - volatility * years_to_expiry.sqrt();
     (d1, d2)
 }
 
-pub(crate) fn call_price(
-    spot: f32,
-    strike: f32,
-    volatility: f32,
-    risk_free_rate: f32,
-    years_to_expiry: f32,
-    dividend_yield: f32
-) -> f32 {
+/// Price of a European call option
+pub fn call_price<F: Float>(
+    spot: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
     let (d1, d2) = d(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
 
-    let call: f32 =
+    let call: f64 =
         spot * (-dividend_yield * years_to_expiry).exp() * normal_cdf(d1) -
         strike * (-risk_free_rate * years_to_expiry).exp() * normal_cdf(d2);
-    call
+    F::from_f64(call)
 }
 
-pub(crate) fn put_price(
-    spot: f32,
-    strike: f32,
-    volatility: f32,
-    risk_free_rate: f32,
-    years_to_expiry: f32,
-    dividend_yield: f32
-) -> f32 {
+/// Price of a European put option
+pub fn put_price<F: Float>(
+    spot: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
     let (d1, d2) = d(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
 
-    let put: f32 =
-        strike * (-risk_free_rate * years_to_expiry).exp() * (1.0 - normal_cdf(d2)) -
-        spot * (-dividend_yield * years_to_expiry).exp() * (1.0 - normal_cdf(d1));
-    put
+    let put: f64 =
+        strike * (-risk_free_rate * years_to_expiry).exp() * normal_cdf(-d2) -
+        spot * (-dividend_yield * years_to_expiry).exp() * normal_cdf(-d1);
+    F::from_f64(put)
 }
 
-pub(crate) fn gamma(
-    spot: f32,
-    strike: f32,
-    volatility: f32,
-    risk_free_rate: f32,
-    years_to_expiry: f32,
-    dividend_yield: f32
-) -> f32 {
+/// Price of a European call or put option, [`call_price`] or [`put_price`] by `option_type`
+pub fn price<F: Float>(
+    option_type: OptionType,
+    spot: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    match option_type {
+        OptionType::Call => call_price(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield),
+        OptionType::Put => put_price(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield),
+    }
+}
+
+/// Gamma, the same for calls and puts
+pub fn gamma<F: Float>(
+    spot: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
     let (d1, _) = d(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
-    let nd1 = pdf(d1, 0.0, 1.0);
-    ((-dividend_yield * years_to_expiry).exp() * nd1) / (spot * volatility * years_to_expiry.sqrt())
-}
-
-pub(crate) fn call_delta(
-    spot: f32,
-    strike: f32,
-    volatility: f32,
-    risk_free_rate: f32,
-    years_to_expiry: f32,
-    dividend_yield: f32
-) -> f32 {
+    let nd1 = normal_pdf(d1);
+    F::from_f64(
+        ((-dividend_yield * years_to_expiry).exp() * nd1) / (spot * volatility * years_to_expiry.sqrt())
+    )
+}
+
+/// Delta of a call option
+pub fn call_delta<F: Float>(
+    spot: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
     let (d1, _) = d(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
     let nd1 = normal_cdf(d1);
-    (-dividend_yield * years_to_expiry).exp() * nd1
+    F::from_f64((-dividend_yield * years_to_expiry).exp() * nd1)
 }
 
-pub(crate) fn put_delta(
-    spot: f32,
-    strike: f32,
-    volatility: f32,
-    risk_free_rate: f32,
-    years_to_expiry: f32,
-    dividend_yield: f32
-) -> f32 {
+/// Delta of a put option
+pub fn put_delta<F: Float>(
+    spot: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
     let (d1, _) = d(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
     let nd1 = normal_cdf(d1);
-    (-dividend_yield * years_to_expiry).exp() * (nd1 - 1.0)
+    F::from_f64((-dividend_yield * years_to_expiry).exp() * (nd1 - 1.0))
 }
 
-pub(crate) fn vega(
-    spot: f32,
-    strike: f32,
-    volatility: f32,
-    risk_free_rate: f32,
-    years_to_expiry: f32,
-    dividend_yield: f32
-) -> f32 {
+/// Vega per 1% change in the volatility, the same for calls and puts
+pub fn vega<F: Float>(
+    spot: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
     let (d1, _) = d(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
-    let nd1 = pdf(d1, 0.0, 1.0);
-    ((-dividend_yield * years_to_expiry).exp() * nd1 * (spot * years_to_expiry.sqrt())) / 100.0
-}
-
-pub(crate) fn call_rho(
-    spot: f32,
-    strike: f32,
-    volatility: f32,
-    risk_free_rate: f32,
-    years_to_expiry: f32,
-    dividend_yield: f32
-) -> f32 {
+    let nd1 = normal_pdf(d1);
+    F::from_f64(((-dividend_yield * years_to_expiry).exp() * nd1 * (spot * years_to_expiry.sqrt())) / 100.0)
+}
+
+/// Rho of a call option per 1% change in the risk-free rate
+pub fn call_rho<F: Float>(
+    spot: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
     let (_, d2) = d(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
     let nd2 = normal_cdf(d2);
-    strike * years_to_expiry * (-risk_free_rate * years_to_expiry).exp() * nd2 / 100.0
+    F::from_f64(strike * years_to_expiry * (-risk_free_rate * years_to_expiry).exp() * nd2 / 100.0)
 }
 
-pub(crate) fn put_rho(
-    spot: f32,
-    strike: f32,
-    volatility: f32,
-    risk_free_rate: f32,
-    years_to_expiry: f32,
-    dividend_yield: f32
-) -> f32 {
+/// Rho of a put option per 1% change in the risk-free rate
+pub fn put_rho<F: Float>(
+    spot: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
     let (_, d2) = d(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
     let nd2 = normal_cdf(d2);
-    strike * years_to_expiry * (-risk_free_rate * years_to_expiry).exp() * (nd2 - 1.0) / 100.0
+    F::from_f64(strike * years_to_expiry * (-risk_free_rate * years_to_expiry).exp() * (nd2 - 1.0) / 100.0)
 }
 
-pub(crate) fn call_theta(
-    spot: f32,
-    strike: f32,
-    volatility: f32,
-    risk_free_rate: f32,
-    years_to_expiry: f32,
-    dividend_yield: f32
-) -> f32 {
+/// Dividend rho of a call option per 1% change in the dividend yield
+pub fn call_dividend_rho<F: Float>(
+    spot: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (d1, _) = d(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    F::from_f64(
+        -spot * years_to_expiry * (-dividend_yield * years_to_expiry).exp() * normal_cdf(d1) / 100.0
+    )
+}
+
+/// Dividend rho of a put option per 1% change in the dividend yield
+pub fn put_dividend_rho<F: Float>(
+    spot: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (d1, _) = d(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    F::from_f64(
+        spot * years_to_expiry * (-dividend_yield * years_to_expiry).exp() * normal_cdf(-d1) / 100.0
+    )
+}
+
+/// Theta of a call option per year
+pub fn call_theta<F: Float>(
+    spot: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
     let (d1, d2) = d(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
     let result =
-        (-(-dividend_yield * years_to_expiry).exp() * spot * pdf(d1, 0.0, 1.0) * volatility) /
+        (-(-dividend_yield * years_to_expiry).exp() * spot * normal_pdf(d1) * volatility) /
         (2.0 * years_to_expiry.sqrt());
     let result_part1 = risk_free_rate * strike * (-risk_free_rate * years_to_expiry).exp();
     let result_part2 = dividend_yield * spot * (-dividend_yield * years_to_expiry).exp();
-    result - result_part1 * normal_cdf(d2) + result_part2 * normal_cdf(d1)
+    F::from_f64(result - result_part1 * normal_cdf(d2) + result_part2 * normal_cdf(d1))
 }
 
-pub(crate) fn put_theta(
-    spot: f32,
-    strike: f32,
-    volatility: f32,
-    risk_free_rate: f32,
-    years_to_expiry: f32,
-    dividend_yield: f32
-) -> f32 {
+/// Theta of a put option per year
+pub fn put_theta<F: Float>(
+    spot: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
     let (d1, d2) = d(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
     let result =
-        (-(-dividend_yield * years_to_expiry).exp() * spot * pdf(d1, 0.0, 1.0) * volatility) /
+        (-(-dividend_yield * years_to_expiry).exp() * spot * normal_pdf(d1) * volatility) /
         (2.0 * years_to_expiry.sqrt());
     let result_part1 = risk_free_rate * strike * (-risk_free_rate * years_to_expiry).exp();
     let result_part2 = dividend_yield * spot * (-dividend_yield * years_to_expiry).exp();
-    result + result_part1 * normal_cdf(-d2) - result_part2 * normal_cdf(-d1)
-}
\ No newline at end of file
+    F::from_f64(result + result_part1 * normal_cdf(-d2) - result_part2 * normal_cdf(-d1))
+}
+
+/// Price of a European call option on a futures or forward price in Black's 1976 model. The
+/// futures has no drift, so this is the Black-Scholes price with a dividend yield of the
+/// risk-free rate.
+pub fn black76_call_price<F: Float>(
+    forward: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F
+) -> F {
+    call_price(forward, strike, volatility, risk_free_rate, years_to_expiry, risk_free_rate)
+}
+
+/// Price of a European put option on a futures or forward price in Black's 1976 model
+pub fn black76_put_price<F: Float>(
+    forward: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F
+) -> F {
+    put_price(forward, strike, volatility, risk_free_rate, years_to_expiry, risk_free_rate)
+}
+
+/// Gamma in the futures price in Black's 1976 model, the same for calls and puts
+pub fn black76_gamma<F: Float>(
+    forward: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F
+) -> F {
+    gamma(forward, strike, volatility, risk_free_rate, years_to_expiry, risk_free_rate)
+}
+
+/// Delta in the futures price of a call option in Black's 1976 model
+pub fn black76_call_delta<F: Float>(
+    forward: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F
+) -> F {
+    call_delta(forward, strike, volatility, risk_free_rate, years_to_expiry, risk_free_rate)
+}
+
+/// Delta in the futures price of a put option in Black's 1976 model
+pub fn black76_put_delta<F: Float>(
+    forward: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F
+) -> F {
+    put_delta(forward, strike, volatility, risk_free_rate, years_to_expiry, risk_free_rate)
+}
+
+/// Vega per 1% change in the volatility in Black's 1976 model, the same for calls and puts
+pub fn black76_vega<F: Float>(
+    forward: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F
+) -> F {
+    vega(forward, strike, volatility, risk_free_rate, years_to_expiry, risk_free_rate)
+}
+
+/// Rho of a call option per 1% change in the risk-free rate in Black's 1976 model. The futures
+/// price does not move with the rate, so only the discount factor does.
+pub fn black76_call_rho<F: Float>(
+    forward: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F
+) -> F {
+    let price = black76_call_price(forward, strike, volatility, risk_free_rate, years_to_expiry);
+    F::from_f64(-years_to_expiry.to_f64() * price.to_f64() / 100.0)
+}
+
+/// Rho of a put option per 1% change in the risk-free rate in Black's 1976 model
+pub fn black76_put_rho<F: Float>(
+    forward: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F
+) -> F {
+    let price = black76_put_price(forward, strike, volatility, risk_free_rate, years_to_expiry);
+    F::from_f64(-years_to_expiry.to_f64() * price.to_f64() / 100.0)
+}
+
+/// Theta of a call option per year in Black's 1976 model, with the futures price fixed
+pub fn black76_call_theta<F: Float>(
+    forward: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F
+) -> F {
+    call_theta(forward, strike, volatility, risk_free_rate, years_to_expiry, risk_free_rate)
+}
+
+/// Theta of a put option per year in Black's 1976 model, with the futures price fixed
+pub fn black76_put_theta<F: Float>(
+    forward: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F
+) -> F {
+    put_theta(forward, strike, volatility, risk_free_rate, years_to_expiry, risk_free_rate)
+}
+
+// The forward, the discount factor, the standard deviation of the forward at expiry and the
+// standardised moneyness d = (F - K) / (sigma sqrt(T)) of the Bachelier model
+fn bachelier_d(
+    spot: f64,
+    strike: f64,
+    normal_volatility: f64,
+    risk_free_rate: f64,
+    years_to_expiry: f64,
+    dividend_yield: f64
+) -> (f64, f64, f64, f64) {
+    let forward = spot * ((risk_free_rate - dividend_yield) * years_to_expiry).exp();
+    let discount = (-risk_free_rate * years_to_expiry).exp();
+    let std_dev = normal_volatility * years_to_expiry.sqrt();
+    (forward, discount, std_dev, (forward - strike) / std_dev)
+}
+
+/// Price of a European call option in the Bachelier model, in which the forward to expiry is
+/// normal with standard deviation `normal_volatility` times the square root of the time, so the
+/// spot and the strike can be zero or negative
+pub fn bachelier_call_price<F: Float>(
+    spot: F,
+    strike: F,
+    normal_volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (forward, discount, std_dev, d) =
+        bachelier_d(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    F::from_f64(discount * ((forward - strike) * normal_cdf(d) + std_dev * normal_pdf(d)))
+}
+
+/// Price of a European put option in the Bachelier model
+pub fn bachelier_put_price<F: Float>(
+    spot: F,
+    strike: F,
+    normal_volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (forward, discount, std_dev, d) =
+        bachelier_d(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    F::from_f64(discount * ((strike - forward) * normal_cdf(-d) + std_dev * normal_pdf(d)))
+}
+
+/// Price of a European call or put option in the Bachelier model, [`bachelier_call_price`] or
+/// [`bachelier_put_price`] by `option_type`
+pub fn bachelier_price<F: Float>(
+    option_type: OptionType,
+    spot: F,
+    strike: F,
+    normal_volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    match option_type {
+        OptionType::Call =>
+            bachelier_call_price(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield),
+        OptionType::Put =>
+            bachelier_put_price(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield),
+    }
+}
+
+/// Gamma in the Bachelier model, the same for calls and puts
+pub fn bachelier_gamma<F: Float>(
+    spot: F,
+    strike: F,
+    normal_volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (_, discount, std_dev, d) =
+        bachelier_d(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    // The forward moves by F / S for every unit of the spot
+    let forward_per_spot = ((risk_free_rate - dividend_yield) * years_to_expiry).exp();
+    F::from_f64(discount * forward_per_spot * forward_per_spot * normal_pdf(d) / std_dev)
+}
+
+/// Delta of a call option in the Bachelier model
+pub fn bachelier_call_delta<F: Float>(
+    spot: F,
+    strike: F,
+    normal_volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (_, _, _, d) =
+        bachelier_d(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    F::from_f64((-dividend_yield * years_to_expiry).exp() * normal_cdf(d))
+}
+
+/// Delta of a put option in the Bachelier model
+pub fn bachelier_put_delta<F: Float>(
+    spot: F,
+    strike: F,
+    normal_volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (_, _, _, d) =
+        bachelier_d(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    F::from_f64(-(-dividend_yield * years_to_expiry).exp() * normal_cdf(-d))
+}
+
+/// Vega per 0.01 change in the normal volatility in the Bachelier model, the same for calls and
+/// puts
+pub fn bachelier_vega<F: Float>(
+    spot: F,
+    strike: F,
+    normal_volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (_, discount, _, d) =
+        bachelier_d(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    F::from_f64(discount * years_to_expiry.sqrt() * normal_pdf(d) / 100.0)
+}
+
+/// Rho of a call option per 1% change in the risk-free rate in the Bachelier model, which moves
+/// both the forward and the discount factor
+pub fn bachelier_call_rho<F: Float>(
+    spot: F,
+    strike: F,
+    normal_volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let price = bachelier_call_price(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (forward, discount, _, d) =
+        bachelier_d(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    F::from_f64(years_to_expiry * (discount * forward * normal_cdf(d) - price.to_f64()) / 100.0)
+}
+
+/// Rho of a put option per 1% change in the risk-free rate in the Bachelier model
+pub fn bachelier_put_rho<F: Float>(
+    spot: F,
+    strike: F,
+    normal_volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let price = bachelier_put_price(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (forward, discount, _, d) =
+        bachelier_d(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    F::from_f64(-years_to_expiry * (discount * forward * normal_cdf(-d) + price.to_f64()) / 100.0)
+}
+
+/// Theta of a call option per year in the Bachelier model
+pub fn bachelier_call_theta<F: Float>(
+    spot: F,
+    strike: F,
+    normal_volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let price = bachelier_call_price(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (forward, discount, _, d) =
+        bachelier_d(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let carry = (risk_free_rate - dividend_yield) * forward;
+    let decay = normal_volatility * normal_pdf(d) / (2.0 * years_to_expiry.sqrt());
+    F::from_f64(risk_free_rate * price.to_f64() - discount * (carry * normal_cdf(d) + decay))
+}
+
+/// Theta of a put option per year in the Bachelier model
+pub fn bachelier_put_theta<F: Float>(
+    spot: F,
+    strike: F,
+    normal_volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let price = bachelier_put_price(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (forward, discount, _, d) =
+        bachelier_d(spot, strike, normal_volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let carry = (risk_free_rate - dividend_yield) * forward;
+    let decay = normal_volatility * normal_pdf(d) / (2.0 * years_to_expiry.sqrt());
+    F::from_f64(risk_free_rate * price.to_f64() + discount * (carry * normal_cdf(-d) - decay))
+}
+
+/// Price of a barrier option with a continuously monitored barrier, from Reiner and
+/// Rubinstein's formulas. The rebate is paid when the barrier is hit for knock-out options,
+/// and at expiry if the barrier was never hit for knock-in options.
+pub fn barrier_price<F: Float>(
+    option_type: OptionType,
+    barrier_type: BarrierType,
+    spot: F,
+    strike: F,
+    barrier: F,
+    rebate: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (barrier, rebate) = (barrier.to_f64(), rebate.to_f64());
+    let up = barrier_type.is_up();
+
+    // An option whose barrier has already been hit is worth the rebate or a vanilla option
+    if (up && spot >= barrier) || (!up && spot <= barrier) {
+        if !barrier_type.is_knock_in() {
+            return F::from_f64(rebate);
+        }
+        return F::from_f64(
+            price(option_type, spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield)
+        );
+    }
+
+    let phi = option_type.call_mult();
+    let eta = if up { -1.0 } else { 1.0 };
+    let sigma_sqrt_t = volatility * years_to_expiry.sqrt();
+    let mu = (risk_free_rate - dividend_yield - 0.5 * volatility * volatility) / (volatility * volatility);
+    let lambda = (mu * mu + (2.0 * risk_free_rate) / (volatility * volatility)).sqrt();
+    let spot_discount = spot * (-dividend_yield * years_to_expiry).exp();
+    let strike_discount = strike * (-risk_free_rate * years_to_expiry).exp();
+    let ratio = barrier / spot;
+
+    let x1 = (spot / strike).ln() / sigma_sqrt_t + (1.0 + mu) * sigma_sqrt_t;
+    let x2 = (spot / barrier).ln() / sigma_sqrt_t + (1.0 + mu) * sigma_sqrt_t;
+    let y1 = ((barrier * barrier) / (spot * strike)).ln() / sigma_sqrt_t + (1.0 + mu) * sigma_sqrt_t;
+    let y2 = ratio.ln() / sigma_sqrt_t + (1.0 + mu) * sigma_sqrt_t;
+    let z = ratio.ln() / sigma_sqrt_t + lambda * sigma_sqrt_t;
+
+    // The terms the prices of all eight barrier options are made of, named as in Haug's book
+    let a =
+        phi * spot_discount * normal_cdf(phi * x1) -
+        phi * strike_discount * normal_cdf(phi * (x1 - sigma_sqrt_t));
+    let b =
+        phi * spot_discount * normal_cdf(phi * x2) -
+        phi * strike_discount * normal_cdf(phi * (x2 - sigma_sqrt_t));
+    let c =
+        phi * spot_discount * ratio.powf(2.0 * (mu + 1.0)) * normal_cdf(eta * y1) -
+        phi * strike_discount * ratio.powf(2.0 * mu) * normal_cdf(eta * (y1 - sigma_sqrt_t));
+    let d =
+        phi * spot_discount * ratio.powf(2.0 * (mu + 1.0)) * normal_cdf(eta * y2) -
+        phi * strike_discount * ratio.powf(2.0 * mu) * normal_cdf(eta * (y2 - sigma_sqrt_t));
+    let e =
+        rebate * (-risk_free_rate * years_to_expiry).exp() *
+        (normal_cdf(eta * (x2 - sigma_sqrt_t)) -
+            ratio.powf(2.0 * mu) * normal_cdf(eta * (y2 - sigma_sqrt_t)));
+    let f =
+        rebate *
+        (ratio.powf(mu + lambda) * normal_cdf(eta * z) +
+            ratio.powf(mu - lambda) * normal_cdf(eta * (z - 2.0 * lambda * sigma_sqrt_t)));
+
+    let above = strike > barrier;
+    let price = match (barrier_type, option_type) {
+        (BarrierType::DownAndIn, OptionType::Call) => if above { c + e } else { a - b + d + e },
+        (BarrierType::UpAndIn, OptionType::Call) => if above { a + e } else { b - c + d + e },
+        (BarrierType::DownAndIn, OptionType::Put) => if above { b - c + d + e } else { a + e },
+        (BarrierType::UpAndIn, OptionType::Put) => if above { a - b + d + e } else { c + e },
+        (BarrierType::DownAndOut, OptionType::Call) => if above { a - c + f } else { b - d + f },
+        (BarrierType::UpAndOut, OptionType::Call) => if above { f } else { a - b + c - d + f },
+        (BarrierType::DownAndOut, OptionType::Put) => if above { a - b + c - d + f } else { f },
+        (BarrierType::UpAndOut, OptionType::Put) => if above { b - d + f } else { a - c + f },
+    };
+    F::from_f64(price)
+}
+
+/// The barrier of a continuously monitored option with about the same price as a barrier
+/// monitored every `monitoring_interval` years, from Broadie, Glasserman and Kou's shift of
+/// 0.5826 standard deviations away from the spot
+pub fn discrete_barrier_shift<F: Float>(
+    barrier_type: BarrierType,
+    barrier: F,
+    volatility: F,
+    monitoring_interval: F
+) -> F {
+    // -zeta(1/2) / sqrt(2 pi)
+    const BETA: f64 = 0.5825971579390106;
+    let shift = BETA * volatility.to_f64() * monitoring_interval.to_f64().sqrt();
+    let direction = if barrier_type.is_up() { 1.0 } else { -1.0 };
+    F::from_f64(barrier.to_f64() * (direction * shift).exp())
+}
+
+// The part of a lookback price that comes from the spot moving beyond the extreme spot so far,
+// for calls on the maximum when call_mult is 1.0 and puts on the minimum when it is -1.0.
+// It divides by the cost of carry, so a zero cost of carry is replaced by a tiny one.
+fn lookback_extreme_term(
+    spot: f64,
+    extreme: f64,
+    volatility: f64,
+    risk_free_rate: f64,
+    years_to_expiry: f64,
+    dividend_yield: f64,
+    call_mult: f64
+) -> f64 {
+    let carry = risk_free_rate - dividend_yield;
+    let carry = if carry.abs() < 1e-7 { 1e-7f64.copysign(carry) } else { carry };
+    let variance = volatility * volatility;
+    let sqrt_time = years_to_expiry.sqrt();
+    let d1 =
+        ((spot / extreme).ln() + (carry + 0.5 * variance) * years_to_expiry) /
+        (volatility * sqrt_time);
+    let d2 = d1 - volatility * sqrt_time;
+
+    call_mult *
+        (spot * (-dividend_yield * years_to_expiry).exp() * normal_cdf(call_mult * d1) -
+            extreme * (-risk_free_rate * years_to_expiry).exp() * normal_cdf(call_mult * d2)) +
+        spot * (-risk_free_rate * years_to_expiry).exp() * (variance / (2.0 * carry)) *
+            call_mult *
+            ((carry * years_to_expiry).exp() * normal_cdf(call_mult * d1) -
+                (spot / extreme).powf((-2.0 * carry) / variance) *
+                    normal_cdf(call_mult * (d1 - (2.0 * carry * sqrt_time) / volatility)))
+}
+
+/// Price of a floating strike lookback call, paying the spot at expiry minus the lowest spot,
+/// from Goldman, Sosin and Gatto's formula for continuous monitoring. `min_spot` is the lowest
+/// spot so far, the spot itself for a new option.
+pub fn floating_lookback_call_price<F: Float>(
+    spot: F,
+    min_spot: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, min_spot, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, min_spot, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    // The call is the spot minus the value of receiving the lowest spot at expiry, which is
+    // the lowest spot so far less a put on the minimum struck there
+    let put_on_min = lookback_extreme_term(
+        spot,
+        min_spot,
+        volatility,
+        risk_free_rate,
+        years_to_expiry,
+        dividend_yield,
+        -1.0
+    );
+    F::from_f64(
+        spot * (-dividend_yield * years_to_expiry).exp() -
+        min_spot * (-risk_free_rate * years_to_expiry).exp() +
+        put_on_min
+    )
+}
+
+/// Price of a floating strike lookback put, paying the highest spot minus the spot at expiry,
+/// from Goldman, Sosin and Gatto's formula for continuous monitoring. `max_spot` is the
+/// highest spot so far, the spot itself for a new option.
+pub fn floating_lookback_put_price<F: Float>(
+    spot: F,
+    max_spot: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, max_spot, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, max_spot, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let call_on_max = lookback_extreme_term(
+        spot,
+        max_spot,
+        volatility,
+        risk_free_rate,
+        years_to_expiry,
+        dividend_yield,
+        1.0
+    );
+    F::from_f64(
+        max_spot * (-risk_free_rate * years_to_expiry).exp() -
+        spot * (-dividend_yield * years_to_expiry).exp() +
+        call_on_max
+    )
+}
+
+/// Price of a fixed strike lookback call, paying the highest spot minus the strike, for
+/// continuous monitoring. `max_spot` is the highest spot so far.
+pub fn fixed_lookback_call_price<F: Float>(
+    spot: F,
+    strike: F,
+    max_spot: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let max_spot = max_spot.to_f64();
+    // Below the highest spot so far the strike is certain to be beaten, by at least the gap
+    let extreme = strike.max(max_spot);
+    let sure_gain = (max_spot - strike).max(0.0) * (-risk_free_rate * years_to_expiry).exp();
+    F::from_f64(
+        sure_gain +
+            lookback_extreme_term(
+                spot,
+                extreme,
+                volatility,
+                risk_free_rate,
+                years_to_expiry,
+                dividend_yield,
+                1.0
+            )
+    )
+}
+
+/// Price of a fixed strike lookback put, paying the strike minus the lowest spot, for
+/// continuous monitoring. `min_spot` is the lowest spot so far.
+pub fn fixed_lookback_put_price<F: Float>(
+    spot: F,
+    strike: F,
+    min_spot: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let min_spot = min_spot.to_f64();
+    let extreme = strike.min(min_spot);
+    let sure_gain = (strike - min_spot).max(0.0) * (-risk_free_rate * years_to_expiry).exp();
+    F::from_f64(
+        sure_gain +
+            lookback_extreme_term(
+                spot,
+                extreme,
+                volatility,
+                risk_free_rate,
+                years_to_expiry,
+                dividend_yield,
+                -1.0
+            )
+    )
+}
+
+/// Price of a cash-or-nothing call, paying `cash` if the spot ends above the strike
+pub fn cash_or_nothing_call_price<F: Float>(
+    spot: F,
+    strike: F,
+    cash: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (_, d2) = d(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    F::from_f64(cash.to_f64() * (-risk_free_rate * years_to_expiry).exp() * normal_cdf(d2))
+}
+
+/// Price of a cash-or-nothing put, paying `cash` if the spot ends below the strike
+pub fn cash_or_nothing_put_price<F: Float>(
+    spot: F,
+    strike: F,
+    cash: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (_, d2) = d(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    F::from_f64(cash.to_f64() * (-risk_free_rate * years_to_expiry).exp() * normal_cdf(-d2))
+}
+
+/// Price of an asset-or-nothing call, paying the spot if it ends above the strike
+pub fn asset_or_nothing_call_price<F: Float>(
+    spot: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (d1, _) = d(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    F::from_f64(spot * (-dividend_yield * years_to_expiry).exp() * normal_cdf(d1))
+}
+
+/// Price of an asset-or-nothing put, paying the spot if it ends below the strike
+pub fn asset_or_nothing_put_price<F: Float>(
+    spot: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (d1, _) = d(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    F::from_f64(spot * (-dividend_yield * years_to_expiry).exp() * normal_cdf(-d1))
+}
+
+/// Price of a gap call, paying the spot minus `payoff_strike` if the spot ends above the strike
+pub fn gap_call_price<F: Float>(
+    spot: F,
+    strike: F,
+    payoff_strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    F::from_f64(
+        asset_or_nothing_call_price(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield)
+            .to_f64() -
+        cash_or_nothing_call_price(
+            spot,
+            strike,
+            payoff_strike,
+            volatility,
+            risk_free_rate,
+            years_to_expiry,
+            dividend_yield
+        ).to_f64()
+    )
+}
+
+/// Price of a gap put, paying `payoff_strike` minus the spot if the spot ends below the strike
+pub fn gap_put_price<F: Float>(
+    spot: F,
+    strike: F,
+    payoff_strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> F {
+    F::from_f64(
+        cash_or_nothing_put_price(
+            spot,
+            strike,
+            payoff_strike,
+            volatility,
+            risk_free_rate,
+            years_to_expiry,
+            dividend_yield
+        ).to_f64() -
+        asset_or_nothing_put_price(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield)
+            .to_f64()
+    )
+}
+
+// Gauss-Legendre points and weights on [-1, 1] for Genz's bivariate normal CDF, with 6, 12
+// and 20 points for weak, medium and strong correlations. Only the positive points are kept,
+// the integrals are symmetric.
+const GENZ_WEIGHTS: [&[f64]; 3] = [
+    &[0.1713244923791705, 0.3607615730481384, 0.4679139345726904],
+    &[
+        0.04717533638651177, 0.1069393259953183, 0.1600783285433464, 0.2031674267230659,
+        0.2334925365383547, 0.2491470458134029,
+    ],
+    &[
+        0.01761400713915212, 0.04060142980038694, 0.06267204833410906, 0.08327674157670475,
+        0.1019301198172404, 0.1181945319615184, 0.1316886384491766, 0.1420961093183821,
+        0.1491729864726037, 0.1527533871307259,
+    ],
+];
+const GENZ_POINTS: [&[f64]; 3] = [
+    &[0.9324695142031522, 0.6612093864662647, 0.238619186083197],
+    &[
+        0.9815606342467191, 0.904117256370475, 0.769902674194305, 0.5873179542866171,
+        0.3678314989981802, 0.1252334085114692,
+    ],
+    &[
+        0.9931285991850949, 0.9639719272779138, 0.9122344282513259, 0.8391169718222188,
+        0.7463319064601508, 0.636053680726515, 0.5108670019508271, 0.3737060887154196,
+        0.2277858511416451, 0.07652652113349733,
+    ],
+];
+
+/// Cumulative distribution function of the standard bivariate normal distribution, the
+/// probability that X <= x and Y <= y for standard normals X and Y with the given correlation.
+/// Uses Alan Genz's method, which is accurate to about 1e-15.
+pub fn bivariate_normal_cdf(x: f64, y: f64, correlation: f64) -> f64 {
+    // Genz integrates the upper tail P(X > h, Y > k)
+    let (h, mut k) = (-x, -y);
+    if correlation == 0.0 {
+        return normal_cdf(x) * normal_cdf(y);
+    }
+    let abs_correlation = correlation.abs();
+    let order = if abs_correlation < 0.3 { 0 } else if abs_correlation < 0.75 { 1 } else { 2 };
+    // Every point p is used as 1 - p and 1 + p, for the points on both sides of 0
+    let nodes = || {
+        GENZ_WEIGHTS[order]
+            .iter()
+            .zip(GENZ_POINTS[order])
+            .flat_map(|(&weight, &point)| [(weight, 1.0 - point), (weight, 1.0 + point)])
+    };
+    let two_pi = 2.0 * std::f64::consts::PI;
+    let mut hk = h * k;
+
+    if abs_correlation < 0.925 {
+        // Integrate the density over the correlation from 0, by Gauss-Legendre on asin
+        let hs = (h * h + k * k) / 2.0;
+        let asr = correlation.asin() / 2.0;
+        let integral: f64 = nodes()
+            .map(|(weight, point)| {
+                let sn = (asr * point).sin();
+                weight * ((sn * hk - hs) / (1.0 - sn * sn)).exp()
+            })
+            .sum();
+        return ((integral * asr) / two_pi + normal_cdf(-h) * normal_cdf(-k)).clamp(0.0, 1.0);
+    }
+
+    // Close to perfect correlation, integrate from the singular distribution of correlation 1
+    if correlation < 0.0 {
+        k = -k;
+        hk = -hk;
+    }
+    let mut tail = 0.0;
+    if abs_correlation < 1.0 {
+        let a_squared = (1.0 - correlation) * (1.0 + correlation);
+        let a = a_squared.sqrt();
+        let b_squared = (h - k) * (h - k);
+        let c = (4.0 - hk) / 8.0;
+        let d = (12.0 - hk) / 80.0;
+        let exponent = -(b_squared / a_squared + hk) / 2.0;
+        if exponent > -100.0 {
+            tail =
+                a *
+                exponent.exp() *
+                (1.0 - (c * (b_squared - a_squared) * (1.0 - d * b_squared)) / 3.0 +
+                    c * d * a_squared * a_squared);
+        }
+        if hk > -100.0 {
+            let b = b_squared.sqrt();
+            tail -=
+                (-hk / 2.0).exp() *
+                two_pi.sqrt() *
+                normal_cdf(-b / a) *
+                b *
+                (1.0 - (c * b_squared * (1.0 - d * b_squared)) / 3.0);
+        }
+        let half_a = a / 2.0;
+        let integral: f64 = nodes()
+            .map(|(weight, point)| {
+                let xs = (half_a * point) * (half_a * point);
+                let exponent = -(b_squared / xs + hk) / 2.0;
+                if exponent <= -100.0 {
+                    return 0.0;
+                }
+                let rs = (1.0 - xs).sqrt();
+                let series = 1.0 + c * xs * (1.0 + 5.0 * d * xs);
+                let exact = (-(hk / 2.0) * xs / ((1.0 + rs) * (1.0 + rs))).exp() / rs;
+                weight * exponent.exp() * (series - exact)
+            })
+            .sum();
+        tail = (half_a * integral - tail) / two_pi;
+    }
+
+    let upper = if correlation > 0.0 {
+        tail + normal_cdf(-h.max(k))
+    } else if h >= k {
+        -tail
+    } else {
+        let between = if h < 0.0 {
+            normal_cdf(k) - normal_cdf(h)
+        } else {
+            normal_cdf(-h) - normal_cdf(-k)
+        };
+        between - tail
+    };
+    upper.clamp(0.0, 1.0)
+}
+
+/// Margrabe's price of an option to exchange the second asset for the first at expiry, paying
+/// max(spot - other_spot, 0) with both spots at expiry. The risk-free rate drops out, since the
+/// second asset pays for the first.
+pub fn exchange_price<F: Float>(
+    spot: F,
+    other_spot: F,
+    volatility: F,
+    other_volatility: F,
+    correlation: F,
+    years_to_expiry: F,
+    dividend_yield: F,
+    other_dividend_yield: F
+) -> F {
+    let (spot, other_spot, volatility, other_volatility, years_to_expiry, dividend_yield) = to_f64(
+        spot,
+        other_spot,
+        volatility,
+        other_volatility,
+        years_to_expiry,
+        dividend_yield
+    );
+    let correlation = correlation.to_f64();
+    let other_dividend_yield = other_dividend_yield.to_f64();
+
+    // The first asset priced in units of the second is lognormal with the volatility of the
+    // ratio, and the dividend yield of the second asset as its risk-free rate
+    let ratio_volatility = (
+        volatility * volatility +
+        other_volatility * other_volatility -
+        2.0 * correlation * volatility * other_volatility
+    )
+        .max(0.0)
+        .sqrt();
+    let spot_value = spot * (-dividend_yield * years_to_expiry).exp();
+    let other_spot_value = other_spot * (-other_dividend_yield * years_to_expiry).exp();
+    if ratio_volatility == 0.0 {
+        return F::from_f64((spot_value - other_spot_value).max(0.0));
+    }
+    let (d1, d2) = d(
+        spot,
+        other_spot,
+        ratio_volatility,
+        other_dividend_yield,
+        years_to_expiry,
+        dividend_yield
+    );
+    F::from_f64(spot_value * normal_cdf(d1) - other_spot_value * normal_cdf(d2))
+}
+
+/// Stulz's price of a call or put on the best or the worst of two assets, e.g. a best-of call
+/// pays max(max(spot, other_spot) - strike, 0) with both spots at expiry
+pub fn rainbow_price<F: Float>(
+    option_type: OptionType,
+    rainbow_type: RainbowType,
+    spot: F,
+    other_spot: F,
+    strike: F,
+    volatility: F,
+    other_volatility: F,
+    correlation: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F,
+    other_dividend_yield: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (other_spot, other_volatility, correlation, other_dividend_yield) = (
+        other_spot.to_f64(),
+        other_volatility.to_f64(),
+        correlation.to_f64(),
+        other_dividend_yield.to_f64(),
+    );
+    let sqrt_t = years_to_expiry.sqrt();
+    let spot_value = spot * (-dividend_yield * years_to_expiry).exp();
+    let other_spot_value = other_spot * (-other_dividend_yield * years_to_expiry).exp();
+    let strike_value = strike * (-risk_free_rate * years_to_expiry).exp();
+    let exchange = exchange_price(
+        spot,
+        other_spot,
+        volatility,
+        other_volatility,
+        correlation,
+        years_to_expiry,
+        dividend_yield,
+        other_dividend_yield
+    );
+    // The value of the best or worst asset at expiry, which is the call with a strike of 0
+    let zero_strike_call = match rainbow_type {
+        RainbowType::BestOf => other_spot_value + exchange,
+        RainbowType::WorstOf => spot_value - exchange,
+    };
+
+    let call = if strike <= 0.0 {
+        zero_strike_call
+    } else {
+        let ratio_volatility = (
+            volatility * volatility +
+            other_volatility * other_volatility -
+            2.0 * correlation * volatility * other_volatility
+        )
+            .max(1e-12)
+            .sqrt();
+        let (ratio_d, _) = d(
+            spot,
+            other_spot,
+            ratio_volatility,
+            other_dividend_yield,
+            years_to_expiry,
+            dividend_yield
+        );
+        let (y1, _) = d(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+        let (y2, _) = d(
+            other_spot,
+            strike,
+            other_volatility,
+            risk_free_rate,
+            years_to_expiry,
+            other_dividend_yield
+        );
+        let rho1 = (volatility - correlation * other_volatility) / ratio_volatility;
+        let rho2 = (other_volatility - correlation * volatility) / ratio_volatility;
+        let (z1, z2) = (y1 - volatility * sqrt_t, y2 - other_volatility * sqrt_t);
+        match rainbow_type {
+            RainbowType::BestOf =>
+                spot_value * bivariate_normal_cdf(y1, ratio_d, rho1) +
+                    other_spot_value *
+                        bivariate_normal_cdf(y2, -ratio_d + ratio_volatility * sqrt_t, rho2) -
+                    strike_value * (1.0 - bivariate_normal_cdf(-z1, -z2, correlation)),
+            RainbowType::WorstOf =>
+                spot_value * bivariate_normal_cdf(y1, -ratio_d, -rho1) +
+                    other_spot_value *
+                        bivariate_normal_cdf(y2, ratio_d - ratio_volatility * sqrt_t, -rho2) -
+                    strike_value * bivariate_normal_cdf(z1, z2, correlation),
+        }
+    };
+    let price = match option_type {
+        OptionType::Call => call,
+        // Put-call parity on the best or worst asset
+        OptionType::Put => strike_value - zero_strike_call + call,
+    };
+    F::from_f64(price)
+}
+
+// Upper bound on the number of jump counts summed over by merton_price
+const MAX_JUMP_TERMS: usize = 1000;
+
+/// Merton's price of a European option when the spot also jumps at the times of a Poisson
+/// process, `jump_intensity` times a year on average, the log of every jump being normal with
+/// mean `mean_log_jump` and standard deviation `log_jump_volatility`. The price is the sum of
+/// Black-Scholes prices given the number of jumps, weighted by the probability of that many
+/// jumps, until the weights are negligible.
+pub fn merton_price<F: Float>(
+    option_type: OptionType,
+    spot: F,
+    strike: F,
+    volatility: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F,
+    jump_intensity: F,
+    mean_log_jump: F,
+    log_jump_volatility: F
+) -> F {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        to_f64(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let (jump_intensity, mean_log_jump, log_jump_volatility) =
+        (jump_intensity.to_f64(), mean_log_jump.to_f64(), log_jump_volatility.to_f64());
+
+    // The log of the mean jump, and the expected number of jumps under the measure that has the
+    // spot as its numeraire
+    let log_mean_jump = mean_log_jump + 0.5 * log_jump_volatility * log_jump_volatility;
+    let mean_jump = log_mean_jump.exp();
+    let jump_rate = jump_intensity * mean_jump * years_to_expiry;
+
+    let mut weight = (-jump_rate).exp();
+    let mut total = 0.0;
+    for jumps in 0..MAX_JUMP_TERMS {
+        let n = jumps as f64;
+        // Given n jumps, the spot at expiry is lognormal with the jump variance spread over the
+        // life of the option, and the jumps moving its mean
+        let jump_volatility = (
+            volatility * volatility +
+            (n * log_jump_volatility * log_jump_volatility) / years_to_expiry
+        ).sqrt();
+        let jump_rate_n =
+            risk_free_rate - jump_intensity * (mean_jump - 1.0) + (n * log_mean_jump) / years_to_expiry;
+        total +=
+            weight *
+            price(option_type, spot, strike, jump_volatility, jump_rate_n, years_to_expiry, dividend_yield);
+
+        if n > jump_rate && weight < 1e-17 {
+            break;
+        }
+        weight *= jump_rate / (n + 1.0);
+    }
+    F::from_f64(total)
+}
+
+/// Hagan's approximation of the Black-Scholes implied volatility of an option on a forward
+/// `forward` in the SABR model, in which the forward follows dF = alpha F^beta dW, the volatility
+/// alpha starting at `alpha` and following d alpha = nu alpha dZ, with W and Z having
+/// correlation `rho`. Accurate when nu^2 times the years to expiry is small.
+pub fn sabr_implied_volatility<F: Float>(
+    forward: F,
+    strike: F,
+    years_to_expiry: F,
+    alpha: F,
+    beta: F,
+    rho: F,
+    nu: F
+) -> F {
+    let (forward, strike, years_to_expiry) = (forward.to_f64(), strike.to_f64(), years_to_expiry.to_f64());
+    let (alpha, beta, rho, nu) = (alpha.to_f64(), beta.to_f64(), rho.to_f64(), nu.to_f64());
+
+    let one_minus_beta = 1.0 - beta;
+    let log_moneyness = (forward / strike).ln();
+    // (F K)^((1 - beta) / 2), the backbone at the geometric mean of the forward and the strike
+    let backbone = (forward * strike).powf(0.5 * one_minus_beta);
+    let z = (nu / alpha) * backbone * log_moneyness;
+    // z / x(z), which tends to 1 at the money
+    let smile = if z.abs() < 1e-12 {
+        1.0
+    } else {
+        z / (((1.0 - 2.0 * rho * z + z * z).sqrt() + z - rho) / (1.0 - rho)).ln()
+    };
+    let denominator = backbone * (
+        1.0 +
+        one_minus_beta.powi(2) / 24.0 * log_moneyness.powi(2) +
+        one_minus_beta.powi(4) / 1920.0 * log_moneyness.powi(4)
+    );
+    let correction = 1.0 + (
+        one_minus_beta.powi(2) / 24.0 * alpha * alpha / (backbone * backbone) +
+        0.25 * rho * beta * nu * alpha / backbone +
+        (2.0 - 3.0 * rho * rho) / 24.0 * nu * nu
+    ) * years_to_expiry;
+    F::from_f64(alpha / denominator * smile * correction)
+}
+
+#[test]
+fn normal_cdf_matches_reference_values() {
+    // Reference values computed to full precision with mpmath
+    let cases = [
+        (0.0, 0.5),
+        (1.0, 0.841344746068543),
+        (-1.96, 0.024997895148220435),
+        (0.5, 0.6914624612740131),
+        (-5.7, 5.990371401063528e-9),
+        (-10.0, 7.619853024160525e-24),
+        (-3.0, 0.0013498980316300946),
+        (-8.0, 6.220960574271784e-16),
+        (-20.0, 2.7536241186062337e-89),
+    ];
+    for (x, expected) in cases {
+        let cdf = normal_cdf(x);
+        println!("normal_cdf({}) {} vs {}", x, cdf, expected);
+        assert!(((cdf - expected) / expected).abs() < 1e-14);
+        assert!((normal_cdf(-x) - (1.0 - expected)).abs() < 1e-15);
+    }
+}
+
+#[test]
+fn normal_pdf_matches_reference_values() {
+    assert!((normal_pdf(0.0) - 0.3989422804014327).abs() < 1e-16);
+    assert!((normal_pdf(-1.5) - 0.12951759566589174).abs() < 1e-16);
+}
+
+#[test]
+fn f64_prices_match_reference_values() {
+    let call: f64 = call_price(100.0, 100.0, 0.2, 0.05, 1.0, 0.0);
+    let put: f64 = put_price(100.0, 100.0, 0.2, 0.05, 1.0, 0.0);
+    println!("bs call {} put {}", call, put);
+    assert!((call - 10.450583572185565).abs() < 1e-12);
+    assert!((put - 5.573526022256968).abs() < 1e-12);
+}
+
+#[test]
+fn put_call_parity() {
+    let (spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield) =
+        (112.0, 110.0, 0.14, 0.12, 1.0, 0.02);
+    let call: f64 = call_price(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let put: f64 = put_price(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+    let forward =
+        spot * (-dividend_yield * years_to_expiry).exp() -
+        strike * (-risk_free_rate * years_to_expiry).exp();
+    assert!((call - put - forward).abs() < 1e-12);
+}
+
+#[test]
+fn dividend_rho_matches_finite_difference() {
+    let bump = 1e-6;
+    let call_diff: f64 =
+        (call_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02 + bump) -
+            call_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02 - bump)) /
+        (200.0 * bump);
+    let put_diff: f64 =
+        (put_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02 + bump) -
+            put_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02 - bump)) /
+        (200.0 * bump);
+    let call_rho: f64 = call_dividend_rho(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    let put_rho: f64 = put_dividend_rho(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    println!("bs dividend rho {} vs {}, {} vs {}", call_rho, call_diff, put_rho, put_diff);
+    assert!((call_rho - call_diff).abs() < 1e-6);
+    assert!((put_rho - put_diff).abs() < 1e-6);
+}
+
+#[test]
+fn f32_and_f64_agree() {
+    let price_f32: f32 = call_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    let price_f64: f64 = call_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    assert!(((price_f32 as f64) - price_f64).abs() < 1e-5);
+}
+
+#[test]
+fn barrier_prices_match_reference_values() {
+    // Values from Haug's The Complete Guide to Option Pricing Formulas
+    let cases = [
+        (OptionType::Call, BarrierType::DownAndOut, 90.0, 95.0, 9.0246),
+        (OptionType::Call, BarrierType::DownAndOut, 100.0, 95.0, 6.7924),
+        (OptionType::Call, BarrierType::DownAndIn, 90.0, 95.0, 7.7627),
+        (OptionType::Call, BarrierType::DownAndIn, 100.0, 95.0, 4.0109),
+        (OptionType::Call, BarrierType::UpAndOut, 90.0, 105.0, 2.6789),
+        (OptionType::Put, BarrierType::DownAndOut, 90.0, 95.0, 2.2798),
+    ];
+    for (option_type, barrier_type, strike, barrier, expected) in cases {
+        let price: f64 =
+            barrier_price(option_type, barrier_type, 100.0, strike, barrier, 3.0, 0.25, 0.08, 0.5, 0.04);
+        println!("{:?} {:?} barrier {} vs {}", option_type, barrier_type, price, expected);
+        assert!((price - expected).abs() < 5e-5);
+    }
+}
+
+#[test]
+fn knock_in_plus_knock_out_is_vanilla() {
+    for (strike, barrier) in [(90.0, 95.0), (100.0, 95.0), (90.0, 105.0), (110.0, 105.0)] {
+        let (in_type, out_type) = if barrier < 100.0 {
+            (BarrierType::DownAndIn, BarrierType::DownAndOut)
+        } else {
+            (BarrierType::UpAndIn, BarrierType::UpAndOut)
+        };
+        for option_type in [OptionType::Call, OptionType::Put] {
+            let knock_in: f64 =
+                barrier_price(option_type, in_type, 100.0, strike, barrier, 0.0, 0.3, 0.05, 1.0, 0.02);
+            let knock_out: f64 =
+                barrier_price(option_type, out_type, 100.0, strike, barrier, 0.0, 0.3, 0.05, 1.0, 0.02);
+            let vanilla: f64 = price(option_type, 100.0, strike, 0.3, 0.05, 1.0, 0.02);
+            assert!((knock_in + knock_out - vanilla).abs() < 1e-10);
+        }
+    }
+}
+
+#[test]
+fn lookback_prices_match_reference_values() {
+    // Value from Haug's The Complete Guide to Option Pricing Formulas
+    let call: f64 = floating_lookback_call_price(120.0, 100.0, 0.3, 0.1, 0.5, 0.06);
+    println!("bs floating lookback call {} vs 25.3533", call);
+    assert!((call - 25.3533).abs() < 1e-4);
+
+    // A fixed strike call struck below the highest spot is a floating strike put plus a
+    // forward, since max - strike = (max - spot at expiry) + (spot at expiry - strike)
+    for (strike, max_spot) in [(90.0, 100.0), (100.0, 100.0), (95.0, 110.0)] {
+        let fixed_call: f64 = fixed_lookback_call_price(100.0, strike, max_spot, 0.25, 0.05, 1.0, 0.02);
+        let floating_put: f64 = floating_lookback_put_price(100.0, max_spot, 0.25, 0.05, 1.0, 0.02);
+        let forward: f64 = 100.0 * (-0.02f64).exp() - strike * (-0.05f64).exp();
+        assert!((fixed_call - floating_put - forward).abs() < 1e-10);
+    }
+}
+
+#[test]
+fn lookback_prices_are_continuous_in_carry() {
+    // The formulas divide by the cost of carry, which must not blow up when it is zero
+    let at_zero: f64 = floating_lookback_call_price(100.0, 100.0, 0.25, 0.03, 1.0, 0.03);
+    let below: f64 = floating_lookback_call_price(100.0, 100.0, 0.25, 0.03, 1.0, 0.0301);
+    let above: f64 = floating_lookback_call_price(100.0, 100.0, 0.25, 0.03, 1.0, 0.0299);
+    println!("bs lookback at zero carry {} between {} and {}", at_zero, below, above);
+    assert!((at_zero - 0.5 * (below + above)).abs() < 1e-4);
+    let put: f64 = fixed_lookback_put_price(100.0, 100.0, 100.0, 0.25, 0.03, 1.0, 0.03);
+    assert!(put.is_finite() && put > put_price(100.0, 100.0, 0.25, 0.03, 1.0, 0.03));
+}
+
+#[test]
+fn digital_prices_add_up_to_vanilla() {
+    // A vanilla call is an asset-or-nothing call less a cash-or-nothing call paying the strike
+    let asset: f64 = asset_or_nothing_call_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    let cash: f64 = cash_or_nothing_call_price(100.0, 110.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    let gap: f64 = gap_call_price(100.0, 110.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    let call: f64 = call_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    assert!((asset - cash - call).abs() < 1e-12);
+    assert!((gap - call).abs() < 1e-12);
+    let gap_put: f64 = gap_put_price(100.0, 110.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    assert!((gap_put - put_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02)).abs() < 1e-12);
+
+    // A call and a put on the same strike always pay out exactly once
+    let cash_put: f64 = cash_or_nothing_put_price(100.0, 110.0, 1.0, 0.25, 0.05, 0.5, 0.02);
+    let cash_call: f64 = cash_or_nothing_call_price(100.0, 110.0, 1.0, 0.25, 0.05, 0.5, 0.02);
+    assert!((cash_call + cash_put - (-0.05f64 * 0.5).exp()).abs() < 1e-12);
+
+    // Value from Haug's The Complete Guide to Option Pricing Formulas
+    let gap: f64 = gap_call_price(50.0, 50.0, 57.0, 0.2, 0.09, 0.5, 0.0);
+    println!("bs gap call {} vs -0.0053", gap);
+    assert!((gap + 0.0053).abs() < 1e-4);
+}
+
+#[test]
+fn exchange_price_matches_call_and_parity() {
+    // A second asset without volatility that grows at the risk-free rate is a fixed strike
+    let exchange: f64 = exchange_price(100.0, 110.0, 0.25, 0.0, 0.3, 0.5, 0.02, 0.05);
+    let call: f64 = call_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    assert!((exchange - call).abs() < 1e-12);
+
+    // Exchanging one way less exchanging the other way is a forward on the difference
+    let forward: f64 = exchange_price(100.0, 90.0, 0.25, 0.3, -0.4, 1.0, 0.02, 0.01) -
+        exchange_price(90.0, 100.0, 0.3, 0.25, -0.4, 1.0, 0.01, 0.02);
+    let actual_forward = 100.0 * (-0.02f64).exp() - 90.0 * (-0.01f64).exp();
+    assert!((forward - actual_forward).abs() < 1e-12);
+
+    // Perfectly correlated assets with the same volatility never cross
+    let exchange: f64 = exchange_price(100.0, 90.0, 0.25, 0.25, 1.0, 1.0, 0.0, 0.0);
+    assert!((exchange - 10.0).abs() < 1e-12);
+}
+
+#[test]
+fn bivariate_normal_cdf_matches_reference_values() {
+    // At the origin the CDF is 1/4 + asin(correlation) / (2 pi)
+    for correlation in [-0.95, -0.5, 0.2, 0.8, 0.99] {
+        let actual = 0.25 + f64::asin(correlation) / (2.0 * std::f64::consts::PI);
+        assert!((bivariate_normal_cdf(0.0, 0.0, correlation) - actual).abs() < 1e-15);
+    }
+    // Values integrated numerically with Simpson's rule
+    for (x, y, correlation, actual) in [
+        (0.3, -0.5, 0.2, 0.217213249051),
+        (1.2, 0.4, -0.6, 0.545531618936),
+        (-0.7, 0.9, 0.8, 0.241484853898),
+        (-1.0, -0.3, 0.96, 0.158456453212),
+    ] {
+        let cdf = bivariate_normal_cdf(x, y, correlation);
+        println!("bivariate normal cdf ({}, {}, {}) {} vs {}", x, y, correlation, cdf, actual);
+        assert!((cdf - actual).abs() < 1e-11);
+    }
+    assert!((bivariate_normal_cdf(0.4, -0.2, 0.0) - normal_cdf(0.4) * normal_cdf(-0.2)).abs() < 1e-15);
+    assert!((bivariate_normal_cdf(0.4, 10.0, -0.5) - normal_cdf(0.4)).abs() < 1e-12);
+}
+
+#[test]
+fn rainbow_prices_add_up() {
+    // The best and the worst of two assets are the two assets
+    for option_type in [OptionType::Call, OptionType::Put] {
+        let rainbow = |rainbow_type| -> f64 {
+            rainbow_price(option_type, rainbow_type, 100.0, 95.0, 105.0, 0.25, 0.3, 0.4, 0.05, 1.0, 0.02, 0.01)
+        };
+        let vanillas =
+            price(option_type, 100.0, 105.0, 0.25, 0.05, 1.0, 0.02) +
+            price(option_type, 95.0, 105.0, 0.3, 0.05, 1.0, 0.01);
+        let total = rainbow(RainbowType::BestOf) + rainbow(RainbowType::WorstOf);
+        println!("bs rainbow {:?} best + worst {} vs {}", option_type, total, vanillas);
+        assert!((total - vanillas).abs() < 1e-9);
+    }
+    // A best-of call with a strike of 0 is worth the best asset
+    let best: f64 = rainbow_price(OptionType::Call, RainbowType::BestOf, 100.0, 95.0, 0.0, 0.25, 0.3, 0.4, 0.05, 1.0, 0.0, 0.0);
+    assert!((best - 95.0 - exchange_price(100.0, 95.0, 0.25, 0.3, 0.4, 1.0, 0.0, 0.0)).abs() < 1e-12);
+}
+
+#[test]
+fn merton_price_reduces_to_black_scholes() {
+    // Without jumps, or with jumps of size 1, the spot is lognormal
+    for option_type in [OptionType::Call, OptionType::Put] {
+        let expected = price(option_type, 100.0, 105.0, 0.2, 0.05, 1.0, 0.01);
+        let no_jumps: f64 = merton_price(option_type, 100.0, 105.0, 0.2, 0.05, 1.0, 0.01, 0.0, -0.1, 0.15);
+        let unit_jumps: f64 = merton_price(option_type, 100.0, 105.0, 0.2, 0.05, 1.0, 0.01, 2.0, 0.0, 0.0);
+        assert!((no_jumps - expected).abs() < 1e-12);
+        assert!((unit_jumps - expected).abs() < 1e-12);
+    }
+
+    // Jumps make both tails fatter, and calls and puts still satisfy put-call parity
+    let call: f64 = merton_price(OptionType::Call, 100.0, 130.0, 0.2, 0.05, 1.0, 0.01, 1.0, -0.1, 0.15);
+    let put: f64 = merton_price(OptionType::Put, 100.0, 130.0, 0.2, 0.05, 1.0, 0.01, 1.0, -0.1, 0.15);
+    let forward = 100.0 * (-0.01f64).exp() - 130.0 * (-0.05f64).exp();
+    assert!((call - put - forward).abs() < 1e-10);
+    assert!(call > call_price(100.0, 130.0, 0.2, 0.05, 1.0, 0.01));
+    let low_put: f64 = merton_price(OptionType::Put, 100.0, 70.0, 0.2, 0.05, 1.0, 0.01, 1.0, -0.1, 0.15);
+    assert!(low_put > put_price(100.0, 70.0, 0.2, 0.05, 1.0, 0.01));
+}
+
+#[test]
+fn sabr_implied_volatility_limits() {
+    // Equation (2.17a) of Hagan et al. (2002), Managing smile risk, evaluated independently to 40
+    // digits with mpmath
+    let cases = [(0.03, 0.26193350915263824), (0.05, 0.18357607399122796), (0.07, 0.17209595087725289)];
+    for (strike, expected) in cases {
+        let volatility: f64 = sabr_implied_volatility(0.05, strike, 2.0, 0.04, 0.5, -0.25, 0.45);
+        assert!((volatility - expected).abs() < 1e-12);
+    }
+
+    // Lognormal when beta is 1 and the volatility is constant
+    let lognormal: f64 = sabr_implied_volatility(100.0, 120.0, 1.0, 0.2, 1.0, 0.3, 0.0);
+    assert!((lognormal - 0.2).abs() < 1e-12);
+
+    // Continuous at the money
+    let at_the_money: f64 = sabr_implied_volatility(100.0, 100.0, 1.0, 2.0, 0.5, -0.3, 0.4);
+    let near_the_money: f64 = sabr_implied_volatility(100.0, 100.0 * (1.0 + 1e-7), 1.0, 2.0, 0.5, -0.3, 0.4);
+    assert!((at_the_money - near_the_money).abs() < 1e-7);
+}
+
+#[test]
+fn black76_matches_haug_example() {
+    // Haug's example of an option on a futures at 19, at the money, with 9 months to expiry
+    let call: f64 = black76_call_price(19.0, 19.0, 0.28, 0.1, 0.75);
+    let put: f64 = black76_put_price(19.0, 19.0, 0.28, 0.1, 0.75);
+    assert!((call - 1.7011).abs() < 5e-5);
+    assert!((put - call).abs() < 1e-12);
+
+    // Put-call parity on the discounted futures price, and the Greeks against finite differences
+    let (forward, strike, volatility, rate, years) = (95.0, 100.0, 0.3, 0.04, 1.5);
+    let call: f64 = black76_call_price(forward, strike, volatility, rate, years);
+    let put: f64 = black76_put_price(forward, strike, volatility, rate, years);
+    assert!((call - put - (-rate * years).exp() * (forward - strike)).abs() < 1e-12);
+
+    let bump = 1e-4;
+    let call_at = |forward: f64, volatility: f64, rate: f64, years: f64| {
+        black76_call_price(forward, strike, volatility, rate, years)
+    };
+    let delta = (call_at(forward + bump, volatility, rate, years) - call_at(forward - bump, volatility, rate, years)) /
+        (2.0 * bump);
+    let gamma = (call_at(forward + bump, volatility, rate, years) - 2.0 * call +
+        call_at(forward - bump, volatility, rate, years)) / (bump * bump);
+    let vega = (call_at(forward, volatility + bump, rate, years) - call_at(forward, volatility - bump, rate, years)) /
+        (200.0 * bump);
+    let rho = (call_at(forward, volatility, rate + bump, years) - call_at(forward, volatility, rate - bump, years)) /
+        (200.0 * bump);
+    let theta = (call_at(forward, volatility, rate, years - bump) - call_at(forward, volatility, rate, years + bump)) /
+        (2.0 * bump);
+    assert!((black76_call_delta(forward, strike, volatility, rate, years) - delta).abs() < 1e-7);
+    assert!((black76_gamma(forward, strike, volatility, rate, years) - gamma).abs() < 1e-5);
+    assert!((black76_vega(forward, strike, volatility, rate, years) - vega).abs() < 1e-7);
+    assert!((black76_call_rho(forward, strike, volatility, rate, years) - rho).abs() < 1e-7);
+    assert!((black76_call_theta(forward, strike, volatility, rate, years) - theta).abs() < 1e-7);
+    let put_delta: f64 = black76_put_delta(forward, strike, volatility, rate, years);
+    let put_rho: f64 = black76_put_rho(forward, strike, volatility, rate, years);
+    let put_theta: f64 = black76_put_theta(forward, strike, volatility, rate, years);
+    assert!((black76_call_delta(forward, strike, volatility, rate, years) - put_delta - (-rate * years).exp()).abs() < 1e-12);
+    assert!((put_rho + years * put / 100.0).abs() < 1e-12);
+    let call_theta: f64 = black76_call_theta(forward, strike, volatility, rate, years);
+    assert!((call_theta - put_theta - rate * (call - put)).abs() < 1e-10);
+}
+
+#[test]
+fn bachelier_prices_and_greeks() {
+    // At the money forward, the price is the discounted mean of the positive part of a normal
+    let at_the_money: f64 = bachelier_call_price(100.0, 100.0 * (0.03f64).exp(), 20.0, 0.05, 1.0, 0.02);
+    assert!((at_the_money - (-0.05f64).exp() * 20.0 / (2.0 * std::f64::consts::PI).sqrt()).abs() < 1e-12);
+
+    // Negative spots and strikes, with put-call parity on the forward
+    let (spot, strike, volatility, rate, years, dividend_yield) = (-2.0, -1.0, 3.0, 0.04, 2.0, 0.01);
+    let call: f64 = bachelier_call_price(spot, strike, volatility, rate, years, dividend_yield);
+    let put: f64 = bachelier_put_price(spot, strike, volatility, rate, years, dividend_yield);
+    let forward = spot * ((rate - dividend_yield) * years).exp();
+    assert!((call - put - (-rate * years).exp() * (forward - strike)).abs() < 1e-12);
+
+    // The Greeks against central differences
+    let bump = 1e-4;
+    type Formula = fn(f64, f64, f64, f64, f64, f64) -> f64;
+    let cases: [(Formula, Formula, Formula, Formula); 2] = [
+        (bachelier_call_price, bachelier_call_delta, bachelier_call_rho, bachelier_call_theta),
+        (bachelier_put_price, bachelier_put_delta, bachelier_put_rho, bachelier_put_theta),
+    ];
+    for (price, delta, rho, theta) in cases {
+        let at = |spot: f64, volatility: f64, rate: f64, years: f64| price(spot, strike, volatility, rate, years, dividend_yield);
+        let base = at(spot, volatility, rate, years);
+        let expected_delta = (at(spot + bump, volatility, rate, years) - at(spot - bump, volatility, rate, years)) / (2.0 * bump);
+        let expected_gamma = (at(spot + bump, volatility, rate, years) - 2.0 * base + at(spot - bump, volatility, rate, years)) /
+            (bump * bump);
+        let expected_vega = (at(spot, volatility + bump, rate, years) - at(spot, volatility - bump, rate, years)) / (200.0 * bump);
+        let expected_rho = (at(spot, volatility, rate + bump, years) - at(spot, volatility, rate - bump, years)) / (200.0 * bump);
+        let expected_theta = (at(spot, volatility, rate, years - bump) - at(spot, volatility, rate, years + bump)) / (2.0 * bump);
+        assert!((delta(spot, strike, volatility, rate, years, dividend_yield) - expected_delta).abs() < 1e-7);
+        assert!((bachelier_gamma(spot, strike, volatility, rate, years, dividend_yield) - expected_gamma).abs() < 1e-5);
+        assert!((bachelier_vega(spot, strike, volatility, rate, years, dividend_yield) - expected_vega).abs() < 1e-7);
+        assert!((rho(spot, strike, volatility, rate, years, dividend_yield) - expected_rho).abs() < 1e-7);
+        assert!((theta(spot, strike, volatility, rate, years, dividend_yield) - expected_theta).abs() < 1e-7);
+    }
+}
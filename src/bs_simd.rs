@@ -0,0 +1,299 @@
+// Black-scholes prices and Greeks of 8 options at a time on f32x8 lanes, for pricing whole
+// option chains. Matches the scalar functions in bs to f32 precision.
+
+use wide::*;
+use crate::params::OptionType;
+
+/// Price and Greeks of 8 options, one per lane, in the same units as the scalar functions in
+/// [`crate::bs`]: Vega, Rho and dividend Rho per 1% change, Theta per year
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GreeksX8 {
+    pub price: f32x8,
+    pub delta: f32x8,
+    pub gamma: f32x8,
+    pub vega: f32x8,
+    pub rho: f32x8,
+    pub theta: f32x8,
+    pub dividend_rho: f32x8,
+}
+
+/// Prices and Greeks of a batch of options, one element per option, in the same units as
+/// [`GreeksX8`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BatchGreeks {
+    pub price: Vec<f32>,
+    pub delta: Vec<f32>,
+    pub gamma: Vec<f32>,
+    pub vega: Vec<f32>,
+    pub rho: Vec<f32>,
+    pub theta: Vec<f32>,
+    pub dividend_rho: Vec<f32>,
+}
+
+// Coefficients of the rational function in West's normal CDF, highest power first
+const WEST_NUMERATOR: [f64; 7] = [
+    3.52624965998911e-2,
+    0.700383064443688,
+    6.37396220353165,
+    33.912866078383,
+    112.079291497871,
+    221.213596169931,
+    220.206867912376,
+];
+const WEST_DENOMINATOR: [f64; 8] = [
+    8.83883476483184e-2,
+    1.75566716318264,
+    16.064177579207,
+    86.7807322029461,
+    296.564248779674,
+    637.333633378831,
+    793.826512519948,
+    440.413735824752,
+];
+
+/// Cumulative distribution function of the standard normal distribution on every lane, using
+/// West's version of Hart's algorithm, which needs no branches apart from the far tail
+pub fn normal_cdf_x8(x: f32x8) -> f32x8 {
+    let x_abs = x.abs();
+    let e = (-x_abs * x_abs * f32x8::splat(0.5)).exp();
+
+    let mut numerator = f32x8::splat(0.0);
+    for coefficient in WEST_NUMERATOR {
+        numerator = numerator.mul_add(x_abs, f32x8::splat(coefficient as f32));
+    }
+    let mut denominator = f32x8::splat(0.0);
+    for coefficient in WEST_DENOMINATOR {
+        denominator = denominator.mul_add(x_abs, f32x8::splat(coefficient as f32));
+    }
+    let tail = (e * numerator) / denominator;
+
+    // Continued fraction for the far tail
+    let b = x_abs + f32x8::splat(0.65);
+    let b = x_abs + f32x8::splat(4.0) / b;
+    let b = x_abs + f32x8::splat(3.0) / b;
+    let b = x_abs + f32x8::splat(2.0) / b;
+    let b = x_abs + f32x8::splat(1.0) / b;
+    let far_tail = e / (b * f32x8::splat((2.0 * std::f32::consts::PI).sqrt()));
+
+    let tail = x_abs.cmp_lt(f32x8::splat(5.0 * std::f32::consts::SQRT_2)).blend(tail, far_tail);
+    x.cmp_gt(f32x8::splat(0.0)).blend(f32x8::splat(1.0) - tail, tail)
+}
+
+/// Probability density function of the standard normal distribution on every lane
+pub fn normal_pdf_x8(x: f32x8) -> f32x8 {
+    (-x * x * f32x8::splat(0.5)).exp() / f32x8::splat((2.0 * std::f32::consts::PI).sqrt())
+}
+
+/// Price and all Greeks of 8 European options of the same type in one vectorized pass
+pub fn greeks_x8(
+    option_type: OptionType,
+    spot: f32x8,
+    strike: f32x8,
+    volatility: f32x8,
+    risk_free_rate: f32x8,
+    years_to_expiry: f32x8,
+    dividend_yield: f32x8
+) -> GreeksX8 {
+    // 1.0 for calls and -1.0 for puts, so that N(call_mult * d) is N(d) for calls and N(-d) for puts
+    let call_mult = f32x8::splat(option_type.call_mult() as f32);
+    let hundred = f32x8::splat(100.0);
+
+    let sqrt_time = years_to_expiry.sqrt();
+    let volatility_sqrt_time = volatility * sqrt_time;
+    let d1 =
+        ((spot / strike).ln() +
+            (risk_free_rate - dividend_yield + volatility * volatility * f32x8::splat(0.5)) *
+                years_to_expiry) /
+        volatility_sqrt_time;
+    let d2 = d1 - volatility_sqrt_time;
+
+    let spot_discounted = spot * (-dividend_yield * years_to_expiry).exp();
+    let strike_discounted = strike * (-risk_free_rate * years_to_expiry).exp();
+    let nd1 = normal_cdf_x8(call_mult * d1);
+    let nd2 = normal_cdf_x8(call_mult * d2);
+    let pdf_d1 = normal_pdf_x8(d1);
+
+    let theta =
+        -(spot_discounted * pdf_d1 * volatility) / (f32x8::splat(2.0) * sqrt_time) -
+        call_mult * risk_free_rate * strike_discounted * nd2 +
+        call_mult * dividend_yield * spot_discounted * nd1;
+
+    GreeksX8 {
+        price: call_mult * (spot_discounted * nd1 - strike_discounted * nd2),
+        delta: call_mult * (spot_discounted / spot) * nd1,
+        gamma: (spot_discounted * pdf_d1) / (spot * spot * volatility_sqrt_time),
+        vega: (spot_discounted * pdf_d1 * sqrt_time) / hundred,
+        rho: (call_mult * strike_discounted * years_to_expiry * nd2) / hundred,
+        theta,
+        dividend_rho: -(call_mult * spot_discounted * years_to_expiry * nd1) / hundred,
+    }
+}
+
+// Loads values[start..end] into lanes, for end - start <= 8. The lanes past the end are padded
+// with 1.0, which is a valid value for every parameter, and must be dropped by the caller.
+pub(crate) fn chunk_lanes(values: &[f32], start: usize, end: usize) -> f32x8 {
+    let mut lanes = [1.0; 8];
+    lanes[..end - start].copy_from_slice(&values[start..end]);
+    f32x8::from(lanes)
+}
+
+/// Price and all Greeks of a batch of European options of the same type, e.g. an option chain,
+/// 8 options at a time. Element `i` of the result is the option with element `i` of every
+/// parameter slice. Panics if the slices have different lengths.
+pub fn batch_greeks(
+    option_type: OptionType,
+    spot: &[f32],
+    strike: &[f32],
+    volatility: &[f32],
+    risk_free_rate: &[f32],
+    years_to_expiry: &[f32],
+    dividend_yield: &[f32]
+) -> BatchGreeks {
+    let len = spot.len();
+    for other in [strike, volatility, risk_free_rate, years_to_expiry, dividend_yield] {
+        assert_eq!(other.len(), len, "all parameter slices must have the same length");
+    }
+
+    let mut result = BatchGreeks {
+        price: vec![0.0; len],
+        delta: vec![0.0; len],
+        gamma: vec![0.0; len],
+        vega: vec![0.0; len],
+        rho: vec![0.0; len],
+        theta: vec![0.0; len],
+        dividend_rho: vec![0.0; len],
+    };
+
+    for start in (0..len).step_by(8) {
+        let end = (start + 8).min(len);
+        let lanes = |values: &[f32]| chunk_lanes(values, start, end);
+
+        let greeks = greeks_x8(
+            option_type,
+            lanes(spot),
+            lanes(strike),
+            lanes(volatility),
+            lanes(risk_free_rate),
+            lanes(years_to_expiry),
+            lanes(dividend_yield)
+        );
+
+        for (output, lanes) in [
+            (&mut result.price, greeks.price),
+            (&mut result.delta, greeks.delta),
+            (&mut result.gamma, greeks.gamma),
+            (&mut result.vega, greeks.vega),
+            (&mut result.rho, greeks.rho),
+            (&mut result.theta, greeks.theta),
+            (&mut result.dividend_rho, greeks.dividend_rho),
+        ] {
+            output[start..end].copy_from_slice(&lanes.to_array()[..end - start]);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+use crate::bs;
+
+#[cfg(test)]
+fn assert_close(name: &str, simd: f32, scalar: f64) {
+    println!("bs_simd {} {} vs {}", name, simd, scalar);
+    assert!(((simd as f64) - scalar).abs() <= 1e-4 * scalar.abs().max(1.0));
+}
+
+#[test]
+fn normal_cdf_x8_matches_scalar() {
+    for start in [-40.0, -8.0, -4.0, -1.0, 0.5, 4.0] {
+        let x = f32x8::splat(start) + f32x8::from([0.0, 0.1, 0.3, 0.7, 1.1, 1.7, 2.3, 3.1]);
+        let cdf = normal_cdf_x8(x).to_array();
+        for (lane, &x) in x.to_array().iter().enumerate() {
+            let expected = bs::normal_cdf(x as f64);
+            assert!(((cdf[lane] as f64) - expected).abs() <= 1e-6 * expected.max(1e-30));
+        }
+    }
+}
+
+#[test]
+fn greeks_x8_match_scalar() {
+    let spot = f32x8::from([50.0, 80.0, 95.0, 100.0, 105.0, 120.0, 150.0, 300.0]);
+    let strike = f32x8::from([110.0, 100.0, 100.0, 110.0, 100.0, 90.0, 100.0, 270.0]);
+    let volatility = f32x8::from([0.25, 0.2, 0.3, 0.25, 0.14, 0.5, 0.2, 0.2]);
+    let risk_free_rate = f32x8::from([0.05, 0.0, 0.03, 0.05, 0.12, -0.01, 0.05, 0.09]);
+    let years_to_expiry = f32x8::from([0.5, 1.0, 0.1, 0.5, 1.0, 2.0, 0.25, 1.0]);
+    let dividend_yield = f32x8::from([0.02, 0.0, 0.01, 0.02, 0.02, 0.0, 0.03, 0.0]);
+
+    for option_type in [OptionType::Call, OptionType::Put] {
+        let greeks = greeks_x8(
+            option_type,
+            spot,
+            strike,
+            volatility,
+            risk_free_rate,
+            years_to_expiry,
+            dividend_yield
+        );
+        for lane in 0..8 {
+            let params: [f64; 6] = [
+                spot.to_array()[lane] as f64,
+                strike.to_array()[lane] as f64,
+                volatility.to_array()[lane] as f64,
+                risk_free_rate.to_array()[lane] as f64,
+                years_to_expiry.to_array()[lane] as f64,
+                dividend_yield.to_array()[lane] as f64,
+            ];
+            let [s, k, v, r, t, q] = params;
+            let call = option_type == OptionType::Call;
+            let scalar: [(&str, f32x8, f64); 7] = [
+                ("price", greeks.price, bs::price(option_type, s, k, v, r, t, q)),
+                ("delta", greeks.delta, if call { bs::call_delta(s, k, v, r, t, q) } else { bs::put_delta(s, k, v, r, t, q) }),
+                ("gamma", greeks.gamma, bs::gamma(s, k, v, r, t, q)),
+                ("vega", greeks.vega, bs::vega(s, k, v, r, t, q)),
+                ("rho", greeks.rho, if call { bs::call_rho(s, k, v, r, t, q) } else { bs::put_rho(s, k, v, r, t, q) }),
+                ("theta", greeks.theta, if call { bs::call_theta(s, k, v, r, t, q) } else { bs::put_theta(s, k, v, r, t, q) }),
+                ("dividend rho", greeks.dividend_rho, if call { bs::call_dividend_rho(s, k, v, r, t, q) } else { bs::put_dividend_rho(s, k, v, r, t, q) }),
+            ];
+            for (name, simd, expected) in scalar {
+                assert_close(name, simd.to_array()[lane], expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn batch_greeks_handles_partial_chunks() {
+    // 19 strikes, so the last chunk only has 3 options
+    let strike: Vec<f32> = (0..19).map(|i| 80.0 + 2.5 * (i as f32)).collect();
+    let spot = vec![100.0; strike.len()];
+    let volatility = vec![0.25; strike.len()];
+    let risk_free_rate = vec![0.05; strike.len()];
+    let years_to_expiry = vec![0.5; strike.len()];
+    let dividend_yield = vec![0.02; strike.len()];
+
+    let greeks = batch_greeks(
+        OptionType::Put,
+        &spot,
+        &strike,
+        &volatility,
+        &risk_free_rate,
+        &years_to_expiry,
+        &dividend_yield
+    );
+    assert_eq!(greeks.price.len(), 19);
+    assert_eq!(greeks.dividend_rho.len(), 19);
+    for (i, &k) in strike.iter().enumerate() {
+        let k = k as f64;
+        assert_close("chain price", greeks.price[i], bs::put_price(100.0, k, 0.25, 0.05, 0.5, 0.02));
+        assert_close("chain delta", greeks.delta[i], bs::put_delta(100.0, k, 0.25, 0.05, 0.5, 0.02));
+        assert_close("chain theta", greeks.theta[i], bs::put_theta(100.0, k, 0.25, 0.05, 0.5, 0.02));
+    }
+
+    assert_eq!(batch_greeks(OptionType::Call, &[], &[], &[], &[], &[], &[]), BatchGreeks::default());
+}
+
+#[test]
+#[should_panic(expected = "all parameter slices must have the same length")]
+fn batch_greeks_rejects_mismatched_slices() {
+    batch_greeks(OptionType::Call, &[100.0, 100.0], &[110.0], &[0.25], &[0.05], &[0.5], &[0.02]);
+}
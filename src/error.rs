@@ -0,0 +1,201 @@
+// Errors returned when the pricers are given parameters they cannot price with
+
+use std::fmt;
+use crate::params::Greek;
+
+/// Why a set of pricing parameters was rejected
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PricingError {
+    /// A required parameter was not given to a builder
+    MissingParameter(&'static str),
+    /// The spot price is not a positive, finite number
+    InvalidSpot(f64),
+    /// The strike is negative or not finite
+    InvalidStrike(f64),
+    /// The volatility is not a positive, finite number
+    InvalidVolatility(f64),
+    /// The time to expiry is not a positive, finite number of years
+    InvalidYearsToExpiry(f64),
+    /// The risk-free rate is not finite
+    InvalidRiskFreeRate(f64),
+    /// The dividend yield is not finite
+    InvalidDividendYield(f64),
+    /// Fewer steps than the simulation needs
+    TooFewSteps {
+        steps: usize,
+        min_steps: usize,
+    },
+    /// Fewer trials than the simulation needs
+    TooFewTrials {
+        num_trials: usize,
+        min_trials: usize,
+    },
+    /// The bump used for a finite difference Greek is not positive, or it is so large that
+    /// the bumped parameter becomes invalid
+    InvalidBump {
+        greek: Greek,
+        bump: f64,
+    },
+    /// The amount an option pays is not finite
+    InvalidPayoffAmount(f64),
+    /// The barrier is not a positive, finite number
+    InvalidBarrier(f64),
+    /// The rebate is negative or not finite
+    InvalidRebate(f64),
+    /// An averaging date is not after today, or is after expiry
+    InvalidAveragingDate(f64),
+    /// The degree of the regression basis is zero or too high to regress on reliably
+    InvalidBasisDegree {
+        degree: usize,
+        max_degree: usize,
+    },
+    /// Fewer underlyings than the option needs
+    TooFewAssets {
+        num_assets: usize,
+        min_assets: usize,
+    },
+    /// A per-asset parameter or a row of the correlation matrix is given for a different
+    /// number of assets than there are spots
+    AssetCountMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    /// A correlation is outside [-1, 1], differs from its transpose, or is not 1 on the
+    /// diagonal
+    InvalidCorrelation {
+        row: usize,
+        column: usize,
+        correlation: f64,
+    },
+    /// The correlation matrix is not positive-definite, so no assets can be correlated by it
+    CorrelationNotPositiveDefinite,
+    /// A basket weight is negative or not finite, or all the weights are zero
+    InvalidWeight(f64),
+    /// The speed of mean reversion of the variance is not a positive, finite number
+    InvalidMeanReversion(f64),
+    /// The long-run variance is not a positive, finite number
+    InvalidLongRunVariance(f64),
+    /// The volatility of the volatility is not a positive, finite number
+    InvalidVolOfVol(f64),
+    /// The correlation of the volatility with the spot is outside [-1, 1]
+    InvalidVolCorrelation(f64),
+    /// The average number of jumps a year is negative or not finite
+    InvalidJumpIntensity(f64),
+    /// The mean of the log of the jumps is not finite
+    InvalidJumpMean(f64),
+    /// The standard deviation of the log of the jumps is negative or not finite
+    InvalidJumpVolatility(f64),
+    /// The exponent of the forward in the SABR model is outside [0, 1]
+    InvalidBeta(f64),
+    /// Fewer expiries or strikes in a volatility surface than it needs
+    TooFewGridPoints {
+        num_points: usize,
+        min_points: usize,
+    },
+    /// A volatility surface has a different number of rows than expiries, or of volatilities
+    /// in a row than strikes
+    GridSizeMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    /// The expiries or strikes of a volatility surface are not strictly increasing
+    UnsortedGrid(f64),
+    /// Dupire's formula gives a local variance that is not positive, as an implied volatility
+    /// surface with calendar or butterfly arbitrage does
+    InvalidLocalVariance {
+        years_to_expiry: f64,
+        strike: f64,
+        local_variance: f64,
+    },
+    /// An option price outside the no-arbitrage bounds, which no volatility can produce
+    ArbitrageBoundsViolated {
+        price: f64,
+        lower_bound: f64,
+        upper_bound: f64,
+    },
+}
+
+impl fmt::Display for PricingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PricingError::MissingParameter(name) => write!(f, "missing required parameter {}", name),
+            PricingError::InvalidSpot(spot) => write!(f, "spot must be positive, got {}", spot),
+            PricingError::InvalidStrike(strike) =>
+                write!(f, "strike must not be negative, got {}", strike),
+            PricingError::InvalidVolatility(volatility) =>
+                write!(f, "volatility must be positive, got {}", volatility),
+            PricingError::InvalidYearsToExpiry(years_to_expiry) =>
+                write!(f, "time to expiry must be positive, got {} years", years_to_expiry),
+            PricingError::InvalidRiskFreeRate(risk_free_rate) =>
+                write!(f, "risk-free rate must be finite, got {}", risk_free_rate),
+            PricingError::InvalidDividendYield(dividend_yield) =>
+                write!(f, "dividend yield must be finite, got {}", dividend_yield),
+            PricingError::TooFewSteps { steps, min_steps } =>
+                write!(f, "at least {} steps are required, got {}", min_steps, steps),
+            PricingError::TooFewTrials { num_trials, min_trials } =>
+                write!(f, "at least {} trials are required, got {}", min_trials, num_trials),
+            PricingError::InvalidBump { greek, bump } =>
+                write!(f, "invalid bump {} for {:?}", bump, greek),
+            PricingError::InvalidPayoffAmount(amount) =>
+                write!(f, "payoff amount must be finite, got {}", amount),
+            PricingError::InvalidBarrier(barrier) =>
+                write!(f, "barrier must be positive, got {}", barrier),
+            PricingError::InvalidRebate(rebate) =>
+                write!(f, "rebate must be non-negative, got {}", rebate),
+            PricingError::InvalidAveragingDate(date) =>
+                write!(f, "averaging date must be after today and on or before expiry, got {}", date),
+            PricingError::InvalidBasisDegree { degree, max_degree } =>
+                write!(f, "basis degree must be between 1 and {}, got {}", max_degree, degree),
+            PricingError::TooFewAssets { num_assets, min_assets } =>
+                write!(f, "at least {} assets are required, got {}", min_assets, num_assets),
+            PricingError::AssetCountMismatch { expected, actual } =>
+                write!(f, "expected parameters for {} assets, got {}", expected, actual),
+            PricingError::InvalidCorrelation { row, column, correlation } =>
+                write!(f, "invalid correlation {} at ({}, {})", correlation, row, column),
+            PricingError::CorrelationNotPositiveDefinite =>
+                write!(f, "correlation matrix is not positive-definite"),
+            PricingError::InvalidWeight(weight) =>
+                write!(f, "weights must be non-negative and not all zero, got {}", weight),
+            PricingError::InvalidMeanReversion(mean_reversion) =>
+                write!(f, "mean reversion must be positive, got {}", mean_reversion),
+            PricingError::InvalidLongRunVariance(long_run_variance) =>
+                write!(f, "long-run variance must be positive, got {}", long_run_variance),
+            PricingError::InvalidVolOfVol(vol_of_vol) =>
+                write!(f, "volatility of volatility must be positive, got {}", vol_of_vol),
+            PricingError::InvalidVolCorrelation(correlation) =>
+                write!(f, "volatility correlation must be between -1 and 1, got {}", correlation),
+            PricingError::InvalidJumpIntensity(intensity) =>
+                write!(f, "jump intensity must be non-negative, got {}", intensity),
+            PricingError::InvalidJumpMean(mean) =>
+                write!(f, "mean log jump must be finite, got {}", mean),
+            PricingError::InvalidJumpVolatility(volatility) =>
+                write!(f, "jump volatility must be non-negative, got {}", volatility),
+            PricingError::InvalidBeta(beta) =>
+                write!(f, "beta must be between 0 and 1, got {}", beta),
+            PricingError::TooFewGridPoints { num_points, min_points } =>
+                write!(f, "at least {} grid points are required, got {}", min_points, num_points),
+            PricingError::GridSizeMismatch { expected, actual } =>
+                write!(f, "expected {} grid points, got {}", expected, actual),
+            PricingError::UnsortedGrid(point) =>
+                write!(f, "grid points must be strictly increasing, got {} out of order", point),
+            PricingError::InvalidLocalVariance { years_to_expiry, strike, local_variance } =>
+                write!(
+                    f,
+                    "local variance must be positive, got {} at strike {} and {} years",
+                    local_variance,
+                    strike,
+                    years_to_expiry
+                ),
+            PricingError::ArbitrageBoundsViolated { price, lower_bound, upper_bound } =>
+                write!(
+                    f,
+                    "price {} is outside the no-arbitrage bounds ({}, {})",
+                    price,
+                    lower_bound,
+                    upper_bound
+                ),
+        }
+    }
+}
+
+impl std::error::Error for PricingError {}
@@ -0,0 +1,264 @@
+// Float types the pricers are generic over, so that the precision can be chosen per use case.
+// f32 parameters are simulated on f32x8 lanes and f64 parameters on f64x4 lanes.
+
+use std::fmt::{ Debug, Display };
+use std::ops::{ Add, Div, Mul, Neg, Sub };
+use wide::*;
+use simd_rand::portable::*;
+use crate::rand32x8::{ get_rand_uniform_f32x8, get_rand_uniform_f64x4_pair };
+
+/// A scalar float, f32 or f64, used for the pricing parameters and results
+pub trait Float: Copy +
+    PartialOrd +
+    Debug +
+    Display +
+    Send +
+    Sync +
+    'static +
+    Add<Output = Self> +
+    Sub<Output = Self> +
+    Mul<Output = Self> +
+    Div<Output = Self> +
+    Neg<Output = Self> {
+    /// The SIMD type the Monte Carlo simulations run on at this precision
+    type Lanes: SimdFloat<Scalar = Self>;
+
+    fn from_f64(x: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn is_finite(self) -> bool;
+    fn abs(self) -> Self;
+    fn signum(self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn sqrt(self) -> Self;
+}
+
+macro_rules! impl_float {
+    ($float:ty, $lanes:ty) => {
+        impl Float for $float {
+            type Lanes = $lanes;
+
+            #[inline(always)]
+            fn from_f64(x: f64) -> Self {
+                x as $float
+            }
+
+            #[inline(always)]
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            #[inline(always)]
+            fn is_finite(self) -> bool {
+                <$float>::is_finite(self)
+            }
+
+            #[inline(always)]
+            fn abs(self) -> Self {
+                <$float>::abs(self)
+            }
+
+            #[inline(always)]
+            fn signum(self) -> Self {
+                <$float>::signum(self)
+            }
+
+            #[inline(always)]
+            fn exp(self) -> Self {
+                <$float>::exp(self)
+            }
+
+            #[inline(always)]
+            fn ln(self) -> Self {
+                <$float>::ln(self)
+            }
+
+            #[inline(always)]
+            fn sqrt(self) -> Self {
+                <$float>::sqrt(self)
+            }
+        }
+    };
+}
+
+impl_float!(f32, f32x8);
+impl_float!(f64, f64x4);
+
+/// A SIMD vector of floats that a batch of Monte Carlo trials is simulated on, one trial
+/// per lane. Masks are vectors of the same type with all bits of the selected lanes set,
+/// as returned by `cmp_lt`.
+pub trait SimdFloat: Copy +
+    Send +
+    Sync +
+    'static +
+    Add<Output = Self> +
+    Sub<Output = Self> +
+    Mul<Output = Self> +
+    Div<Output = Self> +
+    Neg<Output = Self> {
+    type Scalar: Float<Lanes = Self>;
+
+    /// Number of lanes, i.e. the number of trials simulated per batch
+    const LANES: usize;
+
+    fn splat(x: f64) -> Self;
+    /// The vector [0.0, 1.0, 2.0, ...]
+    fn lane_index() -> Self;
+    /// Two vectors of uniformly distributed random numbers in [0, 1)
+    fn uniform_pair(rng: &mut Xoshiro256PlusPlusX8) -> (Self, Self);
+    /// Adds up the lanes in f64
+    fn sum_lanes(self) -> f64;
+    /// Writes the lanes as f64 to the first `LANES` elements of `out`
+    fn store_f64(self, out: &mut [f64]);
+
+    fn mul_add(self, m: Self, a: Self) -> Self;
+    fn mul_sub(self, m: Self, s: Self) -> Self;
+    fn fast_max(self, rhs: Self) -> Self;
+    fn fast_min(self, rhs: Self) -> Self;
+    fn cmp_lt(self, rhs: Self) -> Self;
+    /// Lanes of `t` where the mask `self` is set, lanes of `f` elsewhere
+    fn blend(self, t: Self, f: Self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn sin(self) -> Self;
+    fn sin_cos(self) -> (Self, Self);
+}
+
+macro_rules! impl_simd_float {
+    ($lanes:ty, $float:ty, $num_lanes:expr) => {
+        impl SimdFloat for $lanes {
+            type Scalar = $float;
+
+            const LANES: usize = $num_lanes;
+
+            #[inline(always)]
+            fn splat(x: f64) -> Self {
+                <$lanes>::splat(x as $float)
+            }
+
+            #[inline(always)]
+            fn lane_index() -> Self {
+                let mut index = [0.0; $num_lanes];
+                for (lane, value) in index.iter_mut().enumerate() {
+                    *value = lane as $float;
+                }
+                <$lanes>::from(index)
+            }
+
+            #[inline(always)]
+            fn uniform_pair(rng: &mut Xoshiro256PlusPlusX8) -> (Self, Self) {
+                <$lanes as UniformPair>::uniform_pair(rng)
+            }
+
+            #[inline(always)]
+            fn sum_lanes(self) -> f64 {
+                self.to_array()
+                    .iter()
+                    .map(|&x| x as f64)
+                    .sum()
+            }
+
+            #[inline(always)]
+            fn store_f64(self, out: &mut [f64]) {
+                for (out, x) in out.iter_mut().zip(self.to_array()) {
+                    *out = x as f64;
+                }
+            }
+
+            #[inline(always)]
+            fn mul_add(self, m: Self, a: Self) -> Self {
+                <$lanes>::mul_add(self, m, a)
+            }
+
+            #[inline(always)]
+            fn mul_sub(self, m: Self, s: Self) -> Self {
+                <$lanes>::mul_sub(self, m, s)
+            }
+
+            #[inline(always)]
+            fn fast_max(self, rhs: Self) -> Self {
+                <$lanes>::fast_max(self, rhs)
+            }
+
+            #[inline(always)]
+            fn fast_min(self, rhs: Self) -> Self {
+                <$lanes>::fast_min(self, rhs)
+            }
+
+            #[inline(always)]
+            fn cmp_lt(self, rhs: Self) -> Self {
+                CmpLt::cmp_lt(self, rhs)
+            }
+
+            #[inline(always)]
+            fn blend(self, t: Self, f: Self) -> Self {
+                <$lanes>::blend(self, t, f)
+            }
+
+            #[inline(always)]
+            fn exp(self) -> Self {
+                <$lanes>::exp(self)
+            }
+
+            #[inline(always)]
+            fn ln(self) -> Self {
+                <$lanes>::ln(self)
+            }
+
+            #[inline(always)]
+            fn sqrt(self) -> Self {
+                <$lanes>::sqrt(self)
+            }
+
+            #[inline(always)]
+            fn sin(self) -> Self {
+                <$lanes>::sin(self)
+            }
+
+            #[inline(always)]
+            fn sin_cos(self) -> (Self, Self) {
+                <$lanes>::sin_cos(self)
+            }
+        }
+    };
+}
+
+impl_simd_float!(f32x8, f32, 8);
+impl_simd_float!(f64x4, f64, 4);
+
+// How each lane type gets its random numbers out of the 8 f64s the SIMD RNG generates
+trait UniformPair: Sized {
+    fn uniform_pair(rng: &mut Xoshiro256PlusPlusX8) -> (Self, Self);
+}
+
+impl UniformPair for f32x8 {
+    #[inline(always)]
+    fn uniform_pair(rng: &mut Xoshiro256PlusPlusX8) -> (f32x8, f32x8) {
+        let first_rand = get_rand_uniform_f32x8(rng);
+        let second_rand = get_rand_uniform_f32x8(rng);
+        (first_rand, second_rand)
+    }
+}
+
+impl UniformPair for f64x4 {
+    #[inline(always)]
+    fn uniform_pair(rng: &mut Xoshiro256PlusPlusX8) -> (f64x4, f64x4) {
+        get_rand_uniform_f64x4_pair(rng)
+    }
+}
+
+#[test]
+fn lane_index_counts_lanes() {
+    assert_eq!(<f32x8 as SimdFloat>::lane_index().to_array(), [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+    assert_eq!(<f64x4 as SimdFloat>::lane_index().to_array(), [0.0, 1.0, 2.0, 3.0]);
+}
+
+#[test]
+fn blend_selects_masked_lanes() {
+    let lanes = <f64x4 as SimdFloat>::lane_index();
+    let mask = SimdFloat::cmp_lt(lanes, <f64x4 as SimdFloat>::splat(2.0));
+    let blended = SimdFloat::blend(mask, lanes, <f64x4 as SimdFloat>::splat(-1.0));
+    assert_eq!(blended.to_array(), [0.0, 1.0, -1.0, -1.0]);
+    assert_eq!(SimdFloat::sum_lanes(blended), -1.0);
+}
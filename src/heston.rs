@@ -0,0 +1,653 @@
+// Heston's stochastic volatility model, in which the variance of the spot reverts to a long-run
+// level and is driven by a Brownian motion of its own, correlated with the one of the spot. A
+// negative correlation gives the skew of equity smiles, and the volatility of the variance
+// their curvature. With the jumps of Merton's model added to the spot, it is the Bates model.
+// The characteristic function of the log spot is known in closed form for both, so vanillas are
+// also priced semi-analytically by Fourier inversion.
+
+use std::ops::{ Add, Div, Mul, Sub };
+use crate::error::PricingError;
+use crate::float::{ Float, SimdFloat };
+use crate::jump_diffusion::{ JumpSteps, Jumps };
+use crate::mc_simd::{ self, PathPayoff, PriceEstimate, SpotModel, Terminal, Vanilla };
+use crate::params::{ MarketData, OptionContract, OptionType, SimulationConfig };
+
+/// How the variance is simulated over a step
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HestonScheme {
+    /// Euler steps, with negative variances replaced by zero in the drift and the diffusion of
+    /// both the variance and the spot, which has the lowest bias of the Euler schemes
+    FullTruncation,
+    /// Andersen's quadratic-exponential scheme, which draws the variance from a distribution
+    /// with the mean and variance of its exact non-central chi-squared distribution, so it
+    /// never goes negative, and corrects the drift of the spot so that it is a martingale,
+    /// which leaves little bias even on coarse steps
+    QuadraticExponential,
+}
+
+/// The parameters of the Heston model, in which the variance v follows
+/// dv = kappa (theta - v) dt + xi sqrt(v) dW, W having correlation rho with the Brownian motion
+/// of the spot. The variance starts at v0, the square of the volatility of the market.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HestonModel<F: Float = f32> {
+    /// How fast the variance reverts to the long-run variance, kappa
+    pub mean_reversion: F,
+    /// The variance that the variance reverts to, theta
+    pub long_run_variance: F,
+    /// The volatility of the variance, xi
+    pub vol_of_vol: F,
+    /// The correlation of the variance with the spot, rho
+    pub correlation: F,
+    pub scheme: HestonScheme,
+    /// Jumps of the spot, independent of its Brownian motion and of the variance, which make
+    /// this the Bates model
+    pub jumps: Option<Jumps<F>>,
+}
+
+impl<F: Float> HestonModel<F> {
+    /// A model simulated with the quadratic-exponential scheme
+    pub fn new(
+        mean_reversion: F,
+        long_run_variance: F,
+        vol_of_vol: F,
+        correlation: F
+    ) -> HestonModel<F> {
+        HestonModel {
+            mean_reversion,
+            long_run_variance,
+            vol_of_vol,
+            correlation,
+            scheme: HestonScheme::QuadraticExponential,
+            jumps: None,
+        }
+    }
+
+    /// The same model, simulated with the given scheme
+    pub fn with_scheme(self, scheme: HestonScheme) -> HestonModel<F> {
+        HestonModel { scheme, ..self }
+    }
+
+    /// The Bates model, the same model with jumps of the spot
+    pub fn with_jumps(self, jumps: Jumps<F>) -> HestonModel<F> {
+        HestonModel { jumps: Some(jumps), ..self }
+    }
+
+    pub fn validate(&self) -> Result<(), PricingError> {
+        if !(self.mean_reversion > F::from_f64(0.0) && self.mean_reversion.is_finite()) {
+            return Err(PricingError::InvalidMeanReversion(self.mean_reversion.to_f64()));
+        }
+        if !(self.long_run_variance > F::from_f64(0.0) && self.long_run_variance.is_finite()) {
+            return Err(PricingError::InvalidLongRunVariance(self.long_run_variance.to_f64()));
+        }
+        if !(self.vol_of_vol > F::from_f64(0.0) && self.vol_of_vol.is_finite()) {
+            return Err(PricingError::InvalidVolOfVol(self.vol_of_vol.to_f64()));
+        }
+        if !(self.correlation >= F::from_f64(-1.0) && self.correlation <= F::from_f64(1.0)) {
+            return Err(PricingError::InvalidVolCorrelation(self.correlation.to_f64()));
+        }
+        match &self.jumps {
+            Some(jumps) => jumps.validate(),
+            None => Ok(()),
+        }
+    }
+
+    // The characteristic function E[exp(iuX)] of X = ln(S / F), the log of the spot at expiry
+    // over its forward, in the form of Albrecher et al. that keeps the complex logarithm on its
+    // principal branch. The jumps are independent of the rest, so their compensated
+    // characteristic function multiplies it.
+    fn characteristic_function(&self, u: Complex, initial_variance: f64, years_to_expiry: f64) -> Complex {
+        let kappa = self.mean_reversion.to_f64();
+        let theta = self.long_run_variance.to_f64();
+        let xi = self.vol_of_vol.to_f64();
+        let rho = self.correlation.to_f64();
+
+        let iu = Complex::new(-u.im, u.re);
+        let beta = Complex::new(kappa, 0.0) - iu * (rho * xi);
+        let d = (beta * beta + (iu + u * u) * (xi * xi)).sqrt();
+        let g = (beta - d) / (beta + d);
+        let decay = (d * -years_to_expiry).exp();
+        let one = Complex::new(1.0, 0.0);
+
+        let c = ((beta - d) * years_to_expiry - ((one - g * decay) / (one - g)).ln() * 2.0) *
+            (kappa * theta / (xi * xi));
+        let d_term = (beta - d) * (one - decay) / ((one - g * decay) * (xi * xi));
+        let log_jumps = match &self.jumps {
+            Some(jumps) => {
+                let log_jump_variance = jumps.log_jump_volatility.to_f64().powi(2);
+                let jump = (iu * jumps.mean_log_jump.to_f64() - u * u * (0.5 * log_jump_variance)).exp();
+                (jump - one - iu * jumps.mean_jump()) * (jumps.intensity.to_f64() * years_to_expiry)
+            }
+            None => Complex::new(0.0, 0.0),
+        };
+        (c + d_term * initial_variance + log_jumps).exp()
+    }
+}
+
+// The constants of a step of the Heston model, splatted to V once for all the bundles. The
+// state of every path is the log of the spot over the initial spot and the variance.
+struct HestonSteps<V> {
+    spot: V,
+    initial_variance: V,
+    // (r - q) dt
+    drift: V,
+    quadratic_exponential: bool,
+    // Full truncation
+    dt: V,
+    half_dt: V,
+    mean_reversion_dt: V,
+    long_run_variance: V,
+    vol_of_vol: V,
+    correlation: V,
+    // sqrt(1 - rho^2), the weight of the normal of the spot independent of the variance
+    orthogonal: V,
+    // Quadratic-exponential, as in Andersen's paper with gamma1 = gamma2 = 1/2: the decay
+    // exp(-kappa dt) of the mean of the variance, the coefficients of the variance of the
+    // variance, and K0 to K3 of the log spot step (K4 being equal to K3), K0 being replaced
+    // by its martingale correction wherever that is finite
+    decay: V,
+    variance_mults: (V, V),
+    log_mults: [V; 4],
+    jumps: Option<JumpSteps<V>>,
+}
+
+impl<V: SimdFloat> HestonSteps<V> {
+    fn new<F: Float>(
+        model: &HestonModel<F>,
+        market: &MarketData<F>,
+        years_to_expiry: f64,
+        steps: usize
+    ) -> HestonSteps<V> {
+        let kappa = model.mean_reversion.to_f64();
+        let theta = model.long_run_variance.to_f64();
+        let xi = model.vol_of_vol.to_f64();
+        let rho = model.correlation.to_f64();
+        let volatility = market.volatility.to_f64();
+        let dt = years_to_expiry / (steps as f64);
+        let decay = (-kappa * dt).exp();
+        let drift_mult = 0.5 * dt * (kappa * rho / xi - 0.5);
+
+        HestonSteps {
+            spot: V::splat(market.spot.to_f64()),
+            initial_variance: V::splat(volatility * volatility),
+            drift: V::splat((market.risk_free_rate.to_f64() - market.carry_yield().to_f64()) * dt),
+            quadratic_exponential: model.scheme == HestonScheme::QuadraticExponential,
+            dt: V::splat(dt),
+            half_dt: V::splat(0.5 * dt),
+            mean_reversion_dt: V::splat(kappa * dt),
+            long_run_variance: V::splat(theta),
+            vol_of_vol: V::splat(xi),
+            correlation: V::splat(rho),
+            orthogonal: V::splat((1.0 - rho * rho).max(0.0).sqrt()),
+            decay: V::splat(decay),
+            variance_mults: (
+                V::splat((xi * xi * decay * (1.0 - decay)) / kappa),
+                V::splat((theta * xi * xi * (1.0 - decay) * (1.0 - decay)) / (2.0 * kappa)),
+            ),
+            log_mults: [
+                V::splat((-rho * kappa * theta * dt) / xi),
+                V::splat(drift_mult - rho / xi),
+                V::splat(drift_mult + rho / xi),
+                V::splat(0.5 * dt * (1.0 - rho * rho)),
+            ],
+            jumps: model.jumps.as_ref().map(|jumps| JumpSteps::new(jumps, dt)),
+        }
+    }
+
+    // Full truncation Euler step, the first normal driving the variance
+    #[inline(always)]
+    fn full_truncation_step(&self, log_mult: &mut V, variance: &mut V, normals: &[V]) {
+        let positive_variance = variance.fast_max(V::splat(0.0));
+        let sqrt_variance_dt = (positive_variance * self.dt).sqrt();
+        let spot_normal = V::mul_add(self.correlation, normals[0], self.orthogonal * normals[1]);
+
+        *log_mult = V::mul_add(
+            sqrt_variance_dt,
+            spot_normal,
+            *log_mult + self.drift - self.half_dt * positive_variance
+        );
+        *variance = V::mul_add(
+            self.vol_of_vol * sqrt_variance_dt,
+            normals[0],
+            V::mul_add(self.mean_reversion_dt, self.long_run_variance - positive_variance, *variance)
+        );
+    }
+
+    // Quadratic-exponential step. Lanes whose variance has a low dispersion psi draw it as a
+    // scaled non-central chi-squared with one degree of freedom, a * (b + Z)^2, and the others
+    // from a mass p at zero with an exponential tail, by inversion of the uniform. Both are
+    // computed on every lane and blended, the unused one possibly being NaN.
+    #[inline(always)]
+    fn quadratic_exponential_step(
+        &self,
+        log_mult: &mut V,
+        variance: &mut V,
+        normals: &[V],
+        uniform: V
+    ) {
+        let zeros = V::splat(0.0);
+        let one = V::splat(1.0);
+        let two = V::splat(2.0);
+
+        let mean = V::mul_add(*variance - self.long_run_variance, self.decay, self.long_run_variance);
+        let variance_of_variance = V::mul_add(*variance, self.variance_mults.0, self.variance_mults.1);
+        let psi = variance_of_variance / (mean * mean);
+
+        let two_over_psi = two / psi;
+        let b_squared = two_over_psi - one + (two_over_psi * (two_over_psi - one)).sqrt();
+        let a = mean / (one + b_squared);
+        let root = b_squared.sqrt() + normals[0];
+        let quadratic = a * root * root;
+
+        let p = (psi - one) / (psi + one);
+        let beta = (one - p) / mean;
+        // The complement of the uniform is kept away from zero, since f32 lanes round uniforms
+        // just below 1 up to 1
+        let complement = (one - uniform).fast_max(V::splat(1e-30));
+        let tail = ((one - p) / complement).ln() / beta;
+        let exponential = uniform.cmp_lt(p).blend(zeros, tail);
+
+        let quadratic_lanes = psi.cmp_lt(V::splat(1.5));
+        let next_variance = quadratic_lanes.blend(quadratic, exponential);
+
+        // Andersen's martingale correction: K0 is chosen so that the spot grows at the drift
+        // given the variance, from the moment generating function of the next variance at
+        // A = K2 + K4 / 2. Lanes where it is infinite keep the K0 of the discretization.
+        let [k0, k1, k2, k3] = self.log_mults;
+        let half = V::splat(0.5);
+        let moment = V::mul_add(half, k3, k2);
+        let two_moment_a = two * moment * a;
+        let quadratic_k0 = half * (one - two_moment_a).ln() - moment * b_squared * a / (one - two_moment_a);
+        let exponential_k0 = -(p + beta * (one - p) / (beta - moment)).ln();
+        let martingale_k0 = quadratic_lanes.blend(quadratic_k0, exponential_k0) - V::mul_add(half, k3, k1) * *variance;
+        let moment_limit = quadratic_lanes.blend(one / (two * a), beta);
+        let k0 = moment.cmp_lt(moment_limit).blend(martingale_k0, k0);
+
+        let log_drift = V::mul_add(k2, next_variance, V::mul_add(k1, *variance, self.drift + k0));
+        *log_mult = V::mul_add((k3 * (*variance + next_variance)).sqrt(), normals[1], *log_mult + log_drift);
+        *variance = next_variance;
+    }
+}
+
+impl<V: SimdFloat> SpotModel<V> for HestonSteps<V> {
+    type State = (V, V);
+
+    // Two normals for the variance and the spot, a uniform for the quadratic-exponential
+    // scheme, and a normal and a uniform for the jumps
+    fn draws_per_step(&self) -> (usize, usize) {
+        let (normals, uniforms) = if self.quadratic_exponential { (2, 1) } else { (2, 0) };
+        if self.jumps.is_some() { (normals + 1, uniforms + 1) } else { (normals, uniforms) }
+    }
+
+    fn start(&self) -> (V, V) {
+        (V::splat(0.0), self.initial_variance)
+    }
+
+    fn step(&self, (log_mult, variance): &mut (V, V), _step: usize, normals: &[V], uniforms: &[V]) -> V {
+        if self.quadratic_exponential {
+            self.quadratic_exponential_step(log_mult, variance, normals, uniforms[0]);
+        } else {
+            self.full_truncation_step(log_mult, variance, normals);
+        }
+        if let Some(jumps) = &self.jumps {
+            *log_mult = *log_mult + jumps.log_jump(normals[2], uniforms[uniforms.len() - 1]);
+        }
+        self.spot * log_mult.exp()
+    }
+}
+
+/// Price a path-dependent option expiring in `years_to_expiry` years in the Heston model, or in
+/// the Bates model if it has jumps, with the standard error and confidence interval of the
+/// price. The payoff is shown the spot after each of the `config.steps` steps, as in
+/// [`mc_simd::price_path`].
+pub fn price_path<F: Float, P: PathPayoff<F::Lanes>>(
+    model: &HestonModel<F>,
+    market: &MarketData<F>,
+    years_to_expiry: F,
+    config: &SimulationConfig,
+    payoff: &P
+) -> Result<PriceEstimate<F>, PricingError> {
+    model.validate()?;
+    mc_simd::validate_path(market, years_to_expiry, config)?;
+
+    let steps = HestonSteps::<F::Lanes>::new(model, market, years_to_expiry.to_f64(), config.steps);
+    Ok(mc_simd::monte_carlo_model_pricing(
+        &steps,
+        market.risk_free_rate.to_f64(),
+        years_to_expiry.to_f64(),
+        config,
+        payoff
+    ))
+}
+
+/// Price a European option in the Heston or Bates model, with the standard error and
+/// confidence interval of the price
+pub fn price<F: Float>(
+    contract: &OptionContract<F>,
+    model: &HestonModel<F>,
+    market: &MarketData<F>,
+    config: &SimulationConfig
+) -> Result<PriceEstimate<F>, PricingError> {
+    contract.validate()?;
+
+    let payoff = Vanilla::<F::Lanes>::new(contract.strike.to_f64(), contract.option_type.call_mult());
+    price_path(model, market, contract.years_to_expiry, config, &Terminal(payoff))
+}
+
+// Width of the ranges of frequencies the Fourier integral is split into, the number of Simpson
+// intervals in each, and the frequency it is cut off at if the integrand never decays
+const CHUNK_WIDTH: f64 = 10.0;
+const CHUNK_INTERVALS: usize = 200;
+const MAX_FREQUENCY: f64 = 10000.0;
+
+/// Price a European option in the Heston or Bates model semi-analytically with Lewis's formula,
+/// a single Fourier integral of the characteristic function of the log spot, which is
+/// integrated with Simpson's rule until the integrand has decayed
+pub fn analytic_price<F: Float>(
+    contract: &OptionContract<F>,
+    model: &HestonModel<F>,
+    market: &MarketData<F>
+) -> Result<F, PricingError> {
+    contract.validate()?;
+    model.validate()?;
+    market.validate()?;
+
+    let spot = market.spot.to_f64();
+    let strike = contract.strike.to_f64();
+    let volatility = market.volatility.to_f64();
+    let risk_free_rate = market.risk_free_rate.to_f64();
+    let years_to_expiry = contract.years_to_expiry.to_f64();
+    let dividend_yield = market.carry_yield().to_f64();
+    let discounted_spot = spot * (-dividend_yield * years_to_expiry).exp();
+    let discounted_strike = strike * (-risk_free_rate * years_to_expiry).exp();
+
+    let call = if strike == 0.0 {
+        discounted_spot
+    } else {
+        let log_moneyness = (spot / strike).ln() + (risk_free_rate - dividend_yield) * years_to_expiry;
+        let integrand = |u: f64| {
+            let shift = Complex::new(0.0, u * log_moneyness).exp();
+            let value = shift * model.characteristic_function(
+                Complex::new(u, -0.5),
+                volatility * volatility,
+                years_to_expiry
+            );
+            value * (1.0 / (u * u + 0.25))
+        };
+
+        let h = CHUNK_WIDTH / (CHUNK_INTERVALS as f64);
+        let mut integral = 0.0;
+        let mut start = 0.0;
+        while start < MAX_FREQUENCY {
+            let end = integrand(start + CHUNK_WIDTH);
+            let sum = (1..CHUNK_INTERVALS).fold(integrand(start).re + end.re, |sum, interval| {
+                let weight = if interval % 2 == 1 { 4.0 } else { 2.0 };
+                sum + weight * integrand(start + (interval as f64) * h).re
+            });
+            integral += (sum * h) / 3.0;
+            start += CHUNK_WIDTH;
+            if end.abs() < 1e-16 {
+                break;
+            }
+        }
+
+        let discount = (-0.5 * (risk_free_rate + dividend_yield) * years_to_expiry).exp();
+        discounted_spot - ((spot * strike).sqrt() * discount * integral) / std::f64::consts::PI
+    };
+
+    let price = match contract.option_type {
+        OptionType::Call => call,
+        OptionType::Put => call - discounted_spot + discounted_strike,
+    };
+    Ok(F::from_f64(price))
+}
+
+// A complex number, for the characteristic function
+#[derive(Clone, Copy, Debug)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Complex {
+        Complex { re, im }
+    }
+
+    fn abs(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    fn exp(self) -> Complex {
+        let modulus = self.re.exp();
+        Complex::new(modulus * self.im.cos(), modulus * self.im.sin())
+    }
+
+    // The principal branch of the logarithm
+    fn ln(self) -> Complex {
+        Complex::new(self.abs().ln(), self.im.atan2(self.re))
+    }
+
+    // The principal square root, with a non-negative real part
+    fn sqrt(self) -> Complex {
+        let modulus = self.abs();
+        Complex::new(
+            (0.5 * (modulus + self.re)).sqrt(),
+            (0.5 * (modulus - self.re)).sqrt().copysign(self.im)
+        )
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, rhs: Complex) -> Complex {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, rhs: Complex) -> Complex {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, rhs: Complex) -> Complex {
+        Complex::new(self.re * rhs.re - self.im * rhs.im, self.re * rhs.im + self.im * rhs.re)
+    }
+}
+
+impl Mul<f64> for Complex {
+    type Output = Complex;
+
+    fn mul(self, rhs: f64) -> Complex {
+        Complex::new(self.re * rhs, self.im * rhs)
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+
+    fn div(self, rhs: Complex) -> Complex {
+        let norm = rhs.re * rhs.re + rhs.im * rhs.im;
+        Complex::new(
+            (self.re * rhs.re + self.im * rhs.im) / norm,
+            (self.im * rhs.re - self.re * rhs.im) / norm
+        )
+    }
+}
+
+#[cfg(test)]
+use crate::bs;
+#[cfg(test)]
+use wide::f64x4;
+
+// Lewis's benchmark model, whose variance hits zero since it breaks the Feller condition
+#[cfg(test)]
+fn lewis_model(scheme: HestonScheme) -> HestonModel<f64> {
+    HestonModel::new(4.0, 0.25, 1.0, -0.5).with_scheme(scheme)
+}
+
+#[test]
+fn analytic_price_matches_lewis_benchmark() {
+    let market = MarketData::new(100.0, 0.2, 0.01, 0.02);
+    let expected_calls = [
+        (80.0, 26.774758743998854),
+        (90.0, 20.93334900059671),
+        (100.0, 16.070154917028834),
+        (110.0, 12.132211516709844),
+        (120.0, 9.024913483457836),
+    ];
+    for (strike, expected_call) in expected_calls {
+        let model = lewis_model(HestonScheme::QuadraticExponential);
+        let call = analytic_price(&OptionContract::new(OptionType::Call, strike, 1.0), &model, &market).unwrap();
+        let put = analytic_price(&OptionContract::new(OptionType::Put, strike, 1.0), &model, &market).unwrap();
+        println!("heston call {} {} vs {}", strike, call, expected_call);
+        assert!((call - expected_call).abs() < 1e-8);
+        let forward = 100.0 * (-0.02f64).exp() - strike * (-0.01f64).exp();
+        assert!((call - put - forward).abs() < 1e-10);
+    }
+}
+
+#[test]
+fn heston_matches_fourier_price() {
+    // A put and a call on a model that meets the Feller condition and on one that breaks it,
+    // with enough steps that the discretization bias is below the noise
+    let market = MarketData::new(100.0, 0.2, 0.01, 0.02);
+    let feller_model = HestonModel::new(2.0, 0.04, 0.3, -0.7).with_scheme(HestonScheme::QuadraticExponential);
+    let config = SimulationConfig::builder().steps(32).num_trials(100000).seed(101).antithetic(true).build().unwrap();
+    for model in [feller_model, lewis_model(HestonScheme::QuadraticExponential)] {
+        for (option_type, strike) in [(OptionType::Put, 80.0), (OptionType::Call, 120.0)] {
+            let contract = OptionContract::new(option_type, strike, 1.0);
+            let estimate = price(&contract, &model, &market, &config).unwrap();
+            let actual_price = analytic_price(&contract, &model, &market).unwrap();
+            println!("heston {:?} {} {:?} vs {}", option_type, strike, estimate, actual_price);
+            assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+        }
+    }
+}
+
+#[test]
+fn heston_scheme_bias_shrinks_with_steps() {
+    // When the variance hits zero full truncation is biased on coarse steps, by about 0.34 on 4
+    // steps, and the bias falls below the noise by 64. QE is within the noise from 4 steps.
+    let market = MarketData::new(100.0, 0.2, 0.01, 0.02);
+    let contract = OptionContract::new(OptionType::Call, 100.0, 1.0);
+    let bias = |scheme: HestonScheme, steps: usize| {
+        let model = lewis_model(scheme);
+        let actual_price = analytic_price(&contract, &model, &market).unwrap();
+        let config = SimulationConfig::builder().steps(steps).num_trials(200000).seed(105).antithetic(true).build().unwrap();
+        let estimate = price(&contract, &model, &market, &config).unwrap();
+        println!("heston {:?} {} steps {:?} vs {}", scheme, steps, estimate, actual_price);
+        (estimate.price - actual_price, estimate.std_error)
+    };
+
+    let (coarse_bias, coarse_std_error) = bias(HestonScheme::FullTruncation, 4);
+    let (medium_bias, _) = bias(HestonScheme::FullTruncation, 16);
+    let (fine_bias, fine_std_error) = bias(HestonScheme::FullTruncation, 64);
+    assert!(coarse_bias > 4.0 * coarse_std_error);
+    assert!(medium_bias.abs() < 0.25 * coarse_bias);
+    assert!(fine_bias.abs() < 4.0 * fine_std_error);
+
+    for steps in [4, 16, 64] {
+        let (quadratic_exponential_bias, std_error) = bias(HestonScheme::QuadraticExponential, steps);
+        assert!(quadratic_exponential_bias.abs() < 4.0 * std_error);
+        assert!(quadratic_exponential_bias.abs() < 0.5 * coarse_bias);
+    }
+}
+
+#[test]
+fn f32_heston_with_constant_variance_matches_bs() {
+    // With the variance starting at its long-run level and barely moving, the model is
+    // Black-Scholes
+    let market = MarketData::new(100.0f32, 0.2, 0.05, 0.01);
+    let config = SimulationConfig::builder().steps(10).num_trials(100000).seed(102).build().unwrap();
+    for scheme in [HestonScheme::FullTruncation, HestonScheme::QuadraticExponential] {
+        let model = HestonModel::new(1.0f32, 0.04, 0.01, -0.5).with_scheme(scheme);
+        let estimate = price(&OptionContract::new(OptionType::Call, 105.0, 1.0), &model, &market, &config).unwrap();
+        let actual_price = bs::call_price(100.0f32, 105.0, 0.2, 0.05, 1.0, 0.01);
+        println!("f32 heston {:?} {:?} vs {}", scheme, estimate, actual_price);
+        assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+    }
+}
+
+#[test]
+fn heston_skew_lifts_low_strikes() {
+    // Against Black-Scholes at the volatility of the long-run variance, negative correlation
+    // makes puts far below the spot dearer and calls far above it cheaper, path by path as
+    // well as in closed form
+    let market = MarketData::new(100.0, 0.2, 0.0, 0.0);
+    let model = HestonModel::new(2.0, 0.04, 0.6, -0.8);
+    let config = SimulationConfig::builder().steps(50).num_trials(100000).seed(103).build().unwrap();
+    let put = |strike: f64| {
+        let payoff = move |spot: f64x4| f64x4::fast_max(f64x4::splat(strike) - spot, f64x4::splat(0.0));
+        price_path(&model, &market, 1.0, &config, &Terminal(payoff)).unwrap()
+    };
+    let low_put = put(70.0);
+    assert!(low_put.price - 4.0 * low_put.std_error > bs::put_price(100.0, 70.0, 0.2, 0.0, 1.0, 0.0));
+    let high_call = analytic_price(&OptionContract::new(OptionType::Call, 130.0, 1.0), &model, &market).unwrap();
+    assert!(high_call < bs::call_price(100.0, 130.0, 0.2, 0.0, 1.0, 0.0));
+}
+
+#[test]
+fn bates_with_constant_variance_matches_merton() {
+    let market = MarketData::new(100.0, 0.2, 0.05, 0.01);
+    let jumps = Jumps::new(0.8, -0.15, 0.2);
+    let model = HestonModel::new(1.0, 0.04, 1e-3, 0.0).with_jumps(jumps);
+    for option_type in [OptionType::Call, OptionType::Put] {
+        for strike in [70.0, 100.0, 130.0] {
+            let contract = OptionContract::new(option_type, strike, 1.0);
+            let bates_price = analytic_price(&contract, &model, &market).unwrap();
+            let merton_price = bs::merton_price(option_type, 100.0, strike, 0.2, 0.05, 1.0, 0.01, 0.8, -0.15, 0.2);
+            println!("bates {:?} {} {} vs {}", option_type, strike, bates_price, merton_price);
+            assert!((bates_price - merton_price).abs() < 1e-4);
+        }
+    }
+}
+
+#[test]
+fn bates_matches_fourier_price() {
+    let market = MarketData::new(100.0, 0.2, 0.01, 0.02);
+    let jumps = Jumps::new(0.5, -0.2, 0.15);
+    let config = SimulationConfig::builder().steps(32).num_trials(100000).seed(104).antithetic(true).build().unwrap();
+    // The jumps are added to the log spot step of either scheme
+    for (scheme, option_type, strike) in [
+        (HestonScheme::FullTruncation, OptionType::Put, 80.0),
+        (HestonScheme::QuadraticExponential, OptionType::Call, 120.0),
+    ] {
+        let model = HestonModel::new(2.0, 0.04, 0.3, -0.7).with_scheme(scheme).with_jumps(jumps);
+        let contract = OptionContract::new(option_type, strike, 1.0);
+        let estimate = price(&contract, &model, &market, &config).unwrap();
+        let actual_price = analytic_price(&contract, &model, &market).unwrap();
+        println!("bates {:?} {:?} {} {:?} vs {}", scheme, option_type, strike, estimate, actual_price);
+        assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+    }
+}
+
+#[test]
+fn invalid_heston_parameters() {
+    let market = MarketData::new(100.0, 0.2, 0.05, 0.0);
+    let contract = OptionContract::new(OptionType::Call, 100.0, 1.0);
+    let config = SimulationConfig::new(10, 100);
+    let model = HestonModel::new(2.0, 0.04, 0.3, -0.7);
+    let cases = [
+        (HestonModel { mean_reversion: 0.0, ..model }, PricingError::InvalidMeanReversion(0.0)),
+        (HestonModel { long_run_variance: -0.04, ..model }, PricingError::InvalidLongRunVariance(-0.04)),
+        (HestonModel { vol_of_vol: f64::INFINITY, ..model }, PricingError::InvalidVolOfVol(f64::INFINITY)),
+        (HestonModel { correlation: -1.5, ..model }, PricingError::InvalidVolCorrelation(-1.5)),
+        (model.with_jumps(Jumps::new(-1.0, 0.0, 0.1)), PricingError::InvalidJumpIntensity(-1.0)),
+    ];
+    for (model, error) in cases {
+        assert_eq!(price(&contract, &model, &market, &config), Err(error));
+        assert_eq!(analytic_price(&contract, &model, &market), Err(error));
+    }
+    assert_eq!(
+        price_path(&model, &market, 0.0, &config, &Terminal(|spot: f64x4| spot)),
+        Err(PricingError::InvalidYearsToExpiry(0.0))
+    );
+}
+
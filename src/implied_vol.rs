@@ -0,0 +1,518 @@
+// Implied volatility solvers, which back out the Black-scholes volatility from an option price.
+// Newton steps on vega are used while they stay inside a bracket around the root, and the
+// bracket is bisected otherwise, so the solvers converge for every price within the bounds.
+
+use wide::*;
+use crate::bs;
+use crate::bs_simd::{ chunk_lanes, greeks_x8 };
+use crate::error::PricingError;
+use crate::float::Float;
+use crate::params::{ MarketData, OptionContract, OptionType };
+
+// The upper end of the bracket starts at a volatility of 1.0 and is doubled at most this many
+// times, so the largest implied volatility found is 1024.0
+const MAX_DOUBLINGS: usize = 10;
+// The normal bracket starts at a lower bound of the volatility, which is tiny far from the
+// money, so it may have to double across the whole exponent range of f64
+const MAX_NORMAL_DOUBLINGS: usize = 2100;
+const MAX_ITERATIONS: usize = 100;
+const TOLERANCE: f64 = 1e-12;
+// f32 prices only carry about 7 digits, so the batch solver stops earlier
+const MAX_ITERATIONS_X8: usize = 40;
+const TOLERANCE_X8: f32 = 1e-6;
+
+// Prices of an option with zero and infinite volatility, which any price must lie strictly
+// between to have an implied volatility
+fn price_bounds(
+    option_type: OptionType,
+    spot: f64,
+    strike: f64,
+    risk_free_rate: f64,
+    years_to_expiry: f64,
+    dividend_yield: f64
+) -> (f64, f64) {
+    let spot_discounted = spot * (-dividend_yield * years_to_expiry).exp();
+    let strike_discounted = strike * (-risk_free_rate * years_to_expiry).exp();
+    match option_type {
+        OptionType::Call => ((spot_discounted - strike_discounted).max(0.0), spot_discounted),
+        OptionType::Put => ((strike_discounted - spot_discounted).max(0.0), strike_discounted),
+    }
+}
+
+// Checks the parameters and that the price is within the no-arbitrage bounds
+fn validate<F: Float>(
+    option_type: OptionType,
+    price: F,
+    spot: F,
+    strike: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> Result<(), PricingError> {
+    OptionContract::new(option_type, strike, years_to_expiry).validate()?;
+    // The volatility is what we solve for, so any valid volatility passes the market checks
+    MarketData::new(spot, F::from_f64(1.0), risk_free_rate, dividend_yield).validate()?;
+
+    let price = price.to_f64();
+    let (lower_bound, upper_bound) = price_bounds(
+        option_type,
+        spot.to_f64(),
+        strike.to_f64(),
+        risk_free_rate.to_f64(),
+        years_to_expiry.to_f64(),
+        dividend_yield.to_f64()
+    );
+    if !(price > lower_bound && price < upper_bound) {
+        return Err(PricingError::ArbitrageBoundsViolated { price, lower_bound, upper_bound });
+    }
+    Ok(())
+}
+
+/// The volatility at which the Black-scholes price of a European option equals `price`.
+/// Returns [`PricingError::ArbitrageBoundsViolated`] if the price is not strictly between the
+/// prices at zero and infinite volatility.
+pub fn implied_volatility<F: Float>(
+    option_type: OptionType,
+    price: F,
+    spot: F,
+    strike: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> Result<F, PricingError> {
+    validate(option_type, price, spot, strike, risk_free_rate, years_to_expiry, dividend_yield)?;
+
+    let price = price.to_f64();
+    let spot = spot.to_f64();
+    let strike = strike.to_f64();
+    let risk_free_rate = risk_free_rate.to_f64();
+    let years_to_expiry = years_to_expiry.to_f64();
+    let dividend_yield = dividend_yield.to_f64();
+    let model_price = |volatility: f64| -> f64 {
+        bs::price(option_type, spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield)
+    };
+
+    // The price increases with the volatility, so bracket the root by doubling the upper end
+    let mut low = 0.0;
+    let mut high = 1.0;
+    let mut doublings = 0;
+    while model_price(high) < price {
+        if doublings == MAX_DOUBLINGS {
+            // Only prices indistinguishable from the upper bound get here
+            let (lower_bound, upper_bound) = price_bounds(
+                option_type,
+                spot,
+                strike,
+                risk_free_rate,
+                years_to_expiry,
+                dividend_yield
+            );
+            return Err(PricingError::ArbitrageBoundsViolated { price, lower_bound, upper_bound });
+        }
+        low = high;
+        high *= 2.0;
+        doublings += 1;
+    }
+
+    // Brenner-Subrahmanyam initial guess, which is close for options near the money
+    let mut volatility = ((2.0 * std::f64::consts::PI) / years_to_expiry).sqrt() * (price / spot);
+    if !(volatility > low && volatility < high) {
+        volatility = 0.5 * (low + high);
+    }
+
+    for _ in 0..MAX_ITERATIONS {
+        let diff = model_price(volatility) - price;
+        if diff.abs() <= TOLERANCE * price {
+            break;
+        }
+        if diff > 0.0 {
+            high = volatility;
+        } else {
+            low = volatility;
+        }
+
+        // Multiplied by 100.0 since bs::vega is per 1% change in the volatility
+        let vega =
+            100.0 * bs::vega(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+        let newton = volatility - diff / vega;
+        let next = if newton > low && newton < high { newton } else { 0.5 * (low + high) };
+        let step = (next - volatility).abs();
+        volatility = next;
+        if step <= TOLERANCE * volatility {
+            break;
+        }
+    }
+
+    Ok(F::from_f64(volatility))
+}
+
+/// The normal volatility at which the Bachelier price of a European option equals `price`,
+/// for any finite spot and strike, including negative ones. Returns
+/// [`PricingError::ArbitrageBoundsViolated`] if the price is not above the discounted intrinsic
+/// value of the forward, the price at zero volatility.
+pub fn normal_implied_volatility<F: Float>(
+    option_type: OptionType,
+    price: F,
+    spot: F,
+    strike: F,
+    risk_free_rate: F,
+    years_to_expiry: F,
+    dividend_yield: F
+) -> Result<F, PricingError> {
+    if !spot.is_finite() {
+        return Err(PricingError::InvalidSpot(spot.to_f64()));
+    }
+    if !strike.is_finite() {
+        return Err(PricingError::InvalidStrike(strike.to_f64()));
+    }
+    if !(years_to_expiry > F::from_f64(0.0) && years_to_expiry.is_finite()) {
+        return Err(PricingError::InvalidYearsToExpiry(years_to_expiry.to_f64()));
+    }
+    if !risk_free_rate.is_finite() {
+        return Err(PricingError::InvalidRiskFreeRate(risk_free_rate.to_f64()));
+    }
+    if !dividend_yield.is_finite() {
+        return Err(PricingError::InvalidDividendYield(dividend_yield.to_f64()));
+    }
+
+    let price = price.to_f64();
+    let spot = spot.to_f64();
+    let strike = strike.to_f64();
+    let risk_free_rate = risk_free_rate.to_f64();
+    let years_to_expiry = years_to_expiry.to_f64();
+    let dividend_yield = dividend_yield.to_f64();
+    let discount = (-risk_free_rate * years_to_expiry).exp();
+    let forward = spot * ((risk_free_rate - dividend_yield) * years_to_expiry).exp();
+    let intrinsic = discount * (option_type.call_mult() * (forward - strike)).max(0.0);
+    // The price has no upper bound, since the forward is unbounded both ways
+    if !(price > intrinsic && price.is_finite()) {
+        return Err(PricingError::ArbitrageBoundsViolated { price, lower_bound: intrinsic, upper_bound: f64::INFINITY });
+    }
+    let model_price = |volatility: f64| -> f64 {
+        bs::bachelier_price(option_type, spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield)
+    };
+
+    // The time value is at most that of an option at the money, so this is at most the implied
+    // volatility, and equal to it at the money
+    let mut low = 0.0;
+    let mut volatility =
+        ((price - intrinsic) / discount) * ((2.0 * std::f64::consts::PI) / years_to_expiry).sqrt();
+    let mut high = volatility;
+    let mut doublings = 0;
+    while model_price(high) < price {
+        if doublings == MAX_NORMAL_DOUBLINGS || !high.is_finite() {
+            return Err(PricingError::ArbitrageBoundsViolated { price, lower_bound: intrinsic, upper_bound: f64::INFINITY });
+        }
+        low = high;
+        high *= 2.0;
+        doublings += 1;
+    }
+
+    for _ in 0..MAX_ITERATIONS {
+        let diff = model_price(volatility) - price;
+        if diff.abs() <= TOLERANCE * price {
+            break;
+        }
+        if diff > 0.0 {
+            high = volatility;
+        } else {
+            low = volatility;
+        }
+
+        // Multiplied by 100.0 since bs::bachelier_vega is per 0.01 change in the volatility
+        let vega = 100.0 *
+            bs::bachelier_vega(spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield);
+        let newton = volatility - diff / vega;
+        let next = if newton > low && newton < high { newton } else { 0.5 * (low + high) };
+        let step = (next - volatility).abs();
+        volatility = next;
+        if step <= TOLERANCE * volatility {
+            break;
+        }
+    }
+
+    // Prices so large that the volatility overflows
+    if !volatility.is_finite() {
+        return Err(PricingError::ArbitrageBoundsViolated { price, lower_bound: intrinsic, upper_bound: f64::INFINITY });
+    }
+    Ok(F::from_f64(volatility))
+}
+
+/// Implied volatilities of 8 European options of the same type in one vectorized pass. Lanes
+/// whose price is outside the no-arbitrage bounds, whose parameters are invalid, or whose
+/// volatility could not be bracketed in f32, are NaN.
+pub fn implied_volatility_x8(
+    option_type: OptionType,
+    price: f32x8,
+    spot: f32x8,
+    strike: f32x8,
+    risk_free_rate: f32x8,
+    years_to_expiry: f32x8,
+    dividend_yield: f32x8
+) -> f32x8 {
+    let zeros = f32x8::splat(0.0);
+    let half = f32x8::splat(0.5);
+    let call_mult = f32x8::splat(option_type.call_mult() as f32);
+
+    let spot_discounted = spot * (-dividend_yield * years_to_expiry).exp();
+    let strike_discounted = strike * (-risk_free_rate * years_to_expiry).exp();
+    let lower_bound = (call_mult * (spot_discounted - strike_discounted)).fast_max(zeros);
+    let upper_bound = call_mult.cmp_gt(zeros).blend(spot_discounted, strike_discounted);
+    let valid =
+        price.cmp_gt(lower_bound) &
+        price.cmp_lt(upper_bound) &
+        spot.cmp_gt(zeros) &
+        strike.cmp_gt(zeros) &
+        years_to_expiry.cmp_gt(zeros);
+
+    let model = |volatility: f32x8| {
+        greeks_x8(option_type, spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield)
+    };
+
+    // Bracket the root in every lane by doubling the upper end where the price is still too low
+    let mut low = zeros;
+    let mut high = f32x8::splat(1.0);
+    let mut too_low = model(high).price.cmp_lt(price);
+    for _ in 0..MAX_DOUBLINGS {
+        if too_low.none() {
+            break;
+        }
+        low = too_low.blend(high, low);
+        high = too_low.blend(high + high, high);
+        too_low = model(high).price.cmp_lt(price);
+    }
+    // Prices too close to the upper bound for f32 are never reached however high the volatility
+    let valid = valid & !too_low;
+
+    // Brenner-Subrahmanyam initial guess, replaced by the middle of the bracket if it is outside
+    let guess = f32x8::splat(((2.0 * std::f64::consts::PI).sqrt()) as f32) * (price / spot) /
+        years_to_expiry.sqrt();
+    let mut volatility = (guess.cmp_gt(low) & guess.cmp_lt(high)).blend(guess, (low + high) * half);
+
+    for _ in 0..MAX_ITERATIONS_X8 {
+        let greeks = model(volatility);
+        let diff = greeks.price - price;
+        let too_high = diff.cmp_gt(zeros);
+        high = too_high.blend(volatility, high);
+        low = too_high.blend(low, volatility);
+
+        // Multiplied by 100.0 since the vega is per 1% change in the volatility
+        let newton = volatility - diff / (greeks.vega * f32x8::splat(100.0));
+        let in_bracket = newton.cmp_gt(low) & newton.cmp_lt(high);
+        let next = in_bracket.blend(newton, (low + high) * half);
+        let converged = (next - volatility).abs().cmp_le(next * f32x8::splat(TOLERANCE_X8));
+        volatility = next;
+        if (converged | !valid).all() {
+            break;
+        }
+    }
+
+    valid.blend(volatility, f32x8::splat(f32::NAN))
+}
+
+/// Implied volatilities of a batch of European options of the same type, e.g. an option chain,
+/// 8 options at a time. Element `i` of the result is the option with element `i` of every
+/// parameter slice, or the reason it has no implied volatility. Options that
+/// [`implied_volatility_x8`] cannot solve are errors, and if their parameters are valid the
+/// error is [`PricingError::ArbitrageBoundsViolated`]. Panics if the slices have different
+/// lengths.
+pub fn batch_implied_volatility(
+    option_type: OptionType,
+    price: &[f32],
+    spot: &[f32],
+    strike: &[f32],
+    risk_free_rate: &[f32],
+    years_to_expiry: &[f32],
+    dividend_yield: &[f32]
+) -> Vec<Result<f32, PricingError>> {
+    let len = price.len();
+    for other in [spot, strike, risk_free_rate, years_to_expiry, dividend_yield] {
+        assert_eq!(other.len(), len, "all parameter slices must have the same length");
+    }
+
+    let mut result = Vec::with_capacity(len);
+    for start in (0..len).step_by(8) {
+        let end = (start + 8).min(len);
+        let lanes = |values: &[f32]| chunk_lanes(values, start, end);
+
+        let volatility = implied_volatility_x8(
+            option_type,
+            lanes(price),
+            lanes(spot),
+            lanes(strike),
+            lanes(risk_free_rate),
+            lanes(years_to_expiry),
+            lanes(dividend_yield)
+        ).to_array();
+
+        for i in start..end {
+            let volatility = volatility[i - start];
+            if !volatility.is_nan() {
+                result.push(Ok(volatility));
+                continue;
+            }
+            let reason = validate(
+                option_type,
+                price[i],
+                spot[i],
+                strike[i],
+                risk_free_rate[i],
+                years_to_expiry[i],
+                dividend_yield[i]
+            );
+            result.push(Err(reason.err().unwrap_or_else(|| {
+                let (lower_bound, upper_bound) = price_bounds(
+                    option_type,
+                    spot[i] as f64,
+                    strike[i] as f64,
+                    risk_free_rate[i] as f64,
+                    years_to_expiry[i] as f64,
+                    dividend_yield[i] as f64
+                );
+                PricingError::ArbitrageBoundsViolated { price: price[i] as f64, lower_bound, upper_bound }
+            })));
+        }
+    }
+
+    result
+}
+
+#[test]
+fn implied_volatility_round_trips() {
+    for option_type in [OptionType::Call, OptionType::Put] {
+        for strike in [50.0, 80.0, 100.0, 110.0, 150.0] {
+            for volatility in [0.05, 0.25, 0.8, 2.5] {
+                let price: f64 = bs::price(option_type, 100.0, strike, volatility, 0.05, 0.5, 0.02);
+                // Prices this close to the intrinsic value carry no information about the volatility
+                if price < 1e-8 || price - price_bounds(option_type, 100.0, strike, 0.05, 0.5, 0.02).0 < 1e-8 {
+                    continue;
+                }
+                let implied = implied_volatility(option_type, price, 100.0, strike, 0.05, 0.5, 0.02).unwrap();
+                println!("implied vol {:?} {} {} vs {}", option_type, strike, implied, volatility);
+                assert!((implied - volatility).abs() < 1e-8);
+            }
+        }
+    }
+}
+
+#[test]
+fn implied_volatility_f32() {
+    let price: f32 = bs::put_price(112.0, 110.0, 0.14, 0.12, 1.0, 0.02);
+    let implied: f32 = implied_volatility(OptionType::Put, price, 112.0, 110.0, 0.12, 1.0, 0.02).unwrap();
+    println!("implied vol f32 {} vs {}", implied, 0.14);
+    assert!((implied - 0.14).abs() < 1e-4);
+}
+
+#[test]
+fn implied_volatility_rejects_arbitrage() {
+    // Below the intrinsic value of a deep in-the-money call
+    assert!(matches!(
+        implied_volatility(OptionType::Call, 10.0, 150.0, 100.0, 0.05, 0.5, 0.0),
+        Err(PricingError::ArbitrageBoundsViolated { price, lower_bound, .. })
+            if price == 10.0 && lower_bound > 50.0
+    ));
+    // A put can never be worth more than the discounted strike
+    assert!(matches!(
+        implied_volatility(OptionType::Put, 120.0, 100.0, 110.0, 0.05, 0.5, 0.0),
+        Err(PricingError::ArbitrageBoundsViolated { upper_bound, .. }) if upper_bound < 110.0
+    ));
+    assert!(matches!(
+        implied_volatility(OptionType::Call, 0.0, 100.0, 110.0, 0.05, 0.5, 0.0),
+        Err(PricingError::ArbitrageBoundsViolated { .. })
+    ));
+    assert!(matches!(
+        implied_volatility(OptionType::Call, f64::NAN, 100.0, 110.0, 0.05, 0.5, 0.0),
+        Err(PricingError::ArbitrageBoundsViolated { .. })
+    ));
+    assert_eq!(
+        implied_volatility(OptionType::Call, 5.0, -100.0, 110.0, 0.05, 0.5, 0.0),
+        Err(PricingError::InvalidSpot(-100.0))
+    );
+}
+
+#[test]
+fn implied_volatility_x8_matches_scalar() {
+    let strike = f32x8::from([70.0, 85.0, 95.0, 100.0, 105.0, 115.0, 130.0, 160.0]);
+    let volatility = f32x8::from([0.1, 0.2, 0.25, 0.3, 0.35, 0.4, 0.6, 1.2]);
+    for option_type in [OptionType::Call, OptionType::Put] {
+        let spot = f32x8::splat(100.0);
+        let (risk_free_rate, years_to_expiry, dividend_yield) =
+            (f32x8::splat(0.05), f32x8::splat(0.75), f32x8::splat(0.02));
+        let price = greeks_x8(option_type, spot, strike, volatility, risk_free_rate, years_to_expiry, dividend_yield).price;
+        let implied = implied_volatility_x8(option_type, price, spot, strike, risk_free_rate, years_to_expiry, dividend_yield);
+        println!("implied vol x8 {:?} {:?} vs {:?}", option_type, implied, volatility);
+        for (implied, volatility) in implied.to_array().iter().zip(volatility.to_array()) {
+            assert!((implied - volatility).abs() < 1e-3);
+        }
+    }
+}
+
+#[test]
+fn batch_implied_volatility_reports_invalid_prices() {
+    // 11 options, so the last chunk is partial, with an arbitrage violation in each chunk
+    let strike: Vec<f32> = (0..11).map(|i| 80.0 + 4.0 * (i as f32)).collect();
+    let mut price: Vec<f32> = strike
+        .iter()
+        .map(|&k| bs::call_price(100.0, k, 0.3, 0.05, 0.5, 0.02))
+        .collect();
+    price[2] = 0.01;
+    price[9] = 200.0;
+    // Within the bounds in f64, but too close to the upper bound to bracket in f32
+    price[5] = (100.0 * (-0.02f64 * 0.5).exp() - 3e-6) as f32;
+    let n = strike.len();
+
+    let implied = batch_implied_volatility(
+        OptionType::Call,
+        &price,
+        &vec![100.0; n],
+        &strike,
+        &vec![0.05; n],
+        &vec![0.5; n],
+        &vec![0.02; n]
+    );
+    assert_eq!(implied.len(), n);
+    for (i, result) in implied.iter().enumerate() {
+        println!("batch implied vol {} {:?}", strike[i], result);
+        match i {
+            2 | 5 | 9 => assert!(matches!(result, Err(PricingError::ArbitrageBoundsViolated { .. }))),
+            _ => assert!((result.unwrap() - 0.3).abs() < 1e-3),
+        }
+    }
+}
+
+#[test]
+fn normal_implied_volatility_round_trips() {
+    // Negative and positive spots and strikes, in and out of the money
+    for (spot, strike) in [(-2.0, -1.0), (-0.5, 0.5), (0.01, 0.0), (100.0, 80.0), (100.0, 130.0)] {
+        for option_type in [OptionType::Call, OptionType::Put] {
+            let normal_volatility = 0.3 * (1.0f64 + spot.abs());
+            let price: f64 = bs::bachelier_price(option_type, spot, strike, normal_volatility, 0.03, 1.5, 0.01);
+            let implied = normal_implied_volatility(option_type, price, spot, strike, 0.03, 1.5, 0.01).unwrap();
+            assert!((implied - normal_volatility).abs() < 1e-8 * normal_volatility);
+        }
+    }
+
+    // Below the discounted intrinsic value of the forward
+    assert!(matches!(
+        normal_implied_volatility(OptionType::Put, 0.5, -2.0, -1.0, 0.0, 1.0, 0.0),
+        Err(PricingError::ArbitrageBoundsViolated { lower_bound, .. }) if (lower_bound - 1.0f64).abs() < 1e-12
+    ));
+    assert!(matches!(
+        normal_implied_volatility(OptionType::Call, 1.0, f64::NAN, -1.0, 0.0, 1.0, 0.0),
+        Err(PricingError::InvalidSpot(spot)) if spot.is_nan()
+    ));
+
+    // Far from the money, where the bracket starts many doublings below the volatility
+    let price: f64 = bs::bachelier_price(OptionType::Call, 0.0, 10.0, 1.0, 0.03, 1.5, 0.01);
+    let implied = normal_implied_volatility(OptionType::Call, price, 0.0, 10.0, 0.03, 1.5, 0.01).unwrap();
+    assert!((implied - 1.0).abs() < 1e-8);
+
+    // Huge finite prices, the largest with a volatility that overflows
+    let implied = normal_implied_volatility(OptionType::Call, 1e300, 100.0, 100.0, 0.03, 1.5, 0.01).unwrap();
+    let price: f64 = bs::bachelier_price(OptionType::Call, 100.0, 100.0, implied, 0.03, 1.5, 0.01);
+    assert!((price - 1e300).abs() < 1e-8 * 1e300);
+    assert!(matches!(
+        normal_implied_volatility(OptionType::Call, f64::MAX, 100.0, 100.0, 0.03, 1.5, 0.01),
+        Err(PricingError::ArbitrageBoundsViolated { upper_bound, .. }) if upper_bound == f64::INFINITY
+    ));
+}
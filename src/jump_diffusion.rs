@@ -0,0 +1,286 @@
+// Merton's jump-diffusion model, in which the spot follows geometric Brownian motion between
+// jumps that arrive at the times of a Poisson process, the log of every jump being normally
+// distributed. The jumps fatten the tails of the distribution of the spot, which a constant
+// volatility cannot, and are added to the Heston model as the Bates model in heston.
+
+use crate::bs;
+use crate::error::PricingError;
+use crate::float::{ Float, SimdFloat };
+use crate::mc_simd::{ self, PathPayoff, PriceEstimate, SpotModel, Terminal, Vanilla };
+use crate::params::{ MarketData, OptionContract, SimulationConfig };
+
+/// The jumps of the spot. The drift is compensated for the mean jump, so that the jumps do
+/// not change the forward.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Jumps<F: Float = f32> {
+    /// Average number of jumps a year, lambda
+    pub intensity: F,
+    /// Mean of the log of every jump
+    pub mean_log_jump: F,
+    /// Standard deviation of the log of every jump
+    pub log_jump_volatility: F,
+}
+
+impl<F: Float> Jumps<F> {
+    pub fn new(intensity: F, mean_log_jump: F, log_jump_volatility: F) -> Jumps<F> {
+        Jumps { intensity, mean_log_jump, log_jump_volatility }
+    }
+
+    pub fn validate(&self) -> Result<(), PricingError> {
+        if !(self.intensity >= F::from_f64(0.0) && self.intensity.is_finite()) {
+            return Err(PricingError::InvalidJumpIntensity(self.intensity.to_f64()));
+        }
+        if !self.mean_log_jump.is_finite() {
+            return Err(PricingError::InvalidJumpMean(self.mean_log_jump.to_f64()));
+        }
+        if !(self.log_jump_volatility >= F::from_f64(0.0) && self.log_jump_volatility.is_finite()) {
+            return Err(PricingError::InvalidJumpVolatility(self.log_jump_volatility.to_f64()));
+        }
+        Ok(())
+    }
+
+    // E[exp(J)] - 1, the mean relative size of a jump
+    pub(crate) fn mean_jump(&self) -> f64 {
+        let log_jump_volatility = self.log_jump_volatility.to_f64();
+        (self.mean_log_jump.to_f64() + 0.5 * log_jump_volatility * log_jump_volatility).exp() - 1.0
+    }
+}
+
+// Upper bound on the number of jumps drawn in a single step
+const MAX_JUMPS_PER_STEP: usize = 1000;
+
+// The jumps over a step of dt years, splatted to V once for all the bundles. The number of
+// jumps in the step is drawn by inverting the Poisson CDF at a uniform, and the sum of the logs
+// of that many jumps from a single standard normal.
+pub(crate) struct JumpSteps<V> {
+    // The Poisson CDF at 0, 1, 2, ... jumps, up to the count where the rest of the tail is
+    // lost to rounding. Entries that round to 1 in V are left out, since no uniform reaches them.
+    cdf: Vec<V>,
+    mean_log_jump: V,
+    log_jump_volatility: V,
+    // -lambda * E[exp(J) - 1] dt, which compensates for the mean jump
+    compensator: V,
+}
+
+impl<V: SimdFloat> JumpSteps<V> {
+    pub(crate) fn new<F: Float>(jumps: &Jumps<F>, dt: f64) -> JumpSteps<V> {
+        let mean_jumps = jumps.intensity.to_f64() * dt;
+        let mut probability = (-mean_jumps).exp();
+        let mut cdf = vec![probability];
+        let mut total = probability;
+        // The total often settles just below 1 in f64, so also stop once the probabilities fall
+        // past the mode below the rounding of the total
+        while 1.0 - total > f64::EPSILON && cdf.len() < MAX_JUMPS_PER_STEP {
+            probability *= mean_jumps / (cdf.len() as f64);
+            if (cdf.len() as f64) > mean_jumps && probability < f64::EPSILON * total {
+                break;
+            }
+            total += probability;
+            cdf.push(total);
+        }
+
+        JumpSteps {
+            cdf: cdf
+                .into_iter()
+                .filter(|&cdf| V::Scalar::from_f64(cdf).to_f64() < 1.0)
+                .map(V::splat)
+                .collect(),
+            mean_log_jump: V::splat(jumps.mean_log_jump.to_f64()),
+            log_jump_volatility: V::splat(jumps.log_jump_volatility.to_f64()),
+            compensator: V::splat(-jumps.intensity.to_f64() * jumps.mean_jump() * dt),
+        }
+    }
+
+    // The compensated log of the product of the jumps of a step, given the normal of the sizes
+    // and the uniform of the number of jumps
+    #[inline(always)]
+    pub(crate) fn log_jump(&self, normal: V, uniform: V) -> V {
+        let one = V::splat(1.0);
+        let count = self.cdf.iter().fold(V::splat(0.0), |count, &cdf| {
+            cdf.cmp_lt(uniform).blend(count + one, count)
+        });
+        V::mul_add(
+            count.sqrt() * self.log_jump_volatility,
+            normal,
+            V::mul_add(count, self.mean_log_jump, self.compensator)
+        )
+    }
+}
+
+// The constants of a step of Merton's model. The state of every path is the log of the spot
+// over the initial spot.
+struct MertonSteps<V> {
+    spot: V,
+    nudt: V,
+    sidt: V,
+    jumps: JumpSteps<V>,
+}
+
+impl<V: SimdFloat> SpotModel<V> for MertonSteps<V> {
+    type State = V;
+
+    fn draws_per_step(&self) -> (usize, usize) {
+        (2, 1)
+    }
+
+    fn start(&self) -> V {
+        V::splat(0.0)
+    }
+
+    fn step(&self, log_mult: &mut V, _step: usize, normals: &[V], uniforms: &[V]) -> V {
+        let log_jump = self.jumps.log_jump(normals[1], uniforms[0]);
+        *log_mult = V::mul_add(normals[0], self.sidt, *log_mult + self.nudt + log_jump);
+        self.spot * log_mult.exp()
+    }
+}
+
+/// Price a path-dependent option expiring in `years_to_expiry` years in Merton's model, with
+/// the standard error and confidence interval of the price. The payoff is shown the spot after
+/// each of the `config.steps` steps, as in [`mc_simd::price_path`].
+pub fn price_path<F: Float, P: PathPayoff<F::Lanes>>(
+    jumps: &Jumps<F>,
+    market: &MarketData<F>,
+    years_to_expiry: F,
+    config: &SimulationConfig,
+    payoff: &P
+) -> Result<PriceEstimate<F>, PricingError> {
+    jumps.validate()?;
+    mc_simd::validate_path(market, years_to_expiry, config)?;
+
+    let volatility = market.volatility.to_f64();
+    let risk_free_rate = market.risk_free_rate.to_f64();
+    let dt = years_to_expiry.to_f64() / (config.steps as f64);
+    let drift = risk_free_rate - market.carry_yield().to_f64() - 0.5 * volatility * volatility;
+    let steps = MertonSteps {
+        spot: F::Lanes::splat(market.spot.to_f64()),
+        nudt: F::Lanes::splat(drift * dt),
+        sidt: F::Lanes::splat(volatility * dt.sqrt()),
+        jumps: JumpSteps::new(jumps, dt),
+    };
+    Ok(mc_simd::monte_carlo_model_pricing(
+        &steps,
+        risk_free_rate,
+        years_to_expiry.to_f64(),
+        config,
+        payoff
+    ))
+}
+
+/// Price a European option in Merton's model, with the standard error and confidence interval
+/// of the price
+pub fn price<F: Float>(
+    contract: &OptionContract<F>,
+    jumps: &Jumps<F>,
+    market: &MarketData<F>,
+    config: &SimulationConfig
+) -> Result<PriceEstimate<F>, PricingError> {
+    contract.validate()?;
+
+    let payoff = Vanilla::<F::Lanes>::new(contract.strike.to_f64(), contract.option_type.call_mult());
+    price_path(jumps, market, contract.years_to_expiry, config, &Terminal(payoff))
+}
+
+/// Price a European option in Merton's model in closed form with [`bs::merton_price`]
+pub fn analytic_price<F: Float>(
+    contract: &OptionContract<F>,
+    jumps: &Jumps<F>,
+    market: &MarketData<F>
+) -> Result<F, PricingError> {
+    contract.validate()?;
+    jumps.validate()?;
+    market.validate()?;
+
+    Ok(bs::merton_price(
+        contract.option_type,
+        market.spot,
+        contract.strike,
+        market.volatility,
+        market.risk_free_rate,
+        contract.years_to_expiry,
+        market.carry_yield(),
+        jumps.intensity,
+        jumps.mean_log_jump,
+        jumps.log_jump_volatility
+    ))
+}
+
+#[cfg(test)]
+use crate::params::OptionType;
+#[cfg(test)]
+use wide::{ f32x8, f64x4 };
+
+#[test]
+fn merton_matches_series_price() {
+    // The diffusion and the jumps are both drawn exactly, so a single step is as good as many
+    let market = MarketData::new(100.0, 0.2, 0.05, 0.01);
+    let jumps = Jumps::new(0.8, -0.15, 0.2);
+    for steps in [1, 50] {
+        let config = SimulationConfig::builder().steps(steps).num_trials(100000).seed(111).antithetic(true).build().unwrap();
+        for (option_type, strike) in [(OptionType::Put, 70.0), (OptionType::Call, 130.0)] {
+            let contract = OptionContract::new(option_type, strike, 1.0);
+            let estimate = price(&contract, &jumps, &market, &config).unwrap();
+            let actual_price = analytic_price(&contract, &jumps, &market).unwrap();
+            println!("merton {} steps {:?} {} {:?} vs {}", steps, option_type, strike, estimate, actual_price);
+            assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+        }
+    }
+}
+
+#[test]
+fn f32_merton_with_frequent_jumps() {
+    // Many jumps a step, so that the jump counts come from far into the Poisson CDF
+    let market = MarketData::new(100.0f32, 0.15, 0.03, 0.0);
+    let jumps = Jumps::new(50.0f32, 0.0, 0.04);
+    let config = SimulationConfig::builder().steps(4).num_trials(100000).seed(112).build().unwrap();
+    let contract = OptionContract::new(OptionType::Put, 95.0, 0.5);
+    let estimate = price(&contract, &jumps, &market, &config).unwrap();
+    let actual_price = analytic_price(&contract, &jumps, &market).unwrap();
+    println!("f32 merton {:?} vs {}", estimate, actual_price);
+    assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+}
+
+#[test]
+fn jump_count_tables_stop_at_the_tail() {
+    // Rare jumps on fine steps, the Bates test's 0.5 a year on 32 steps, and the frequent jumps
+    // above, none of which reach 1 in f64
+    for mean_jumps in [0.1, 0.015625, 6.25] {
+        let jumps = Jumps::new(mean_jumps, -0.1, 0.15);
+        let table_f64 = JumpSteps::<f64x4>::new(&jumps, 1.0).cdf;
+        let table_f32 = JumpSteps::<f32x8>::new(&jumps, 1.0).cdf;
+        println!("jump counts {} f64 {} f32 {}", mean_jumps, table_f64.len(), table_f32.len());
+        assert!(table_f64.len() < 50 && table_f32.len() <= table_f64.len());
+        assert!(1.0 - table_f64.last().unwrap().to_array()[0] < 1e-15);
+        assert!(table_f32.iter().all(|cdf| cdf.to_array()[0] < 1.0));
+    }
+    // Without jumps there is nothing to compare the uniform with
+    assert!(JumpSteps::<f32x8>::new(&Jumps::new(0.0, -0.1, 0.15), 1.0).cdf.is_empty());
+}
+
+#[test]
+fn jumps_keep_the_forward() {
+    // The drift is compensated for the jumps, so the mean spot at expiry is the forward
+    let market = MarketData::new(100.0, 0.2, 0.05, 0.02);
+    let jumps = Jumps::new(2.0, -0.3, 0.25);
+    let config = SimulationConfig::builder().steps(20).num_trials(100000).seed(113).build().unwrap();
+    let estimate = price_path(&jumps, &market, 2.0, &config, &Terminal(|spot: f64x4| spot)).unwrap();
+    let forward_value = 100.0 * (-0.02f64 * 2.0).exp();
+    println!("merton forward {:?} vs {}", estimate, forward_value);
+    assert!((estimate.price - forward_value).abs() < 4.0 * estimate.std_error);
+}
+
+#[test]
+fn invalid_jump_parameters() {
+    let market = MarketData::new(100.0, 0.2, 0.05, 0.0);
+    let contract = OptionContract::new(OptionType::Call, 100.0, 1.0);
+    let config = SimulationConfig::new(10, 100);
+    let jumps = Jumps::new(1.0, -0.1, 0.2);
+    let cases = [
+        (Jumps { intensity: -1.0, ..jumps }, PricingError::InvalidJumpIntensity(-1.0)),
+        (Jumps { mean_log_jump: f64::INFINITY, ..jumps }, PricingError::InvalidJumpMean(f64::INFINITY)),
+        (Jumps { log_jump_volatility: -0.2, ..jumps }, PricingError::InvalidJumpVolatility(-0.2)),
+    ];
+    for (jumps, error) in cases {
+        assert_eq!(price(&contract, &jumps, &market, &config), Err(error));
+        assert_eq!(analytic_price(&contract, &jumps, &market), Err(error));
+    }
+}
@@ -3,9 +3,40 @@
 // SIMD PRNG
 mod rand32x8;
 
-// Black-scholes priceer, used to test Monte-carlo simulation pricers
-mod bs;
+// Black-scholes prices and Greeks, also used to test Monte-carlo simulation pricers
+pub mod bs;
+// Black-scholes prices and Greeks of 8 options at a time, for option chains
+pub mod bs_simd;
+// Implied volatility from option prices
+pub mod implied_vol;
+
+// Float and SIMD lane types the pricers are generic over
+pub mod float;
+// Contract, market and simulation parameters
+pub mod params;
+
+// Errors for invalid parameters
+pub mod error;
 
 // Monte-carlo simulation modules
 pub mod mc;
 pub mod mc_simd;
+
+// American options priced with Longstaff-Schwartz least-squares Monte-carlo
+pub mod american;
+// Asian options on the average spot, with a geometric average control variate
+pub mod asian;
+// Bachelier model, an arithmetic Brownian motion for underlyings that can go negative
+pub mod bachelier;
+// Barrier options, with a Brownian bridge correction for continuous monitoring
+pub mod barrier;
+// Basket and spread options on several correlated underlyings
+pub mod basket;
+// Heston stochastic volatility model, simulated and priced by Fourier inversion
+pub mod heston;
+// Merton jump-diffusion model, whose jumps also make the Heston model the Bates model
+pub mod jump_diffusion;
+// Local volatility model, built from an implied volatility surface by Dupire's formula
+pub mod local_vol;
+// SABR stochastic volatility model of a forward, with Hagan's implied volatility in bs
+pub mod sabr;
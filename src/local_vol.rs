@@ -0,0 +1,613 @@
+// Local volatility, in which the volatility is a function of the time and the spot, chosen so
+// that the model reprices every vanilla of an implied volatility surface. Dupire's formula gives
+// it from the derivatives of the total implied variance, taken by finite differences on the grid
+// of the surface. The simulation interpolates it at the spot of every lane with splines written
+// as a polynomial plus a sum of hinge functions, which evaluate on SIMD lanes without looking up
+// the interval of every lane.
+
+use crate::error::PricingError;
+use crate::float::{ Float, SimdFloat };
+use crate::mc_simd::{ self, PathPayoff, PriceEstimate, SpotModel, Terminal, Vanilla };
+use crate::params::{ MarketData, OptionContract, SimulationConfig };
+
+// Dupire's formula takes second derivatives in the strike, which need three strikes
+const MIN_STRIKES: usize = 3;
+
+/// How the local volatility is interpolated in the spot between the strikes of the surface. In
+/// time the local variance is always interpolated linearly between the expiries, and it is
+/// flat outside both grids.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Linear in the log of the spot, so bilinear with the time
+    Linear,
+    /// A natural cubic spline in the log of the spot, which has a continuous skew and curvature
+    Cubic,
+}
+
+/// Implied volatilities of vanillas on a grid of expiries and strikes, one row per expiry
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImpliedVolSurface<F: Float = f32> {
+    /// Years to expiry, strictly increasing
+    pub expiries: Vec<F>,
+    /// Strikes, strictly increasing
+    pub strikes: Vec<F>,
+    /// volatilities[i][j] is the implied volatility at expiries[i] and strikes[j]
+    pub volatilities: Vec<Vec<F>>,
+}
+
+impl<F: Float> ImpliedVolSurface<F> {
+    pub fn new(expiries: Vec<F>, strikes: Vec<F>, volatilities: Vec<Vec<F>>) -> ImpliedVolSurface<F> {
+        ImpliedVolSurface { expiries, strikes, volatilities }
+    }
+
+    pub fn validate(&self) -> Result<(), PricingError> {
+        validate_grid(&self.expiries, &self.strikes, &self.volatilities)
+    }
+}
+
+/// Local volatilities on a grid of times and spots, one row per time
+#[derive(Clone, Debug, PartialEq)]
+pub struct LocalVolSurface<F: Float = f32> {
+    /// Years from today, strictly increasing
+    pub expiries: Vec<F>,
+    /// Spots, strictly increasing
+    pub strikes: Vec<F>,
+    /// volatilities[i][j] is the local volatility at expiries[i] and strikes[j]
+    pub volatilities: Vec<Vec<F>>,
+    pub interpolation: Interpolation,
+}
+
+impl<F: Float> LocalVolSurface<F> {
+    /// A surface of the given local volatilities, interpolated linearly
+    pub fn new(expiries: Vec<F>, strikes: Vec<F>, volatilities: Vec<Vec<F>>) -> LocalVolSurface<F> {
+        LocalVolSurface { expiries, strikes, volatilities, interpolation: Interpolation::Linear }
+    }
+
+    /// The local volatilities that reprice the vanillas of an implied volatility surface, by
+    /// Dupire's formula at every point of its grid, interpolated linearly. Only the spot, the
+    /// risk-free rate and the dividend yield of the market are used.
+    pub fn from_implied(
+        implied: &ImpliedVolSurface<F>,
+        market: &MarketData<F>
+    ) -> Result<LocalVolSurface<F>, PricingError> {
+        implied.validate()?;
+        market.validate()?;
+
+        let spot = market.spot.to_f64();
+        let carry = market.risk_free_rate.to_f64() - market.carry_yield().to_f64();
+        let expiries: Vec<f64> = implied.expiries.iter().map(|expiry| expiry.to_f64()).collect();
+        let log_strikes: Vec<f64> = implied.strikes.iter().map(|strike| strike.to_f64().ln()).collect();
+        // Total implied variances w = sigma^2 T
+        let total_variances: Vec<Vec<f64>> = implied.volatilities
+            .iter()
+            .zip(&expiries)
+            .map(|(row, &expiry)| row.iter().map(|volatility| volatility.to_f64().powi(2) * expiry).collect())
+            .collect();
+
+        let mut volatilities = Vec::with_capacity(expiries.len());
+        for (i, &expiry) in expiries.iter().enumerate() {
+            let log_forward = spot.ln() + carry * expiry;
+            let mut row = Vec::with_capacity(log_strikes.len());
+            for (j, &log_strike) in log_strikes.iter().enumerate() {
+                let total_variance = total_variances[i][j];
+                let (slope, curvature) = strike_derivatives(&log_strikes, &total_variances[i], j);
+                // dw/dT at a fixed strike, turned into the derivative at a fixed log-moneyness y,
+                // which moves with the forward
+                let time_derivative = time_derivative(&expiries, &total_variances, i, j) + slope * carry;
+
+                let moneyness = log_strike - log_forward;
+                let denominator = 1.0 - moneyness / total_variance * slope +
+                    0.25 * (-0.25 - 1.0 / total_variance + (moneyness / total_variance).powi(2)) * slope * slope +
+                    0.5 * curvature;
+                let local_variance = time_derivative / denominator;
+                if !(local_variance > 0.0 && local_variance.is_finite()) {
+                    return Err(PricingError::InvalidLocalVariance {
+                        years_to_expiry: expiry,
+                        strike: log_strike.exp(),
+                        local_variance,
+                    });
+                }
+                row.push(F::from_f64(local_variance.sqrt()));
+            }
+            volatilities.push(row);
+        }
+
+        Ok(LocalVolSurface::new(implied.expiries.clone(), implied.strikes.clone(), volatilities))
+    }
+
+    /// The same surface, interpolated with the given interpolation
+    pub fn with_interpolation(self, interpolation: Interpolation) -> LocalVolSurface<F> {
+        LocalVolSurface { interpolation, ..self }
+    }
+
+    pub fn validate(&self) -> Result<(), PricingError> {
+        validate_grid(&self.expiries, &self.strikes, &self.volatilities)
+    }
+
+    /// The local volatility at `years` from today and spot `spot`, interpolated as in the
+    /// simulation
+    pub fn volatility(&self, years: F, spot: F) -> F {
+        let spline = self.spline_at(years.to_f64());
+        F::from_f64(spline.evaluate(spot.to_f64().ln()))
+    }
+
+    // The spline in the log spot of the local volatility at a time, through the local variances
+    // interpolated linearly in time at every strike
+    fn spline_at(&self, years: f64) -> Spline {
+        let last = self.expiries.len() - 1;
+        let next = self.expiries.iter().position(|expiry| expiry.to_f64() > years).unwrap_or(last + 1);
+        let volatilities: Vec<f64> = if next == 0 || next > last {
+            self.volatilities[next.min(last)].iter().map(|volatility| volatility.to_f64()).collect()
+        } else {
+            let previous_expiry = self.expiries[next - 1].to_f64();
+            let weight = (years - previous_expiry) / (self.expiries[next].to_f64() - previous_expiry);
+            self.volatilities[next - 1]
+                .iter()
+                .zip(&self.volatilities[next])
+                .map(|(previous, next)| {
+                    ((1.0 - weight) * previous.to_f64().powi(2) + weight * next.to_f64().powi(2)).sqrt()
+                })
+                .collect()
+        };
+        let log_strikes: Vec<f64> = self.strikes.iter().map(|strike| strike.to_f64().ln()).collect();
+        Spline::new(&log_strikes, &volatilities, self.interpolation)
+    }
+}
+
+fn validate_grid<F: Float>(expiries: &[F], strikes: &[F], volatilities: &[Vec<F>]) -> Result<(), PricingError> {
+    if expiries.is_empty() {
+        return Err(PricingError::TooFewGridPoints { num_points: 0, min_points: 1 });
+    }
+    if strikes.len() < MIN_STRIKES {
+        return Err(PricingError::TooFewGridPoints { num_points: strikes.len(), min_points: MIN_STRIKES });
+    }
+    for &expiry in expiries {
+        if !(expiry > F::from_f64(0.0) && expiry.is_finite()) {
+            return Err(PricingError::InvalidYearsToExpiry(expiry.to_f64()));
+        }
+    }
+    for &strike in strikes {
+        if !(strike > F::from_f64(0.0) && strike.is_finite()) {
+            return Err(PricingError::InvalidStrike(strike.to_f64()));
+        }
+    }
+    for points in [expiries, strikes] {
+        for pair in points.windows(2) {
+            if pair[1] <= pair[0] {
+                return Err(PricingError::UnsortedGrid(pair[1].to_f64()));
+            }
+        }
+    }
+    if volatilities.len() != expiries.len() {
+        return Err(PricingError::GridSizeMismatch { expected: expiries.len(), actual: volatilities.len() });
+    }
+    for row in volatilities {
+        if row.len() != strikes.len() {
+            return Err(PricingError::GridSizeMismatch { expected: strikes.len(), actual: row.len() });
+        }
+        for &volatility in row {
+            if !(volatility > F::from_f64(0.0) && volatility.is_finite()) {
+                return Err(PricingError::InvalidVolatility(volatility.to_f64()));
+            }
+        }
+    }
+    Ok(())
+}
+
+// The first and second derivatives of w in the log strike at strike j, from the three-point
+// differences on the nonuniform grid, one-sided at the first and last strikes
+fn strike_derivatives(log_strikes: &[f64], w: &[f64], j: usize) -> (f64, f64) {
+    let last = log_strikes.len() - 1;
+    let centre = j.clamp(1, last - 1);
+    let left_step = log_strikes[centre] - log_strikes[centre - 1];
+    let right_step = log_strikes[centre + 1] - log_strikes[centre];
+    let curvature = 2.0 * (left_step * w[centre + 1] - (left_step + right_step) * w[centre] + right_step * w[centre - 1]) /
+        (left_step * right_step * (left_step + right_step));
+    let slope = if j == 0 {
+        (w[1] - w[0]) / left_step - 0.5 * curvature * left_step
+    } else if j == last {
+        (w[last] - w[last - 1]) / right_step + 0.5 * curvature * right_step
+    } else {
+        (left_step * left_step * w[j + 1] + (right_step * right_step - left_step * left_step) * w[j] -
+            right_step * right_step * w[j - 1]) / (left_step * right_step * (left_step + right_step))
+    };
+    (slope, curvature)
+}
+
+// The derivative of w in the time to expiry at expiry i and strike j. w is zero today, which
+// gives the difference before the first expiry, and the last uses the backward difference.
+fn time_derivative(expiries: &[f64], w: &[Vec<f64>], i: usize, j: usize) -> f64 {
+    if i == 0 {
+        return w[0][j] / expiries[0];
+    }
+    let before = expiries[i] - expiries[i - 1];
+    if i == expiries.len() - 1 {
+        return (w[i][j] - w[i - 1][j]) / before;
+    }
+    let after = expiries[i + 1] - expiries[i];
+    (before * before * w[i + 1][j] + (after * after - before * before) * w[i][j] - after * after * w[i - 1][j]) /
+        (before * after * (before + after))
+}
+
+// A linear or natural cubic spline through (x_j, y_j), written as a polynomial in x - x_0 plus
+// weights times the hinge functions max(x - x_j, 0) raised to the degree of the spline, at the
+// inner nodes. Both splines only change their highest derivative at a node, so the one
+// polynomial holds on the whole grid. x is clamped to the grid, so the spline is flat outside.
+struct Spline {
+    start: f64,
+    end: f64,
+    polynomial: [f64; 4],
+    nodes: Vec<f64>,
+    weights: Vec<f64>,
+    cubic: bool,
+}
+
+impl Spline {
+    fn new(xs: &[f64], ys: &[f64], interpolation: Interpolation) -> Spline {
+        let n = xs.len();
+        let steps: Vec<f64> = xs.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        let slopes: Vec<f64> = (0..n - 1).map(|j| (ys[j + 1] - ys[j]) / steps[j]).collect();
+
+        let (polynomial, weights) = match interpolation {
+            Interpolation::Linear => (
+                [ys[0], slopes[0], 0.0, 0.0],
+                slopes.windows(2).map(|pair| pair[1] - pair[0]).collect()
+            ),
+            Interpolation::Cubic => {
+                // The second derivatives m at the nodes, zero at the ends, from the tridiagonal
+                // system of the continuity of the first derivative, by the Thomas algorithm
+                let mut m = vec![0.0; n];
+                let mut diagonal = vec![0.0; n];
+                let mut rhs = vec![0.0; n];
+                for j in 1..n - 1 {
+                    diagonal[j] = 2.0 * (steps[j - 1] + steps[j]);
+                    rhs[j] = 6.0 * (slopes[j] - slopes[j - 1]);
+                    if j > 1 {
+                        let factor = steps[j - 1] / diagonal[j - 1];
+                        diagonal[j] -= factor * steps[j - 1];
+                        rhs[j] -= factor * rhs[j - 1];
+                    }
+                }
+                for j in (1..n - 1).rev() {
+                    m[j] = (rhs[j] - steps[j] * m[j + 1]) / diagonal[j];
+                }
+
+                let cubics: Vec<f64> = (0..n - 1).map(|j| (m[j + 1] - m[j]) / (6.0 * steps[j])).collect();
+                (
+                    [ys[0], slopes[0] - steps[0] * (2.0 * m[0] + m[1]) / 6.0, 0.5 * m[0], cubics[0]],
+                    cubics.windows(2).map(|pair| pair[1] - pair[0]).collect()
+                )
+            }
+        };
+
+        Spline {
+            start: xs[0],
+            end: xs[n - 1],
+            polynomial,
+            nodes: xs[1..n - 1].to_vec(),
+            weights,
+            cubic: interpolation == Interpolation::Cubic,
+        }
+    }
+
+    fn evaluate(&self, x: f64) -> f64 {
+        let x = x.clamp(self.start, self.end);
+        let u = x - self.start;
+        let [c0, c1, c2, c3] = self.polynomial;
+        let mut y = ((c3 * u + c2) * u + c1) * u + c0;
+        for (&node, &weight) in self.nodes.iter().zip(&self.weights) {
+            let hinge = (x - node).max(0.0);
+            y += weight * if self.cubic { hinge * hinge * hinge } else { hinge };
+        }
+        y
+    }
+}
+
+// The spline of the local volatility over a step, splatted to V
+struct SplineLanes<V> {
+    polynomial: [V; 4],
+    weights: Vec<V>,
+}
+
+// The constants of a step of the local volatility model, splatted to V once for all the bundles.
+// The state of every path is the log of the spot.
+struct LocalVolSteps<V> {
+    log_spot: V,
+    // (r - q) dt
+    drift: V,
+    half_dt: V,
+    sqrt_dt: V,
+    // The log strikes of the surface, which bound the spline and are its inner nodes
+    min_log_spot: V,
+    max_log_spot: V,
+    nodes: Vec<V>,
+    cubic: bool,
+    // The spline of every step, at the time the step starts
+    splines: Vec<SplineLanes<V>>,
+}
+
+impl<V: SimdFloat> LocalVolSteps<V> {
+    #[inline(always)]
+    fn volatility(&self, step: usize, log_spot: V) -> V {
+        let spline = &self.splines[step];
+        let x = log_spot.fast_max(self.min_log_spot).fast_min(self.max_log_spot);
+        let u = x - self.min_log_spot;
+        let [c0, c1, c2, c3] = spline.polynomial;
+        let polynomial = V::mul_add(V::mul_add(V::mul_add(c3, u, c2), u, c1), u, c0);
+        self.nodes.iter().zip(&spline.weights).fold(polynomial, |volatility, (&node, &weight)| {
+            let hinge = (x - node).fast_max(V::splat(0.0));
+            let hinge = if self.cubic { hinge * hinge * hinge } else { hinge };
+            V::mul_add(weight, hinge, volatility)
+        })
+    }
+}
+
+impl<V: SimdFloat> SpotModel<V> for LocalVolSteps<V> {
+    type State = V;
+
+    fn draws_per_step(&self) -> (usize, usize) {
+        (1, 0)
+    }
+
+    fn start(&self) -> V {
+        self.log_spot
+    }
+
+    fn step(&self, log_spot: &mut V, step: usize, normals: &[V], _uniforms: &[V]) -> V {
+        let volatility = self.volatility(step, *log_spot);
+        let drift = V::mul_add(-(volatility * volatility), self.half_dt, self.drift);
+        *log_spot = V::mul_add(volatility * self.sqrt_dt, normals[0], *log_spot + drift);
+        log_spot.exp()
+    }
+}
+
+/// Price a path-dependent option expiring in `years_to_expiry` years in the local volatility
+/// model of `surface`, with the standard error and confidence interval of the price. The
+/// volatility of every step is the local volatility at the start of the step. The volatility of
+/// the market is not used. The payoff is shown the spot after each of the `config.steps` steps,
+/// as in [`mc_simd::price_path`].
+pub fn price_path<F: Float, P: PathPayoff<F::Lanes>>(
+    surface: &LocalVolSurface<F>,
+    market: &MarketData<F>,
+    years_to_expiry: F,
+    config: &SimulationConfig,
+    payoff: &P
+) -> Result<PriceEstimate<F>, PricingError> {
+    surface.validate()?;
+    mc_simd::validate_path(market, years_to_expiry, config)?;
+
+    let risk_free_rate = market.risk_free_rate.to_f64();
+    let dt = years_to_expiry.to_f64() / (config.steps as f64);
+    let splines: Vec<Spline> = (0..config.steps).map(|step| surface.spline_at((step as f64) * dt)).collect();
+    let steps = LocalVolSteps {
+        log_spot: F::Lanes::splat(market.spot.to_f64().ln()),
+        drift: F::Lanes::splat((risk_free_rate - market.carry_yield().to_f64()) * dt),
+        half_dt: F::Lanes::splat(0.5 * dt),
+        sqrt_dt: F::Lanes::splat(dt.sqrt()),
+        min_log_spot: F::Lanes::splat(splines[0].start),
+        max_log_spot: F::Lanes::splat(splines[0].end),
+        nodes: splines[0].nodes
+            .iter()
+            .map(|&node| F::Lanes::splat(node))
+            .collect(),
+        cubic: surface.interpolation == Interpolation::Cubic,
+        splines: splines
+            .iter()
+            .map(|spline| SplineLanes {
+                polynomial: spline.polynomial.map(F::Lanes::splat),
+                weights: spline.weights
+                    .iter()
+                    .map(|&weight| F::Lanes::splat(weight))
+                    .collect(),
+            })
+            .collect(),
+    };
+    Ok(mc_simd::monte_carlo_model_pricing(
+        &steps,
+        risk_free_rate,
+        years_to_expiry.to_f64(),
+        config,
+        payoff
+    ))
+}
+
+/// Price a European option in the local volatility model of `surface`, with the standard error
+/// and confidence interval of the price
+pub fn price<F: Float>(
+    contract: &OptionContract<F>,
+    surface: &LocalVolSurface<F>,
+    market: &MarketData<F>,
+    config: &SimulationConfig
+) -> Result<PriceEstimate<F>, PricingError> {
+    contract.validate()?;
+
+    let payoff = Vanilla::<F::Lanes>::new(contract.strike.to_f64(), contract.option_type.call_mult());
+    price_path(surface, market, contract.years_to_expiry, config, &Terminal(payoff))
+}
+
+#[cfg(test)]
+use crate::bs;
+#[cfg(test)]
+use crate::params::OptionType;
+
+// A smile in the log-moneyness y = ln(K / F) of every expiry, free of arbitrage
+#[cfg(test)]
+fn smile_surface(market: &MarketData<f64>) -> ImpliedVolSurface<f64> {
+    let expiries = vec![0.25, 0.5, 0.75, 1.0, 1.5, 2.0];
+    let strikes: Vec<f64> = (0..31).map(|j| 50.0 + 5.0 * (j as f64)).collect();
+    let volatilities = expiries
+        .iter()
+        .map(|&expiry| {
+            let forward = market.spot * ((market.risk_free_rate - market.dividend_yield) * expiry).exp();
+            strikes
+                .iter()
+                .map(|&strike| {
+                    let moneyness = (strike / forward).ln();
+                    0.2 - 0.1 * moneyness + 0.1 * moneyness * moneyness
+                })
+                .collect()
+        })
+        .collect();
+    ImpliedVolSurface::new(expiries, strikes, volatilities)
+}
+
+#[test]
+fn flat_surface_matches_black_scholes() {
+    let market = MarketData::new(100.0, 0.25, 0.04, 0.01);
+    let strikes = vec![60.0, 80.0, 95.0, 100.0, 110.0, 140.0];
+    let implied = ImpliedVolSurface::new(vec![0.5, 1.0, 2.0], strikes.clone(), vec![vec![0.25; strikes.len()]; 3]);
+    let surface = LocalVolSurface::from_implied(&implied, &market).unwrap();
+    for row in &surface.volatilities {
+        for &volatility in row {
+            assert!((volatility - 0.25).abs() < 1e-12);
+        }
+    }
+
+    // With a flat surface the log spot is drawn exactly, so a single step is as good as many
+    let contract = OptionContract::new(OptionType::Call, 105.0, 1.5);
+    let actual_price = bs::price(OptionType::Call, 100.0, 105.0, 0.25, 0.04, 1.5, 0.01);
+    for steps in [1, 10] {
+        let config = SimulationConfig::builder().steps(steps).num_trials(100000).seed(121).antithetic(true).build().unwrap();
+        let estimate = price(&contract, &surface, &market, &config).unwrap();
+        println!("flat local vol {} steps {:?} vs {}", steps, estimate, actual_price);
+        assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+    }
+}
+
+#[test]
+fn local_vol_reprices_the_smile() {
+    // The grid vanillas expiring in a year, on the skewed wings where the local volatility is
+    // furthest from the implied one, and on steps fine enough that the scheme bias is below the
+    // noise
+    let market = MarketData::new(100.0, 0.2, 0.03, 0.01);
+    let implied = smile_surface(&market);
+    let surface = LocalVolSurface::from_implied(&implied, &market).unwrap();
+    let config = SimulationConfig::builder().steps(64).num_trials(100000).seed(122).antithetic(true).build().unwrap();
+    let cases = [(Interpolation::Linear, OptionType::Put, 80.0), (Interpolation::Cubic, OptionType::Call, 120.0)];
+    for (interpolation, option_type, strike) in cases {
+        let surface = surface.clone().with_interpolation(interpolation);
+        let contract = OptionContract::new(option_type, strike, 1.0);
+        let implied_volatility = implied.volatilities[3][((strike - 50.0) / 5.0) as usize];
+        let estimate = price(&contract, &surface, &market, &config).unwrap();
+        let actual_price = bs::price(option_type, 100.0, strike, implied_volatility, 0.03, 1.0, 0.01);
+        println!("local vol {:?} {:?} {} {:?} vs {}", interpolation, option_type, strike, estimate, actual_price);
+        assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+    }
+}
+
+#[test]
+fn local_vol_scheme_bias_shrinks_with_steps() {
+    // The volatility of each step is read at its start, which on 4 steps underprices the put
+    // below the spot by about 0.12, and the bias falls about as fast as the step size
+    let market = MarketData::new(100.0, 0.2, 0.03, 0.01);
+    let implied = smile_surface(&market);
+    let surface = LocalVolSurface::from_implied(&implied, &market).unwrap();
+    let contract = OptionContract::new(OptionType::Put, 80.0, 1.0);
+    let actual_price = bs::price(OptionType::Put, 100.0, 80.0, implied.volatilities[3][6], 0.03, 1.0, 0.01);
+    let bias = |steps: usize| {
+        let config = SimulationConfig::builder().steps(steps).num_trials(200000).seed(124).antithetic(true).build().unwrap();
+        let estimate = price(&contract, &surface, &market, &config).unwrap();
+        println!("local vol {} steps {:?} vs {}", steps, estimate, actual_price);
+        (estimate.price - actual_price, estimate.std_error)
+    };
+    let (coarse_bias, coarse_std_error) = bias(4);
+    let (medium_bias, _) = bias(16);
+    let (fine_bias, fine_std_error) = bias(64);
+    assert!(coarse_bias < -4.0 * coarse_std_error);
+    assert!(medium_bias.abs() < 0.5 * coarse_bias.abs());
+    assert!(fine_bias.abs() < 4.0 * fine_std_error);
+}
+
+#[test]
+fn interpolation_passes_through_the_grid() {
+    let market = MarketData::new(100.0, 0.2, 0.03, 0.01);
+    let surface = LocalVolSurface::from_implied(&smile_surface(&market), &market).unwrap();
+    for interpolation in [Interpolation::Linear, Interpolation::Cubic] {
+        let surface = surface.clone().with_interpolation(interpolation);
+        for (i, &expiry) in surface.expiries.iter().enumerate() {
+            for (j, &strike) in surface.strikes.iter().enumerate() {
+                assert!((surface.volatility(expiry, strike) - surface.volatilities[i][j]).abs() < 1e-12);
+            }
+        }
+        // Flat outside the grid
+        assert!((surface.volatility(0.1, 30.0) - surface.volatilities[0][0]).abs() < 1e-12);
+        assert!((surface.volatility(3.0, 300.0) - surface.volatilities[5][30]).abs() < 1e-12);
+    }
+
+    // Linear in the variance in time, then linear in the log spot, between the nodes
+    let surface = LocalVolSurface::new(vec![1.0, 2.0], vec![80.0, 100.0, 125.0], vec![vec![0.3, 0.2, 0.1], vec![0.4, 0.4, 0.4]]);
+    let spot = (80.0f64 * 100.0).sqrt();
+    let expected = 0.5 * (0.5 * 0.3 * 0.3 + 0.5 * 0.4 * 0.4f64).sqrt() + 0.5 * (0.5 * 0.2 * 0.2 + 0.5 * 0.4 * 0.4f64).sqrt();
+    assert!((surface.volatility(1.5, spot) - expected).abs() < 1e-12);
+}
+
+#[test]
+fn f32_local_vol() {
+    let market = MarketData::new(100.0f32, 0.2, 0.05, 0.0);
+    let strikes: Vec<f32> = vec![70.0, 85.0, 100.0, 115.0, 130.0];
+    let implied = ImpliedVolSurface::new(vec![1.0f32], strikes.clone(), vec![vec![0.2; strikes.len()]]);
+    let surface = LocalVolSurface::from_implied(&implied, &market).unwrap().with_interpolation(Interpolation::Cubic);
+    let config = SimulationConfig::builder().steps(20).num_trials(100000).seed(123).build().unwrap();
+    let contract = OptionContract::new(OptionType::Put, 95.0f32, 1.0);
+    let estimate = price(&contract, &surface, &market, &config).unwrap();
+    let actual_price = bs::put_price(100.0f32, 95.0, 0.2, 0.05, 1.0, 0.0);
+    println!("f32 local vol {:?} vs {}", estimate, actual_price);
+    assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+}
+
+#[test]
+fn invalid_surfaces() {
+    let market = MarketData::new(100.0, 0.2, 0.03, 0.0);
+    let implied = ImpliedVolSurface::new(vec![0.5, 1.0], vec![90.0, 100.0, 110.0], vec![vec![0.2; 3]; 2]);
+    let cases = [
+        (
+            ImpliedVolSurface { strikes: vec![90.0, 100.0], ..implied.clone() },
+            PricingError::TooFewGridPoints { num_points: 2, min_points: 3 },
+        ),
+        (
+            ImpliedVolSurface { expiries: vec![], volatilities: vec![], ..implied.clone() },
+            PricingError::TooFewGridPoints { num_points: 0, min_points: 1 },
+        ),
+        (
+            ImpliedVolSurface { expiries: vec![1.0, 0.5], ..implied.clone() },
+            PricingError::UnsortedGrid(0.5),
+        ),
+        (
+            ImpliedVolSurface { strikes: vec![90.0, 110.0, 100.0], ..implied.clone() },
+            PricingError::UnsortedGrid(100.0),
+        ),
+        (
+            ImpliedVolSurface { expiries: vec![0.0, 1.0], ..implied.clone() },
+            PricingError::InvalidYearsToExpiry(0.0),
+        ),
+        (
+            ImpliedVolSurface { volatilities: vec![vec![0.2; 3]], ..implied.clone() },
+            PricingError::GridSizeMismatch { expected: 2, actual: 1 },
+        ),
+        (
+            ImpliedVolSurface { volatilities: vec![vec![0.2; 3], vec![0.2; 4]], ..implied.clone() },
+            PricingError::GridSizeMismatch { expected: 3, actual: 4 },
+        ),
+        (
+            ImpliedVolSurface { volatilities: vec![vec![0.2; 3], vec![0.2, -0.1, 0.2]], ..implied.clone() },
+            PricingError::InvalidVolatility(-0.1),
+        ),
+    ];
+    for (implied, error) in cases {
+        assert_eq!(LocalVolSurface::from_implied(&implied, &market), Err(error));
+    }
+
+    // The total variance falls from the first expiry to the second, a calendar arbitrage
+    let calendar_arbitrage = ImpliedVolSurface { volatilities: vec![vec![0.4; 3], vec![0.2; 3]], ..implied.clone() };
+    assert!(matches!(
+        LocalVolSurface::from_implied(&calendar_arbitrage, &market),
+        Err(PricingError::InvalidLocalVariance { local_variance, .. }) if local_variance < 0.0
+    ));
+
+    let surface = LocalVolSurface::new(implied.expiries.clone(), implied.strikes.clone(), vec![vec![0.2; 2]; 2]);
+    let contract = OptionContract::new(OptionType::Call, 100.0, 1.0);
+    assert_eq!(
+        price(&contract, &surface, &market, &SimulationConfig::new(10, 100)),
+        Err(PricingError::GridSizeMismatch { expected: 3, actual: 2 })
+    );
+}
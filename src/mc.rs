@@ -1,43 +1,59 @@
 use rand_distr::StandardNormal;
 use rand::Rng;
 use crate::bs;
-
-pub fn call_price(
-    spot: f32,
-    strike: f32,
-    volatility: f32,
-    risk_free_rate: f32,
-    years_to_expiry: f32,
-    dividend_yield: f32,
-    steps: f32,
-    num_trials: f32
-) -> f32 {
-    let dt: f32 = years_to_expiry / steps;
-    let nudt: f32 = (risk_free_rate - dividend_yield - 0.5 * (volatility * volatility)) * dt;
-    let sidt: f32 = volatility * dt.sqrt();
-    let mut total_price: f32 = 0.0;
-
-    for _ in 0..num_trials as i32 {
+use crate::error::PricingError;
+use crate::float::Float;
+use crate::mc_simd;
+use crate::params::{ MarketData, OptionContract, OptionType, SimulationConfig };
+
+/// Price a European option. Only `steps` and `num_trials` are read from `config`, the scalar
+/// pricer always draws from `rand::thread_rng()` and never uses antithetic variates. The paths
+/// are simulated and summed in f64, then the price is rounded to the precision `F`.
+pub fn price<F: Float>(
+    contract: &OptionContract<F>,
+    market: &MarketData<F>,
+    config: &SimulationConfig
+) -> Result<F, PricingError> {
+    contract.validate()?;
+    market.validate()?;
+    config.validate()?;
+
+    let num_trials = config.num_trials as f64;
+    let call_mult = contract.option_type.call_mult();
+
+    let spot = market.spot.to_f64();
+    let strike = contract.strike.to_f64();
+    let volatility = market.volatility.to_f64();
+    let risk_free_rate = market.risk_free_rate.to_f64();
+    let years_to_expiry = contract.years_to_expiry.to_f64();
+    let dt: f64 = years_to_expiry / (config.steps as f64);
+    let nudt: f64 =
+        (risk_free_rate - market.carry_yield().to_f64() - 0.5 * (volatility * volatility)) * dt;
+    let sidt: f64 = volatility * dt.sqrt();
+    let mut total_price: f64 = 0.0;
+
+    for _ in 0..config.num_trials {
         let mut stock_price_mult = 1.0;
 
-        for _ in 0..steps as i32 {
-            let rand_val = rand::thread_rng().sample::<f32, _>(StandardNormal);
+        for _ in 0..config.steps {
+            let rand_val = rand::thread_rng().sample::<f64, _>(StandardNormal);
             stock_price_mult *= (nudt + sidt * rand_val).exp();
         }
 
-        let price = spot * stock_price_mult - strike;
+        let price = call_mult * (spot * stock_price_mult - strike);
 
         if price > 0.0 {
             total_price += price;
         }
     }
 
-    let call_option_price: f32 =
-        (total_price / num_trials) * (-risk_free_rate * years_to_expiry).exp();
-    call_option_price
+    let option_price: f64 = (total_price / num_trials) * (-risk_free_rate * years_to_expiry).exp();
+    Ok(F::from_f64(option_price))
 }
 
-pub fn put_price(
+/// Price a call from positional parameters. Returns NaN if they are invalid, or if steps or
+/// num_trials is not a whole number, use [`price`] to get a [`PricingError`] instead.
+pub fn call_price(
     spot: f32,
     strike: f32,
     volatility: f32,
@@ -47,29 +63,35 @@ pub fn put_price(
     steps: f32,
     num_trials: f32
 ) -> f32 {
-    let dt: f32 = years_to_expiry / steps;
-    let nudt: f32 = (risk_free_rate - dividend_yield - 0.5 * (volatility * volatility)) * dt;
-    let sidt: f32 = volatility * dt.sqrt();
-    let mut total_price: f32 = 0.0;
-
-    for _ in 0..num_trials as i32 {
-        let mut stock_price_mult = 1.0;
-
-        for _ in 0..steps as i32 {
-            let rand_val = rand::thread_rng().sample::<f32, _>(StandardNormal);
-            stock_price_mult *= (nudt + sidt * rand_val).exp();
-        }
-
-        let price = strike - spot * stock_price_mult;
-
-        if price > 0.0 {
-            total_price += price;
-        }
-    }
+    let Some(config) = mc_simd::positional_config(steps, num_trials) else {
+        return f32::NAN;
+    };
+    price(
+        &OptionContract::new(OptionType::Call, strike, years_to_expiry),
+        &MarketData::new(spot, volatility, risk_free_rate, dividend_yield),
+        &config
+    ).unwrap_or(f32::NAN)
+}
 
-    let call_option_price: f32 =
-        (total_price / num_trials) * (-risk_free_rate * years_to_expiry).exp();
-    call_option_price
+/// Same as [`call_price`] for a put
+pub fn put_price(
+    spot: f32,
+    strike: f32,
+    volatility: f32,
+    risk_free_rate: f32,
+    years_to_expiry: f32,
+    dividend_yield: f32,
+    steps: f32,
+    num_trials: f32
+) -> f32 {
+    let Some(config) = mc_simd::positional_config(steps, num_trials) else {
+        return f32::NAN;
+    };
+    price(
+        &OptionContract::new(OptionType::Put, strike, years_to_expiry),
+        &MarketData::new(spot, volatility, risk_free_rate, dividend_yield),
+        &config
+    ).unwrap_or(f32::NAN)
 }
 
 #[test]
@@ -118,4 +140,36 @@ fn valid_price_6() {
     let price = put_price(130.0, 100.0, 0.2, 0.1, 1.0, 0.02, 100.0, 10000.0);
     println!("mc 4 {} vs {}", price, actual_price);
     assert_eq!((actual_price - price).abs() <= 1.0, true);
-}
\ No newline at end of file
+}
+
+#[test]
+fn valid_price_from_params() {
+    let contract = OptionContract::builder().put().strike(110.0).years_to_expiry(1.0).build();
+    let market = MarketData::builder()
+        .spot(112.0)
+        .volatility(0.2)
+        .risk_free_rate(0.05)
+        .dividend_yield(0.02)
+        .build();
+    let actual_price = bs::put_price(112.0, 110.0, 0.2, 0.05, 1.0, 0.02);
+    let price = price(&contract.unwrap(), &market.unwrap(), &SimulationConfig::new(100, 10000));
+    let price = price.unwrap();
+    println!("mc params {} vs {}", price, actual_price);
+    assert_eq!((actual_price - price).abs() <= 1.0, true);
+}
+
+#[test]
+fn invalid_parameters_are_rejected() {
+    let contract = OptionContract::new(OptionType::Call, 110.0, 0.5);
+    let market = MarketData::new(100.0, 0.25, 0.05, 0.02);
+    assert_eq!(
+        price(&contract, &market, &SimulationConfig::new(0, 1000)),
+        Err(PricingError::TooFewSteps { steps: 0, min_steps: 1 })
+    );
+    assert_eq!(
+        price(&contract, &MarketData::new(100.0, -0.25, 0.05, 0.02), &SimulationConfig::default()),
+        Err(PricingError::InvalidVolatility(-0.25))
+    );
+    assert!(call_price(100.0, 110.0, -0.25, 0.05, 0.5, 0.02, 100.0, 1000.0).is_nan());
+    assert!(put_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02, 100.5, 1000.0).is_nan());
+}
@@ -1,28 +1,2250 @@
-use wide::*;
 use simd_rand::portable::*;
-use crate::rand32x8::get_rand_uniform_f32x8;
-use rand_core::{ RngCore, SeedableRng };
+use crate::rand32x8::{ seeded_rng, thread_seeded_rng };
 use crate::bs;
+use crate::error::PricingError;
+use crate::float::{ Float, SimdFloat };
+use crate::params::{
+    Greek,
+    GreekBumps,
+    MarketData,
+    Monitoring,
+    MultiAssetMarket,
+    OptionContract,
+    OptionType,
+    RainbowType,
+    SimulationConfig,
+    Underlying,
+};
 use rayon::iter::IntoParallelIterator;
 use rayon::iter::ParallelIterator;
 
+/// Where the simulations get the random number generator for each block of trials. The
+/// trials are simulated in batches of 8 at f32 precision and 4 at f64 precision, and the
+/// batches split into at most 1024 blocks that depend only on the number of trials.
+#[derive(Clone, Copy, Debug)]
+pub enum RngSource {
+    /// Seed every block from `rand::thread_rng()`, so no two runs agree
+    Entropy,
+    /// Derive a stream for every block from a master seed, so the same seed always produces
+    /// bit-identical prices regardless of the rayon thread count. The streams are jumped to
+    /// along the xoshiro256 period, so the streams of all blocks and all master seeds are
+    /// disjoint by construction
+    Seeded(u64),
+    /// Build the generator for every block with a caller-supplied factory, which is passed
+    /// the index of the block
+    Factory(fn(u64) -> Xoshiro256PlusPlusX8),
+}
+
+impl RngSource {
+    fn rng(&self, block: u64) -> Xoshiro256PlusPlusX8 {
+        match *self {
+            RngSource::Entropy => thread_seeded_rng(),
+            RngSource::Seeded(master_seed) => seeded_rng(master_seed, block),
+            RngSource::Factory(factory) => factory(block),
+        }
+    }
+}
+
+/// A Monte Carlo price together with statistics on how noisy it is, at the precision `F` of
+/// the pricing parameters
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PriceEstimate<F: Float = f32> {
+    /// The discounted average payoff
+    pub price: F,
+    /// Sample standard error of the price, NaN when there is only a single sample
+    pub std_error: F,
+    /// 95% confidence interval around the price, as (lower bound, upper bound)
+    pub confidence_interval: (F, F),
+    /// Number of simulated paths that went into the price
+    pub num_paths: usize,
+}
+
+// Two-sided 95% quantile of the standard normal distribution
+const Z_95: f64 = 1.959963984540054;
+
+impl<F: Float> PriceEstimate<F> {
+    // Builds the estimate from the sums of num_samples independent samples and of their
+    // squares. The callers add up the lanes of every batch in f64 before summing the batches,
+    // since the sum of squares loses too many digits to cancellation in f32.
+    fn from_sums(
+        sum: f64,
+        sum_squares: f64,
+        num_samples: usize,
+        num_paths: usize,
+        discount: f64
+    ) -> PriceEstimate<F> {
+        let n = num_samples as f64;
+
+        let mean = sum / n;
+        let variance = if num_samples > 1 {
+            ((sum_squares - sum * mean) / (n - 1.0)).max(0.0)
+        } else {
+            f64::NAN
+        };
+        PriceEstimate::from_moments(mean, variance, num_samples, num_paths, discount)
+    }
+
+    // Builds the estimate of a payoff corrected with a control variate, from the sums of
+    // num_samples samples of the payoff and the control, their squares and their product. The
+    // price is the payoff minus beta times the error of the control, whose discounted
+    // expectation is control_price, with the beta that minimises the variance estimated from
+    // the same samples.
+    fn from_control_sums(
+        sums: [f64; 5],
+        control_price: f64,
+        num_samples: usize,
+        num_paths: usize,
+        discount: f64
+    ) -> PriceEstimate<F> {
+        let n = num_samples as f64;
+        let [sum, sum_control, sum_squares, sum_control_squares, sum_products] = sums;
+        let control_variance = sum_control_squares - sum_control * sum_control / n;
+        let covariance = sum_products - sum * sum_control / n;
+        let beta = if control_variance > 0.0 { covariance / control_variance } else { 0.0 };
+
+        let mean = (sum - beta * (sum_control - n * control_price / discount)) / n;
+        let variance = if num_samples > 1 {
+            ((sum_squares - sum * sum / n - beta * covariance) / (n - 1.0)).max(0.0)
+        } else {
+            f64::NAN
+        };
+        PriceEstimate::from_moments(mean, variance, num_samples, num_paths, discount)
+    }
+
+    // Builds the estimate from the mean and the sample variance of num_samples undiscounted
+    // samples
+    fn from_moments(
+        mean: f64,
+        variance: f64,
+        num_samples: usize,
+        num_paths: usize,
+        discount: f64
+    ) -> PriceEstimate<F> {
+        let price = mean * discount;
+        let std_error = (variance / (num_samples as f64)).sqrt() * discount;
+
+        PriceEstimate {
+            price: F::from_f64(price),
+            std_error: F::from_f64(std_error),
+            confidence_interval: (
+                F::from_f64(price - Z_95 * std_error),
+                F::from_f64(price + Z_95 * std_error),
+            ),
+            num_paths,
+        }
+    }
+}
+
+/// Whether the strike of a lookback option is fixed, or is the best spot over the life of
+/// the option
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LookbackStrike<F: Float = f32> {
+    /// A call pays max(highest spot - strike, 0) and a put max(strike - lowest spot, 0)
+    Fixed(F),
+    /// A call pays the spot at expiry minus the lowest spot, and a put the highest spot minus
+    /// the spot at expiry
+    Floating,
+}
+
+/// A lookback option, whose payoff depends on the highest or lowest spot until expiry
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LookbackOption<F: Float = f32> {
+    pub option_type: OptionType,
+    pub strike: LookbackStrike<F>,
+    pub years_to_expiry: F,
+    pub monitoring: Monitoring,
+}
+
+/// A call or put on the best or the worst of the spots at expiry of the assets of a
+/// [`MultiAssetMarket`], e.g. a worst-of put pays max(strike - lowest spot, 0)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RainbowOption<F: Float = f32> {
+    pub option_type: OptionType,
+    pub rainbow_type: RainbowType,
+    pub strike: F,
+    pub years_to_expiry: F,
+}
+
+/// What a digital option pays when it expires in the money against its strike
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DigitalPayoff<F: Float = f32> {
+    /// A fixed amount of cash
+    CashOrNothing(F),
+    /// The spot at expiry
+    AssetOrNothing,
+    /// The spot at expiry minus this payoff strike for a call, and this payoff strike minus the
+    /// spot for a put, which may be negative
+    Gap(F),
+}
+
+// Upper bound on the number of partial sums kept by sum_batches, and on the number of rngs
+const MAX_BLOCKS: usize = 1024;
+
+// The batches of V::LANES trials of block out of num_blocks, which depend only on num_trials
+fn block_batches(block: usize, num_batches: usize, num_blocks: usize) -> std::ops::Range<usize> {
+    ((block * num_batches) / num_blocks)..(((block + 1) * num_batches) / num_blocks)
+}
+
+// Simulates num_trials trials in parallel batches of V::LANES and adds up the results. The
+// batches are split into blocks that depend only on num_trials, and the partial sums are
+// added in block order, so that rounding never depends on how rayon schedules the work.
+// Every block draws its batches one after the other from its own rng.
+// simulate_batch is also given a mask of the lanes holding real trials, which is only
+// partially set in the last batch when num_trials is not a multiple of V::LANES. Payoffs in
+// the other lanes must be blended to zero so that they do not bias the sums.
+fn sum_batches<V, T, A, S>(
+    num_trials: usize,
+    rng_source: RngSource,
+    zero: T,
+    add: A,
+    simulate_batch: S
+) -> T
+    where
+        V: SimdFloat,
+        T: Copy + Send + Sync,
+        A: Fn(T, T) -> T + Sync,
+        S: Fn(&mut Xoshiro256PlusPlusX8, V) -> T + Sync
+{
+    let lane_index = V::lane_index();
+    let num_batches = num_trials.div_ceil(V::LANES);
+    let num_blocks = num_batches.min(MAX_BLOCKS);
+
+    (0..num_blocks)
+        .into_par_iter()
+        .map(|block| {
+            let mut rng = rng_source.rng(block as u64);
+            block_batches(block, num_batches, num_blocks).fold(zero, |total, batch| {
+                let active_lanes = (num_trials - batch * V::LANES).min(V::LANES);
+                let lanes = lane_index.cmp_lt(V::splat(active_lanes as f64));
+                add(total, simulate_batch(&mut rng, lanes))
+            })
+        })
+        .collect::<Vec<T>>()
+        .into_iter()
+        .fold(zero, add)
+}
+
 #[inline(always)]
-fn speed_update(two_pi: f32x8, stock_price_mult: f32x8, rng: &mut Xoshiro256PlusPlusX8) -> f32x8 {
-    // Simulate two steps in time in order to have clean, vertical f32x8 operations
-    let first_rand: f32x8 = get_rand_uniform_f32x8(rng);
-    let second_rand: f32x8 = get_rand_uniform_f32x8(rng);
+fn speed_update<V: SimdFloat>(two_pi: V, stock_price_mult: V, rng: &mut Xoshiro256PlusPlusX8) -> V {
+    // Simulate two steps in time in order to have clean, vertical SIMD operations
+    let (first_rand, second_rand) = V::uniform_pair(rng);
 
-    // The random f32x8 are uniformly distributed, so we have to
+    // The random vectors are uniformly distributed, so we have to
     // apply the Box-Muller transform to make them normally distributed
-    let (sin_rand, cos_rand) = f32x8::sin_cos(two_pi * second_rand);
+    let (sin_rand, cos_rand) = V::sin_cos(two_pi * second_rand);
 
     // Only necessary operations are kept in the innermost loop
-    f32x8::mul_add((-first_rand.ln()).sqrt(), sin_rand + cos_rand, stock_price_mult)
+    V::mul_add((-first_rand.ln()).sqrt(), sin_rand + cos_rand, stock_price_mult)
+}
+
+#[inline(always)]
+fn single_step_update<V: SimdFloat>(
+    two_pi: V,
+    stock_price_mult: V,
+    rng: &mut Xoshiro256PlusPlusX8
+) -> V {
+    // Only use the sine half of the Box-Muller transform to simulate a single step
+    let (first_rand, second_rand) = V::uniform_pair(rng);
+    V::mul_add((-first_rand.ln()).sqrt(), (two_pi * second_rand).sin(), stock_price_mult)
+}
+
+// Simulates `steps` steps in time for V::LANES trials. The result is the sum of the standard
+// normal draws of every step divided by sqrt(2), since the sqrt(2) is taken out of the
+// Box-Muller transform.
+#[inline(always)]
+fn simulate_steps<V: SimdFloat>(two_pi: V, steps: usize, rng: &mut Xoshiro256PlusPlusX8) -> V {
+    let mut stock_price_mult: V = V::splat(0.0);
+
+    for _ in 0..steps / 2 {
+        stock_price_mult = speed_update(two_pi, stock_price_mult, rng);
+    }
+
+    // An odd number of steps has one step left over
+    if steps % 2 == 1 {
+        stock_price_mult = single_step_update(two_pi, stock_price_mult, rng);
+    }
+
+    stock_price_mult
+}
+
+/* Below are the path simulations, for pricers that need more than the terminal price */
+
+/// A path-dependent payoff of `V::LANES` paths at a time, which is shown the spot of every
+/// path after each step as it is simulated. Only the state is kept per bundle of paths, so
+/// e.g. a running average or maximum prices with any number of steps in constant memory.
+pub trait PathPayoff<V: SimdFloat>: Sync {
+    /// What the payoff remembers of the path so far
+    type State;
+
+    /// The state before the first step
+    fn start(&self) -> Self::State;
+
+    /// Update the state with the spots after step + 1 steps
+    fn observe(&self, state: &mut Self::State, step: usize, spot: V);
+
+    /// The undiscounted payoff of every lane at expiry
+    fn payoff(&self, state: Self::State) -> V;
+}
+
+// Materializes the spot at every step, for payoffs written against the whole path
+struct StoredPath<P> {
+    steps: usize,
+    payoff: P,
+}
+
+impl<V: SimdFloat, P: Fn(&[V]) -> V + Sync> PathPayoff<V> for StoredPath<P> {
+    type State = Vec<V>;
+
+    fn start(&self) -> Vec<V> {
+        Vec::with_capacity(self.steps)
+    }
+
+    fn observe(&self, state: &mut Vec<V>, _step: usize, spot: V) {
+        state.push(spot);
+    }
+
+    fn payoff(&self, state: Vec<V>) -> V {
+        (self.payoff)(&state)
+    }
+}
+
+// Draws `steps` standard normals for each of V::LANES paths and passes them to draw in step
+// order. Unlike simulate_steps, both halves of the Box-Muller transform are kept apart, the
+// sine and the cosine driving two consecutive steps.
+#[inline(always)]
+fn for_each_normal<V: SimdFloat>(
+    two_pi: V,
+    steps: usize,
+    rng: &mut Xoshiro256PlusPlusX8,
+    mut draw: impl FnMut(usize, V)
+) {
+    for step in (0..steps).step_by(2) {
+        let (first_rand, second_rand) = V::uniform_pair(rng);
+        let radius = (V::splat(-2.0) * first_rand.ln()).sqrt();
+        let (sin_rand, cos_rand) = V::sin_cos(two_pi * second_rand);
+
+        draw(step, radius * sin_rand);
+        if step + 1 < steps {
+            draw(step + 1, radius * cos_rand);
+        }
+    }
+}
+
+// Simulates V::LANES paths of prices.len() steps from spot, writing the spot after every step
+// to prices
+#[inline(always)]
+fn simulate_path_bundle<V: SimdFloat>(
+    spot: V,
+    nudt: V,
+    sidt: V,
+    two_pi: V,
+    rng: &mut Xoshiro256PlusPlusX8,
+    prices: &mut [V]
+) {
+    let mut log_mult = V::splat(0.0);
+    for_each_normal(two_pi, prices.len(), rng, |step, normal| {
+        log_mult = V::mul_add(normal, sidt, log_mult + nudt);
+        prices[step] = spot * log_mult.exp();
+    });
+}
+
+// Simulates num_trials risk-neutral paths of `steps` steps and returns the spot at every step
+// in f64, step by step: element step * num_trials + path is the spot of path after step + 1
+// steps. The batches of V::LANES paths are split into blocks with their own rngs as in
+// sum_batches, so seeded paths do not depend on the thread count.
+pub(crate) fn simulate_paths<V: SimdFloat>(
+    spot: f64,
+    volatility: f64,
+    risk_free_rate: f64,
+    years_to_expiry: f64,
+    dividend_yield: f64,
+    steps: usize,
+    num_trials: usize,
+    rng_source: RngSource
+) -> Vec<f64> {
+    let dt: f64 = years_to_expiry / (steps as f64);
+    let nudt = V::splat((risk_free_rate - dividend_yield - 0.5 * (volatility * volatility)) * dt);
+    let sidt = V::splat(volatility * dt.sqrt());
+    let two_pi = V::splat(2.0 * std::f64::consts::PI);
+    let spot_simd = V::splat(spot);
+
+    let num_batches = num_trials.div_ceil(V::LANES);
+    let num_blocks = num_batches.min(MAX_BLOCKS);
+    let bundles: Vec<Vec<V>> = (0..num_blocks)
+        .into_par_iter()
+        .map(|block| {
+            let mut rng = rng_source.rng(block as u64);
+            block_batches(block, num_batches, num_blocks)
+                .map(|_| {
+                    let mut prices = vec![V::splat(0.0); steps];
+                    simulate_path_bundle(spot_simd, nudt, sidt, two_pi, &mut rng, &mut prices);
+                    prices
+                })
+                .collect::<Vec<Vec<V>>>()
+        })
+        .collect::<Vec<Vec<Vec<V>>>>()
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let mut paths = vec![0.0; steps * num_trials];
+    let mut lanes = vec![0.0; V::LANES];
+    for (batch, prices) in bundles.iter().enumerate() {
+        let first_path = batch * V::LANES;
+        let active_lanes = (num_trials - first_path).min(V::LANES);
+        for (step, price) in prices.iter().enumerate() {
+            price.store_f64(&mut lanes);
+            let start = step * num_trials + first_path;
+            paths[start..start + active_lanes].copy_from_slice(&lanes[..active_lanes]);
+        }
+    }
+    paths
+}
+
+// Simulates one bundle of V::LANES paths, streaming the spot after every step to the state of
+// each path. With antithetic, the paths of the negated draws are streamed to a second state.
+#[inline(always)]
+fn simulate_path_states<V: SimdFloat, S>(
+    spot: V,
+    nudt: V,
+    sidt: V,
+    two_pi: V,
+    steps: usize,
+    antithetic: bool,
+    rng: &mut Xoshiro256PlusPlusX8,
+    start: impl Fn() -> S,
+    observe: impl Fn(&mut S, usize, V)
+) -> (S, Option<S>) {
+    let mut state = start();
+    let mut log_mult = V::splat(0.0);
+
+    if antithetic {
+        let mut mirrored_state = start();
+        let mut mirrored_log_mult = V::splat(0.0);
+        for_each_normal(two_pi, steps, rng, |step, normal| {
+            log_mult = V::mul_add(normal, sidt, log_mult + nudt);
+            mirrored_log_mult = V::mul_add(-normal, sidt, mirrored_log_mult + nudt);
+            observe(&mut state, step, spot * log_mult.exp());
+            observe(&mut mirrored_state, step, spot * mirrored_log_mult.exp());
+        });
+        (state, Some(mirrored_state))
+    } else {
+        for_each_normal(two_pi, steps, rng, |step, normal| {
+            log_mult = V::mul_add(normal, sidt, log_mult + nudt);
+            observe(&mut state, step, spot * log_mult.exp());
+        });
+        (state, None)
+    }
+}
+
+// Prices a path-dependent payoff on num_trials paths, which are streamed step by step to
+// payoff. With antithetic, every path is paired with the path of the negated draws and the
+// pair average is one sample, as in monte_carlo_av_pricing. The estimate is returned at the
+// precision F, which may be f64 to take differences of prices simulated on f32x8 lanes.
+fn monte_carlo_path_pricing<V: SimdFloat, P: PathPayoff<V>, F: Float>(
+    spot: f64,
+    volatility: f64,
+    risk_free_rate: f64,
+    years_to_expiry: f64,
+    dividend_yield: f64,
+    steps: usize,
+    num_trials: usize,
+    antithetic: bool,
+    rng_source: RngSource,
+    payoff: &P
+) -> PriceEstimate<F> {
+    let dt: f64 = years_to_expiry / (steps as f64);
+    let nudt = V::splat((risk_free_rate - dividend_yield - 0.5 * (volatility * volatility)) * dt);
+    let sidt = V::splat(volatility * dt.sqrt());
+    let two_pi = V::splat(2.0 * std::f64::consts::PI);
+    let spot_simd = V::splat(spot);
+    let zeros: V = V::splat(0.0);
+    let half: V = V::splat(0.5);
+
+    let (total_prices, total_squared_prices) = sum_batches::<V, _, _, _>(
+        num_trials,
+        rng_source,
+        (0.0, 0.0),
+        |(a, b), (c, d)| (a + c, b + d),
+        |rng, lanes| {
+            let (state, mirrored_state) = simulate_path_states(
+                spot_simd,
+                nudt,
+                sidt,
+                two_pi,
+                steps,
+                antithetic,
+                rng,
+                || payoff.start(),
+                |state, step, spot| payoff.observe(state, step, spot)
+            );
+            let price = match mirrored_state {
+                Some(mirrored_state) => half * (payoff.payoff(state) + payoff.payoff(mirrored_state)),
+                None => payoff.payoff(state),
+            };
+
+            let price = lanes.blend(price, zeros);
+            (price.sum_lanes(), (price * price).sum_lanes())
+        }
+    );
+
+    PriceEstimate::from_sums(
+        total_prices,
+        total_squared_prices,
+        num_trials,
+        if antithetic { 2 * num_trials } else { num_trials },
+        (-risk_free_rate * years_to_expiry).exp()
+    )
+}
+
+// Same as monte_carlo_path_pricing, correcting the price with control, a second payoff of the
+// same paths whose discounted expectation is control_price. The moments are added up in f64
+// for every batch, since the variance left after a good control is a small difference of
+// large sums.
+fn monte_carlo_path_control_pricing<V: SimdFloat, P: PathPayoff<V>, C: PathPayoff<V>>(
+    spot: f64,
+    volatility: f64,
+    risk_free_rate: f64,
+    years_to_expiry: f64,
+    dividend_yield: f64,
+    steps: usize,
+    num_trials: usize,
+    antithetic: bool,
+    rng_source: RngSource,
+    payoff: &P,
+    control: &C,
+    control_price: f64
+) -> PriceEstimate<V::Scalar> {
+    let dt: f64 = years_to_expiry / (steps as f64);
+    let nudt = V::splat((risk_free_rate - dividend_yield - 0.5 * (volatility * volatility)) * dt);
+    let sidt = V::splat(volatility * dt.sqrt());
+    let two_pi = V::splat(2.0 * std::f64::consts::PI);
+    let spot_simd = V::splat(spot);
+    let zeros: V = V::splat(0.0);
+    let half: V = V::splat(0.5);
+
+    // Sums of the payoff, the control, their squares and their product
+    let sums = sum_batches::<V, _, _, _>(
+        num_trials,
+        rng_source,
+        [0.0; 5],
+        |a, b| std::array::from_fn(|i| a[i] + b[i]),
+        |rng, lanes| {
+            let (state, mirrored_state) = simulate_path_states(
+                spot_simd,
+                nudt,
+                sidt,
+                two_pi,
+                steps,
+                antithetic,
+                rng,
+                || (payoff.start(), control.start()),
+                |(payoff_state, control_state), step, spot| {
+                    payoff.observe(payoff_state, step, spot);
+                    control.observe(control_state, step, spot);
+                }
+            );
+            let (price, control_value) = match mirrored_state {
+                Some(mirrored_state) =>
+                    (
+                        half * (payoff.payoff(state.0) + payoff.payoff(mirrored_state.0)),
+                        half * (control.payoff(state.1) + control.payoff(mirrored_state.1)),
+                    ),
+                None => (payoff.payoff(state.0), control.payoff(state.1)),
+            };
+
+            let price = lanes.blend(price, zeros);
+            let control_value = lanes.blend(control_value, zeros);
+            [
+                price.sum_lanes(),
+                control_value.sum_lanes(),
+                (price * price).sum_lanes(),
+                (control_value * control_value).sum_lanes(),
+                (price * control_value).sum_lanes(),
+            ]
+        }
+    );
+
+    PriceEstimate::from_control_sums(
+        sums,
+        control_price,
+        num_trials,
+        if antithetic { 2 * num_trials } else { num_trials },
+        (-risk_free_rate * years_to_expiry).exp()
+    )
+}
+
+/// A payoff of several correlated underlyings, `V::LANES` paths at a time, which is shown the
+/// spots of all of the assets after each step as they are simulated, as in [`PathPayoff`]. It
+/// is implemented for closures of the spots at expiry, so e.g. a call on the best of two
+/// assets is `|spots: &[f32x8]| (spots[0].fast_max(spots[1]) - strike).fast_max(zero)`.
+pub trait MultiAssetPayoff<V: SimdFloat>: Sync {
+    /// What the payoff remembers of the paths so far
+    type State;
+
+    /// The state before the first step
+    fn start(&self) -> Self::State;
+
+    /// Update the state with the spots of every asset after step + 1 steps, in the order of
+    /// the assets in the market
+    fn observe(&self, state: &mut Self::State, step: usize, spots: &[V]);
+
+    /// The undiscounted payoff of every lane at expiry
+    fn payoff(&self, state: Self::State) -> V;
+}
+
+impl<V: SimdFloat, P: Fn(&[V]) -> V + Sync> MultiAssetPayoff<V> for P {
+    type State = Vec<V>;
+
+    fn start(&self) -> Vec<V> {
+        Vec::new()
+    }
+
+    fn observe(&self, spots_so_far: &mut Vec<V>, _step: usize, spots: &[V]) {
+        spots_so_far.clear();
+        spots_so_far.extend_from_slice(spots);
+    }
+
+    fn payoff(&self, spots: Vec<V>) -> V {
+        self(&spots)
+    }
+}
+
+// The payoff of a rainbow option on V::LANES paths, on the highest or lowest spot at expiry
+// across the assets, reduced lane by lane. The paths are simulated in a single step, so the
+// spots seen are those at expiry.
+struct RainbowPayoff<V> {
+    call_mult: V,
+    strike: V,
+    best: bool,
+}
+
+impl<V: SimdFloat> MultiAssetPayoff<V> for RainbowPayoff<V> {
+    type State = V;
+
+    fn start(&self) -> V {
+        V::splat(0.0)
+    }
+
+    fn observe(&self, extreme: &mut V, _step: usize, spots: &[V]) {
+        *extreme = spots[1..].iter().fold(spots[0], |extreme, &spot| {
+            if self.best { extreme.fast_max(spot) } else { extreme.fast_min(spot) }
+        });
+    }
+
+    fn payoff(&self, extreme: V) -> V {
+        V::fast_max(self.call_mult * (extreme - self.strike), V::splat(0.0))
+    }
+}
+
+// Correlates the independent standard normals of every asset with the lower triangular
+// Cholesky factor of the correlation matrix, stored row after row
+#[inline(always)]
+fn correlate<V: SimdFloat>(factor: &[V], independent: &[V], correlated: &mut [V]) {
+    let mut factor_row = factor;
+    for (asset, correlated) in correlated.iter_mut().enumerate() {
+        let (row, rest) = factor_row.split_at(asset + 1);
+        *correlated = row
+            .iter()
+            .zip(independent)
+            .fold(V::splat(0.0), |sum, (&weight, &normal)| V::mul_add(weight, normal, sum));
+        factor_row = rest;
+    }
+}
+
+// The drift and volatility of every asset over one step, and the Cholesky factor of their
+// correlation matrix, splatted to V once for all the bundles
+struct MultiAssetSteps<V> {
+    spots: Vec<V>,
+    nudts: Vec<V>,
+    sidts: Vec<V>,
+    factor: Vec<V>,
+    steps: usize,
+}
+
+impl<V: SimdFloat> MultiAssetSteps<V> {
+    fn new<F: Float>(
+        market: &MultiAssetMarket<F>,
+        years_to_expiry: f64,
+        steps: usize,
+        factor: &[f64]
+    ) -> MultiAssetSteps<V> {
+        let dt = years_to_expiry / (steps as f64);
+        let risk_free_rate = market.risk_free_rate.to_f64();
+        let volatilities = market.volatilities.iter().map(|volatility| volatility.to_f64());
+        MultiAssetSteps {
+            spots: market.spots
+                .iter()
+                .map(|spot| V::splat(spot.to_f64()))
+                .collect(),
+            nudts: volatilities
+                .clone()
+                .zip(&market.dividend_yields)
+                .map(|(volatility, dividend_yield)| {
+                    let drift = risk_free_rate - dividend_yield.to_f64() - 0.5 * volatility * volatility;
+                    V::splat(drift * dt)
+                })
+                .collect(),
+            sidts: volatilities.map(|volatility| V::splat(volatility * dt.sqrt())).collect(),
+            factor: factor
+                .iter()
+                .map(|&weight| V::splat(weight))
+                .collect(),
+            steps,
+        }
+    }
+
+    // Simulates one bundle of V::LANES paths of every asset, streaming the spots after every
+    // step to the state as in simulate_path_states. Every step draws the independent normals
+    // of all the assets, in pairs from the Box-Muller transform, and correlates them.
+    #[inline(always)]
+    fn simulate_states<S>(
+        &self,
+        two_pi: V,
+        antithetic: bool,
+        rng: &mut Xoshiro256PlusPlusX8,
+        start: impl Fn() -> S,
+        observe: impl Fn(&mut S, usize, &[V])
+    ) -> (S, Option<S>) {
+        let num_assets = self.spots.len();
+        let zeros = V::splat(0.0);
+        let mut independent = vec![zeros; num_assets];
+        let mut correlated = vec![zeros; num_assets];
+        let mut log_mults = vec![zeros; num_assets];
+        let mut mirrored_log_mults = vec![zeros; num_assets];
+        let mut spots = vec![zeros; num_assets];
+
+        let mut state = start();
+        let mut mirrored_state = if antithetic { Some(start()) } else { None };
+        for step in 0..self.steps {
+            for_each_normal(two_pi, num_assets, rng, |asset, normal| {
+                independent[asset] = normal;
+            });
+            correlate(&self.factor, &independent, &mut correlated);
+
+            for asset in 0..num_assets {
+                log_mults[asset] = V::mul_add(
+                    correlated[asset],
+                    self.sidts[asset],
+                    log_mults[asset] + self.nudts[asset]
+                );
+                spots[asset] = self.spots[asset] * log_mults[asset].exp();
+            }
+            observe(&mut state, step, &spots);
+
+            if let Some(mirrored_state) = mirrored_state.as_mut() {
+                for asset in 0..num_assets {
+                    mirrored_log_mults[asset] = V::mul_add(
+                        -correlated[asset],
+                        self.sidts[asset],
+                        mirrored_log_mults[asset] + self.nudts[asset]
+                    );
+                    spots[asset] = self.spots[asset] * mirrored_log_mults[asset].exp();
+                }
+                observe(mirrored_state, step, &spots);
+            }
+        }
+        (state, mirrored_state)
+    }
+}
+
+// Prices a payoff of several correlated assets on num_trials paths of every asset, as
+// monte_carlo_path_pricing does for a single asset
+fn monte_carlo_multi_asset_pricing<V: SimdFloat, P: MultiAssetPayoff<V>>(
+    market: &MultiAssetMarket<V::Scalar>,
+    factor: &[f64],
+    years_to_expiry: f64,
+    config: &SimulationConfig,
+    payoff: &P
+) -> PriceEstimate<V::Scalar> {
+    let simulation = MultiAssetSteps::<V>::new(market, years_to_expiry, config.steps, factor);
+    let two_pi = V::splat(2.0 * std::f64::consts::PI);
+    let zeros: V = V::splat(0.0);
+    let half: V = V::splat(0.5);
+
+    let (total_prices, total_squared_prices) = sum_batches::<V, _, _, _>(
+        config.num_trials,
+        config.rng_source,
+        (0.0, 0.0),
+        |(a, b), (c, d)| (a + c, b + d),
+        |rng, lanes| {
+            let (state, mirrored_state) = simulation.simulate_states(
+                two_pi,
+                config.antithetic,
+                rng,
+                || payoff.start(),
+                |state, step, spots| payoff.observe(state, step, spots)
+            );
+            let price = match mirrored_state {
+                Some(mirrored_state) => half * (payoff.payoff(state) + payoff.payoff(mirrored_state)),
+                None => payoff.payoff(state),
+            };
+
+            let price = lanes.blend(price, zeros);
+            (price.sum_lanes(), (price * price).sum_lanes())
+        }
+    );
+
+    PriceEstimate::from_sums(
+        total_prices,
+        total_squared_prices,
+        config.num_trials,
+        if config.antithetic { 2 * config.num_trials } else { config.num_trials },
+        (-market.risk_free_rate.to_f64() * years_to_expiry).exp()
+    )
+}
+
+// Same as monte_carlo_multi_asset_pricing, correcting the price with control, a second payoff
+// of the same paths whose discounted expectation is control_price, as in
+// monte_carlo_path_control_pricing
+fn monte_carlo_multi_asset_control_pricing<
+    V: SimdFloat,
+    P: MultiAssetPayoff<V>,
+    C: MultiAssetPayoff<V>
+>(
+    market: &MultiAssetMarket<V::Scalar>,
+    factor: &[f64],
+    years_to_expiry: f64,
+    config: &SimulationConfig,
+    payoff: &P,
+    control: &C,
+    control_price: f64
+) -> PriceEstimate<V::Scalar> {
+    let simulation = MultiAssetSteps::<V>::new(market, years_to_expiry, config.steps, factor);
+    let two_pi = V::splat(2.0 * std::f64::consts::PI);
+    let zeros: V = V::splat(0.0);
+    let half: V = V::splat(0.5);
+
+    // Sums of the payoff, the control, their squares and their product
+    let sums = sum_batches::<V, _, _, _>(
+        config.num_trials,
+        config.rng_source,
+        [0.0; 5],
+        |a, b| std::array::from_fn(|i| a[i] + b[i]),
+        |rng, lanes| {
+            let (state, mirrored_state) = simulation.simulate_states(
+                two_pi,
+                config.antithetic,
+                rng,
+                || (payoff.start(), control.start()),
+                |(payoff_state, control_state), step, spots| {
+                    payoff.observe(payoff_state, step, spots);
+                    control.observe(control_state, step, spots);
+                }
+            );
+            let (price, control_value) = match mirrored_state {
+                Some(mirrored_state) =>
+                    (
+                        half * (payoff.payoff(state.0) + payoff.payoff(mirrored_state.0)),
+                        half * (control.payoff(state.1) + control.payoff(mirrored_state.1)),
+                    ),
+                None => (payoff.payoff(state.0), control.payoff(state.1)),
+            };
+
+            let price = lanes.blend(price, zeros);
+            let control_value = lanes.blend(control_value, zeros);
+            [
+                price.sum_lanes(),
+                control_value.sum_lanes(),
+                (price * price).sum_lanes(),
+                (control_value * control_value).sum_lanes(),
+                (price * control_value).sum_lanes(),
+            ]
+        }
+    );
+
+    PriceEstimate::from_control_sums(
+        sums,
+        control_price,
+        config.num_trials,
+        if config.antithetic { 2 * config.num_trials } else { config.num_trials },
+        (-market.risk_free_rate.to_f64() * years_to_expiry).exp()
+    )
+}
+
+// The log of the spot divided by the initial spot along a path, with the highest value of the
+// log times sign so far, which tracks the lowest spot when sign is -1.0
+#[derive(Clone, Copy)]
+struct LookbackPath<V> {
+    log_mult: V,
+    extreme: V,
+}
+
+impl<V: SimdFloat> LookbackPath<V> {
+    // Moves the path by increment. bridge is -2 volatility^2 dt ln(u) for a uniform u, to draw
+    // the highest point of the Brownian bridge between the two ends of the step, which is
+    // (a + b + sqrt((b - a)^2 + bridge)) / 2. Without it only the ends of the step count.
+    #[inline(always)]
+    fn step(&mut self, increment: V, sign: V, bridge: Option<V>) {
+        let start = sign * self.log_mult;
+        self.log_mult = self.log_mult + increment;
+        let end = sign * self.log_mult;
+        let peak = match bridge {
+            Some(bridge) => {
+                let gap = end - start;
+                V::splat(0.5) * (start + end + V::mul_add(gap, gap, bridge).sqrt())
+            }
+            None => end,
+        };
+        self.extreme = V::fast_max(self.extreme, peak);
+    }
+}
+
+// Prices a lookback option. The highest or lowest spot of a continuously monitored option is
+// drawn exactly from the Brownian bridge between the ends of every step, which takes a second
+// pair of uniforms for every two steps, so that it needs no more steps than a European option.
+fn monte_carlo_lookback_pricing<V: SimdFloat>(
+    spot: f64,
+    strike: Option<f64>, // None for a floating strike
+    volatility: f64,
+    risk_free_rate: f64,
+    years_to_expiry: f64,
+    dividend_yield: f64,
+    continuous: bool,
+    config: &SimulationConfig,
+    call_mult: f64 // 1.0 if pricing a call option, -1.0 if pricing a put option
+) -> PriceEstimate<V::Scalar> {
+    let steps = config.steps;
+    let dt: f64 = years_to_expiry / (steps as f64);
+    let nudt = V::splat((risk_free_rate - dividend_yield - 0.5 * (volatility * volatility)) * dt);
+    let sidt = V::splat(volatility * dt.sqrt());
+    let bridge_mult = V::splat(-2.0 * volatility * volatility * dt);
+    let two_pi = V::splat(2.0 * std::f64::consts::PI);
+    let spot_simd = V::splat(spot);
+    let call_mult_simd = V::splat(call_mult);
+    let zeros: V = V::splat(0.0);
+    let half: V = V::splat(0.5);
+
+    // Calls on a fixed strike and puts on a floating strike look at the highest spot
+    let sign = V::splat(if (call_mult > 0.0) == strike.is_some() { 1.0 } else { -1.0 });
+    let payoff = |path: LookbackPath<V>| {
+        let extreme_spot = spot_simd * (sign * path.extreme).exp();
+        let intrinsic = match strike {
+            Some(strike) => extreme_spot - V::splat(strike),
+            None => spot_simd * path.log_mult.exp() - extreme_spot,
+        };
+        V::fast_max(call_mult_simd * intrinsic, zeros)
+    };
+
+    let (total_prices, total_squared_prices) = sum_batches::<V, _, _, _>(
+        config.num_trials,
+        config.rng_source,
+        (0.0, 0.0),
+        |(a, b), (c, d)| (a + c, b + d),
+        |rng, lanes| {
+            let mut path = LookbackPath { log_mult: zeros, extreme: zeros };
+            let mut mirrored_path = path;
+
+            let mut step = |normal: V, bridge_rand: V| {
+                let bridge = if continuous { Some(bridge_mult * bridge_rand.ln()) } else { None };
+                path.step(V::mul_add(normal, sidt, nudt), sign, bridge);
+                if config.antithetic {
+                    mirrored_path.step(V::mul_add(-normal, sidt, nudt), sign, bridge);
+                }
+            };
+            for step_index in (0..steps).step_by(2) {
+                let (first_rand, second_rand) = V::uniform_pair(rng);
+                let radius = (V::splat(-2.0) * first_rand.ln()).sqrt();
+                let (sin_rand, cos_rand) = V::sin_cos(two_pi * second_rand);
+                let (first_bridge_rand, second_bridge_rand) = if continuous {
+                    V::uniform_pair(rng)
+                } else {
+                    (zeros, zeros)
+                };
+
+                step(radius * sin_rand, first_bridge_rand);
+                if step_index + 1 < steps {
+                    step(radius * cos_rand, second_bridge_rand);
+                }
+            }
+
+            let price = if config.antithetic {
+                half * (payoff(path) + payoff(mirrored_path))
+            } else {
+                payoff(path)
+            };
+            let price = lanes.blend(price, zeros);
+            (price.sum_lanes(), (price * price).sum_lanes())
+        }
+    );
+
+    PriceEstimate::from_sums(
+        total_prices,
+        total_squared_prices,
+        config.num_trials,
+        if config.antithetic { 2 * config.num_trials } else { config.num_trials },
+        (-risk_free_rate * years_to_expiry).exp()
+    )
+}
+
+// Prices an option that pays asset_mult * spot + cash at expiry when it expires in the money
+// against strike, and nothing otherwise. The payoff is multiplied by the likelihood ratio
+// weight a + b Z + c Z^2, where Z is the standard normal that moved the spot to expiry. A
+// weight of 1 gives the price, and the derivative of the log density of the spot at expiry
+// with respect to a parameter gives the derivative of the price, without differentiating the
+// discontinuous payoff.
+fn monte_carlo_digital_pricing<V: SimdFloat>(
+    spot: f64,
+    strike: f64,
+    asset_mult: f64,
+    cash: f64,
+    volatility: f64,
+    risk_free_rate: f64,
+    years_to_expiry: f64,
+    dividend_yield: f64,
+    config: &SimulationConfig,
+    call_mult: f64, // 1.0 if pricing a call option, -1.0 if pricing a put option
+    weight: (f64, f64, f64)
+) -> PriceEstimate<V::Scalar> {
+    let steps = config.steps;
+    let sigma_sqrt_t = volatility * years_to_expiry.sqrt();
+    let drift = V::splat(
+        (risk_free_rate - dividend_yield - 0.5 * (volatility * volatility)) * years_to_expiry
+    );
+    let sigma_sqrt_t_simd = V::splat(sigma_sqrt_t);
+    // simulate_steps returns the sum of the normals divided by sqrt(2)
+    let normal_mult = V::splat(std::f64::consts::SQRT_2 / (steps as f64).sqrt());
+    let two_pi = V::splat(2.0 * std::f64::consts::PI);
+    let spot_simd = V::splat(spot);
+    let strike_simd = V::splat(strike);
+    let asset_mult_simd = V::splat(asset_mult);
+    let cash_simd = V::splat(cash);
+    let call_mult_simd = V::splat(call_mult);
+    let (weight_constant, weight_linear, weight_square) =
+        (V::splat(weight.0), V::splat(weight.1), V::splat(weight.2));
+    let zeros: V = V::splat(0.0);
+    let half: V = V::splat(0.5);
+
+    let weighted_payoff = |normal: V| {
+        let final_spot = spot_simd * V::mul_add(normal, sigma_sqrt_t_simd, drift).exp();
+        let in_the_money = zeros.cmp_lt(call_mult_simd * (final_spot - strike_simd));
+        let payoff = in_the_money.blend(V::mul_add(asset_mult_simd, final_spot, cash_simd), zeros);
+        let weight = V::mul_add(
+            V::mul_add(weight_square, normal, weight_linear),
+            normal,
+            weight_constant
+        );
+        payoff * weight
+    };
+
+    let (total_prices, total_squared_prices) = sum_batches::<V, _, _, _>(
+        config.num_trials,
+        config.rng_source,
+        (0.0, 0.0),
+        |(a, b), (c, d)| (a + c, b + d),
+        |rng, lanes| {
+            let normal = simulate_steps(two_pi, steps, rng) * normal_mult;
+            let price = if config.antithetic {
+                half * (weighted_payoff(normal) + weighted_payoff(-normal))
+            } else {
+                weighted_payoff(normal)
+            };
+            let price = lanes.blend(price, zeros);
+            (price.sum_lanes(), (price * price).sum_lanes())
+        }
+    );
+
+    PriceEstimate::from_sums(
+        total_prices,
+        total_squared_prices,
+        config.num_trials,
+        if config.antithetic { 2 * config.num_trials } else { config.num_trials },
+        (-risk_free_rate * years_to_expiry).exp()
+    )
+}
+
+/* Below are the simulations of models other than geometric Brownian motion */
+
+// The risk-neutral dynamics of the spot in a model with e.g. stochastic volatility or jumps,
+// simulated V::LANES paths at a time. The draws of every step are handed to the model, so
+// that the mirrored paths of antithetic variates can be simulated from the negated normals
+// and the complements of the uniforms of the same step.
+pub(crate) trait SpotModel<V: SimdFloat>: Sync {
+    // What the model keeps of every path between steps, e.g. the log spot and the variance
+    type State;
+
+    // Number of standard normals and of uniforms in [0, 1) that every step draws
+    fn draws_per_step(&self) -> (usize, usize);
+
+    // The state before the first step
+    fn start(&self) -> Self::State;
+
+    // Moves the paths on by one step, returning the spot after step + 1 steps
+    fn step(&self, state: &mut Self::State, step: usize, normals: &[V], uniforms: &[V]) -> V;
+}
+
+// Simulates one bundle of V::LANES paths of model, streaming the spot after every step to the
+// state as in simulate_path_states
+#[inline(always)]
+fn simulate_model_states<V: SimdFloat, M: SpotModel<V>, S>(
+    model: &M,
+    two_pi: V,
+    steps: usize,
+    antithetic: bool,
+    rng: &mut Xoshiro256PlusPlusX8,
+    start: impl Fn() -> S,
+    observe: impl Fn(&mut S, usize, V)
+) -> (S, Option<S>) {
+    let (num_normals, num_uniforms) = model.draws_per_step();
+    let one = V::splat(1.0);
+    let mut normals = vec![V::splat(0.0); num_normals];
+    let mut uniforms = vec![V::splat(0.0); num_uniforms];
+
+    let mut model_state = model.start();
+    let mut state = start();
+    let mut mirrored = if antithetic { Some((model.start(), start())) } else { None };
+    for step in 0..steps {
+        for_each_normal(two_pi, num_normals, rng, |draw, normal| {
+            normals[draw] = normal;
+        });
+        for pair in uniforms.chunks_mut(2) {
+            let (first_rand, second_rand) = V::uniform_pair(rng);
+            pair[0] = first_rand;
+            if let Some(second) = pair.get_mut(1) {
+                *second = second_rand;
+            }
+        }
+        observe(&mut state, step, model.step(&mut model_state, step, &normals, &uniforms));
+
+        if let Some((mirrored_model_state, mirrored_state)) = mirrored.as_mut() {
+            for normal in normals.iter_mut() {
+                *normal = -*normal;
+            }
+            for uniform in uniforms.iter_mut() {
+                *uniform = one - *uniform;
+            }
+            let spot = model.step(mirrored_model_state, step, &normals, &uniforms);
+            observe(mirrored_state, step, spot);
+        }
+    }
+    (state, mirrored.map(|(_, mirrored_state)| mirrored_state))
+}
+
+// Prices a path-dependent payoff on config.num_trials paths of model, as
+// monte_carlo_path_pricing does for geometric Brownian motion. The model is built for the
+// config.steps steps to years_to_expiry, and the payoff is discounted at risk_free_rate.
+pub(crate) fn monte_carlo_model_pricing<V: SimdFloat, M: SpotModel<V>, P: PathPayoff<V>>(
+    model: &M,
+    risk_free_rate: f64,
+    years_to_expiry: f64,
+    config: &SimulationConfig,
+    payoff: &P
+) -> PriceEstimate<V::Scalar> {
+    let two_pi = V::splat(2.0 * std::f64::consts::PI);
+    let zeros: V = V::splat(0.0);
+    let half: V = V::splat(0.5);
+
+    let (total_prices, total_squared_prices) = sum_batches::<V, _, _, _>(
+        config.num_trials,
+        config.rng_source,
+        (0.0, 0.0),
+        |(a, b), (c, d)| (a + c, b + d),
+        |rng, lanes| {
+            let (state, mirrored_state) = simulate_model_states(
+                model,
+                two_pi,
+                config.steps,
+                config.antithetic,
+                rng,
+                || payoff.start(),
+                |state, step, spot| payoff.observe(state, step, spot)
+            );
+            let price = match mirrored_state {
+                Some(mirrored_state) => half * (payoff.payoff(state) + payoff.payoff(mirrored_state)),
+                None => payoff.payoff(state),
+            };
+
+            let price = lanes.blend(price, zeros);
+            (price.sum_lanes(), (price * price).sum_lanes())
+        }
+    );
+
+    PriceEstimate::from_sums(
+        total_prices,
+        total_squared_prices,
+        config.num_trials,
+        if config.antithetic { 2 * config.num_trials } else { config.num_trials },
+        (-risk_free_rate * years_to_expiry).exp()
+    )
+}
+
+/* Below are the monte carlo simulation pricers */
+
+/// A payoff of the spot at expiry, `V::LANES` paths at a time, that the European pricers
+/// apply to every simulated spot. It is implemented for closures, so e.g. a call spread is
+/// `|spot: f32x8| (spot - low).fast_max(zero) - (spot - high).fast_max(zero)`.
+pub trait Payoff<V: SimdFloat>: Sync {
+    /// The undiscounted payoff of every lane given the spots at expiry
+    fn payoff(&self, spot: V) -> V;
+}
+
+impl<V: SimdFloat, P: Fn(V) -> V + Sync> Payoff<V> for P {
+    #[inline(always)]
+    fn payoff(&self, spot: V) -> V {
+        self(spot)
+    }
+}
+
+// The payoff of a European call or put, max(call_mult * (spot - strike), 0), with the strike
+// multiplied by call_mult up front to keep to one fused multiply-subtract
+pub(crate) struct Vanilla<V> {
+    call_mult: V,
+    strike: V,
+}
+
+impl<V: SimdFloat> Vanilla<V> {
+    pub(crate) fn new(strike: f64, call_mult: f64) -> Vanilla<V> {
+        Vanilla { call_mult: V::splat(call_mult), strike: V::splat(call_mult * strike) }
+    }
+}
+
+impl<V: SimdFloat> Payoff<V> for Vanilla<V> {
+    #[inline(always)]
+    fn payoff(&self, spot: V) -> V {
+        V::fast_max(V::mul_sub(self.call_mult, spot, self.strike), V::splat(0.0))
+    }
+}
+
+// A payoff of the spot at expiry as a path payoff, for the models that are only simulated
+// step by step
+pub(crate) struct Terminal<P>(pub(crate) P);
+
+impl<V: SimdFloat, P: Payoff<V>> PathPayoff<V> for Terminal<P> {
+    type State = V;
+
+    fn start(&self) -> V {
+        V::splat(0.0)
+    }
+
+    fn observe(&self, last_spot: &mut V, _step: usize, spot: V) {
+        *last_spot = spot;
+    }
+
+    fn payoff(&self, last_spot: V) -> V {
+        self.0.payoff(last_spot)
+    }
+}
+
+fn monte_carlo_pricing<V: SimdFloat, P: Payoff<V>>(
+    spot: f64,
+    volatility: f64,
+    risk_free_rate: f64,
+    years_to_expiry: f64,
+    dividend_yield: f64,
+    steps: usize,
+    num_trials: usize,
+    rng_source: RngSource,
+    payoff: &P
+) -> PriceEstimate<V::Scalar> {
+    let dt: f64 = years_to_expiry / (steps as f64);
+    let nudt: f64 = (risk_free_rate - dividend_yield - 0.5 * (volatility * volatility)) * dt;
+    let sidt: f64 = volatility * dt.sqrt();
+
+    let two_pi: V = V::splat(2.0 * std::f64::consts::PI);
+    let spot_simd = V::splat(spot);
+    let zeros: V = V::splat(0.0);
+    let nudt_simd: V = V::splat((steps as f64) * nudt); // multiply by steps since nudt appears n times in the inner most loop
+    let sidt_two_sqrt = V::splat(std::f64::consts::SQRT_2 * sidt); // take the sqrt(2) out of the box muller transform
+
+    // Sum the squared payoffs alongside the payoffs to get the standard error
+    let (total_prices, total_squared_prices) = sum_batches::<V, _, _, _>(
+        num_trials,
+        rng_source,
+        (0.0, 0.0),
+        |(a, b), (c, d)| (a + c, b + d),
+        |rng, lanes| {
+            let stock_price_mult: V = simulate_steps(two_pi, steps, rng);
+
+            let price = payoff.payoff(
+                spot_simd * V::mul_add(stock_price_mult, sidt_two_sqrt, nudt_simd).exp()
+            );
+            let price = lanes.blend(price, zeros);
+            (price.sum_lanes(), (price * price).sum_lanes())
+        }
+    );
+
+    let num_paths = num_trials;
+    PriceEstimate::from_sums(
+        total_prices,
+        total_squared_prices,
+        num_paths,
+        num_paths,
+        (-risk_free_rate * years_to_expiry).exp()
+    )
+}
+
+// Apply the antithetic variate method to reduce variance in simulated prices
+fn monte_carlo_av_pricing<V: SimdFloat, P: Payoff<V>>(
+    spot: f64,
+    volatility: f64,
+    risk_free_rate: f64,
+    years_to_expiry: f64,
+    dividend_yield: f64,
+    steps: usize,
+    num_trials: usize,
+    rng_source: RngSource,
+    payoff: &P
+) -> PriceEstimate<V::Scalar> {
+    let dt: f64 = years_to_expiry / (steps as f64);
+    let nudt: f64 = (risk_free_rate - dividend_yield - 0.5 * (volatility * volatility)) * dt;
+    let sidt: f64 = volatility * dt.sqrt();
+
+    let nudt_simd: V = V::splat((steps as f64) * nudt);
+    let spot_simd = V::splat(spot);
+    let zeros: V = V::splat(0.0);
+    let two_pi = V::splat(2.0 * std::f64::consts::PI);
+    let sidt_two_sqrt_plus = V::splat(std::f64::consts::SQRT_2 * sidt);
+    let sidt_two_sqrt_neg = V::splat(std::f64::consts::SQRT_2 * -sidt);
+
+    let half: V = V::splat(0.5);
+
+    // The two payoffs of an antithetic pair are correlated, so the standard error comes
+    // from the variance of the pair averages rather than of the individual payoffs
+    let (total_pair_prices, total_squared_pair_prices) = sum_batches::<V, _, _, _>(
+        num_trials,
+        rng_source,
+        (0.0, 0.0),
+        |(a, b), (c, d)| (a + c, b + d),
+        |rng, lanes| {
+            let stock_price_mult: V = simulate_steps(two_pi, steps, rng);
+
+            let price_pos = payoff.payoff(
+                spot_simd * V::mul_add(stock_price_mult, sidt_two_sqrt_plus, nudt_simd).exp()
+            );
+            let price_neg = payoff.payoff(
+                spot_simd * V::mul_add(stock_price_mult, sidt_two_sqrt_neg, nudt_simd).exp()
+            );
+            let pair_price = lanes.blend(half * (price_pos + price_neg), zeros);
+            (pair_price.sum_lanes(), (pair_price * pair_price).sum_lanes())
+        }
+    );
+
+    let num_pairs = num_trials;
+    PriceEstimate::from_sums(
+        total_pair_prices,
+        total_squared_pair_prices,
+        num_pairs,
+        2 * num_pairs,
+        (-risk_free_rate * years_to_expiry).exp()
+    )
+}
+
+// Price three options with spots of spot, spot - delta_spot, and spot + delta_spot
+fn monte_carlo_spot_pricing<V: SimdFloat, P: Payoff<V>>(
+    spot: f64,
+    delta_spot: f64,
+    volatility: f64,
+    risk_free_rate: f64,
+    years_to_expiry: f64,
+    dividend_yield: f64,
+    steps: usize,
+    num_trials: usize,
+    rng_source: RngSource,
+    payoff: &P
+) -> (f64, f64, f64) {
+    let dt: f64 = years_to_expiry / (steps as f64);
+
+    let nudt: f64 = (risk_free_rate - dividend_yield - 0.5 * (volatility * volatility)) * dt;
+    let sidt: f64 = volatility * dt.sqrt();
+
+    let nudt_simd: V = V::splat((steps as f64) * nudt);
+    let spot_simd = V::splat(spot);
+    let spot_minus_simd = V::splat(spot - delta_spot);
+    let spot_plus_simd = V::splat(spot + delta_spot);
+
+    let zeros: V = V::splat(0.0);
+    let two_pi = V::splat(2.0 * std::f64::consts::PI);
+    let sidt_two_sqrt = V::splat(std::f64::consts::SQRT_2 * sidt);
+
+    // Calculate three different three stock paths to find the Greeks delta and gamma
+    let (total, total_plus, total_minus) = sum_batches::<V, _, _, _>(
+        num_trials,
+        rng_source,
+        (0.0, 0.0, 0.0),
+        |(a, b, c), (d, e, f)| (a + d, b + e, c + f),
+        |rng, lanes| {
+            let stock_price_mult: V = simulate_steps(two_pi, steps, rng);
+
+            let stock_price_mult_exp = V
+                ::mul_add(stock_price_mult, sidt_two_sqrt, nudt_simd)
+                .exp();
+
+            (
+                lanes.blend(payoff.payoff(spot_simd * stock_price_mult_exp), zeros).sum_lanes(),
+                lanes.blend(payoff.payoff(spot_plus_simd * stock_price_mult_exp), zeros).sum_lanes(),
+                lanes.blend(payoff.payoff(spot_minus_simd * stock_price_mult_exp), zeros).sum_lanes(),
+            )
+        }
+    );
+
+    let final_mult = (-risk_free_rate * years_to_expiry).exp() / (num_trials as f64);
+
+    (
+        total_minus * final_mult,
+        total * final_mult,
+        total_plus * final_mult,
+    )
+}
+
+// Price two options with volatility+delta_volatility, volatility-delta_volatility
+fn monte_carlo_volatility_pricing<V: SimdFloat, P: Payoff<V>>(
+    spot: f64,
+    volatility: f64,
+    delta_volatility: f64,
+    risk_free_rate: f64,
+    years_to_expiry: f64,
+    dividend_yield: f64,
+    steps: usize,
+    num_trials: usize,
+    rng_source: RngSource,
+    payoff: &P
+) -> (f64, f64) {
+    let dt: f64 = years_to_expiry / (steps as f64);
+    let volatility_plus = volatility + delta_volatility;
+    let volatility_minus = volatility - delta_volatility;
+
+    let nudt_plus: f64 =
+        (risk_free_rate - dividend_yield - 0.5 * (volatility_plus * volatility_plus)) * dt;
+    let nudt_minus: f64 =
+        (risk_free_rate - dividend_yield - 0.5 * (volatility_minus * volatility_minus)) * dt;
+    let sidt_plus: f64 = volatility_plus * dt.sqrt();
+    let sidt_minus: f64 = volatility_minus * dt.sqrt();
+
+    let spot_simd = V::splat(spot);
+
+    let nudt_plus_simd: V = V::splat((steps as f64) * nudt_plus);
+    let nudt_minus_simd: V = V::splat((steps as f64) * nudt_minus);
+
+    let zeros: V = V::splat(0.0);
+    let two_pi = V::splat(2.0 * std::f64::consts::PI);
+    let sidt_two_sqrt_plus = V::splat(std::f64::consts::SQRT_2 * sidt_plus);
+    let sidt_two_sqrt_minus = V::splat(std::f64::consts::SQRT_2 * sidt_minus);
+
+    // Calculate two different three stock paths to find the Greek vega
+    let (total_plus, total_minus) = sum_batches::<V, _, _, _>(
+        num_trials,
+        rng_source,
+        (0.0, 0.0),
+        |(a, b), (c, d)| (a + c, b + d),
+        |rng, lanes| {
+            let stock_price_mult: V = simulate_steps(two_pi, steps, rng);
+
+            (
+                lanes.blend(
+                    payoff.payoff(
+                        spot_simd *
+                            V::mul_add(stock_price_mult, sidt_two_sqrt_plus, nudt_plus_simd).exp()
+                    ),
+                    zeros
+                ).sum_lanes(),
+                lanes.blend(
+                    payoff.payoff(
+                        spot_simd *
+                            V::mul_add(stock_price_mult, sidt_two_sqrt_minus, nudt_minus_simd).exp()
+                    ),
+                    zeros
+                ).sum_lanes(),
+            )
+        }
+    );
+
+    let final_mult = (-risk_free_rate * years_to_expiry).exp() / (num_trials as f64);
+
+    (total_minus * final_mult, total_plus * final_mult)
+}
+
+// Price two options with interest rates + and - delta_risk_free_rate
+fn monte_carlo_interest_pricing<V: SimdFloat, P: Payoff<V>>(
+    spot: f64,
+    volatility: f64,
+    risk_free_rate: f64,
+    delta_risk_free_rate: f64,
+    years_to_expiry: f64,
+    dividend_yield: f64,
+    steps: usize,
+    num_trials: usize,
+    rng_source: RngSource,
+    payoff: &P
+) -> (f64, f64) {
+    let dt: f64 = years_to_expiry / (steps as f64);
+    let rfr_plus = risk_free_rate + delta_risk_free_rate;
+    let rfr_minus = risk_free_rate - delta_risk_free_rate;
+
+    let nudt_plus: f64 = (rfr_plus - dividend_yield - 0.5 * (volatility * volatility)) * dt;
+    let nudt_minus: f64 = (rfr_minus - dividend_yield - 0.5 * (volatility * volatility)) * dt;
+    let sidt: f64 = volatility * dt.sqrt();
+
+    let nudt_plus_simd: V = V::splat((steps as f64) * nudt_plus);
+    let nudt_minus_simd: V = V::splat((steps as f64) * nudt_minus);
+
+    let spot_simd = V::splat(spot);
+
+    let zeros: V = V::splat(0.0);
+    let two_pi = V::splat(2.0 * std::f64::consts::PI);
+    let sidt_two_sqrt = V::splat(std::f64::consts::SQRT_2 * sidt);
+
+    // Calculate two different three stock paths to find the Greek rho
+    let (total_plus, total_minus) = sum_batches::<V, _, _, _>(
+        num_trials,
+        rng_source,
+        (0.0, 0.0),
+        |(a, b), (c, d)| (a + c, b + d),
+        |rng, lanes| {
+            let stock_price_mult: V = simulate_steps(two_pi, steps, rng);
+
+            (
+                lanes.blend(
+                    payoff.payoff(
+                        spot_simd * V::mul_add(stock_price_mult, sidt_two_sqrt, nudt_plus_simd).exp()
+                    ),
+                    zeros
+                ).sum_lanes(),
+                lanes.blend(
+                    payoff.payoff(
+                        spot_simd * V::mul_add(stock_price_mult, sidt_two_sqrt, nudt_minus_simd).exp()
+                    ),
+                    zeros
+                ).sum_lanes(),
+            )
+        }
+    );
+
+    (
+        (total_minus * (-rfr_minus * years_to_expiry).exp()) / (num_trials as f64),
+        (total_plus * (-rfr_plus * years_to_expiry).exp()) / (num_trials as f64),
+    )
+}
+
+// Price two options with years_to_expiry + and - delta_years_to_expiry
+fn monte_carlo_time_pricing<V: SimdFloat, P: Payoff<V>>(
+    spot: f64,
+    volatility: f64,
+    risk_free_rate: f64,
+    years_to_expiry: f64,
+    delta_years_to_expiry: f64,
+    dividend_yield: f64,
+    steps: usize,
+    num_trials: usize,
+    rng_source: RngSource,
+    payoff: &P
+) -> (f64, f64) {
+    let time_plus = years_to_expiry + delta_years_to_expiry;
+    let time_minus = years_to_expiry - delta_years_to_expiry;
+
+    let dt_plus: f64 = time_plus / (steps as f64);
+    let dt_minus: f64 = time_minus / (steps as f64);
+
+    let nudt_plus: f64 =
+        (risk_free_rate - dividend_yield - 0.5 * (volatility * volatility)) * dt_plus;
+    let nudt_minus: f64 =
+        (risk_free_rate - dividend_yield - 0.5 * (volatility * volatility)) * dt_minus;
+    let sidt_plus: f64 = volatility * dt_plus.sqrt();
+    let sidt_minus: f64 = volatility * dt_minus.sqrt();
+
+    let spot_simd = V::splat(spot);
+
+    let nudt_plus_simd: V = V::splat((steps as f64) * nudt_plus);
+    let nudt_minus_simd: V = V::splat((steps as f64) * nudt_minus);
+
+    let zeros: V = V::splat(0.0);
+    let two_pi = V::splat(2.0 * std::f64::consts::PI);
+    let sidt_two_sqrt_plus = V::splat(std::f64::consts::SQRT_2 * sidt_plus);
+    let sidt_two_sqrt_minus = V::splat(std::f64::consts::SQRT_2 * sidt_minus);
+
+    // Calculate two different three stock paths to find the Greek vega
+    let (total_plus, total_minus) = sum_batches::<V, _, _, _>(
+        num_trials,
+        rng_source,
+        (0.0, 0.0),
+        |(a, b), (c, d)| (a + c, b + d),
+        |rng, lanes| {
+            let stock_price_mult: V = simulate_steps(two_pi, steps, rng);
+
+            (
+                lanes.blend(
+                    payoff.payoff(
+                        spot_simd *
+                            V::mul_add(stock_price_mult, sidt_two_sqrt_plus, nudt_plus_simd).exp()
+                    ),
+                    zeros
+                ).sum_lanes(),
+                lanes.blend(
+                    payoff.payoff(
+                        spot_simd *
+                            V::mul_add(stock_price_mult, sidt_two_sqrt_minus, nudt_minus_simd).exp()
+                    ),
+                    zeros
+                ).sum_lanes(),
+            )
+        }
+    );
+
+    (
+        (total_minus * (-risk_free_rate * time_minus).exp()) / (num_trials as f64),
+        (total_plus * (-risk_free_rate * time_plus).exp()) / (num_trials as f64),
+    )
+}
+
+/* Below are the methods for getting prices and Greeks */
+
+// Check all of the parameters before simulating, since the simulations silently return NaN or
+// garbage for invalid inputs
+fn validate<F: Float>(
+    contract: &OptionContract<F>,
+    market: &MarketData<F>,
+    config: &SimulationConfig
+) -> Result<(), PricingError> {
+    contract.validate()?;
+    market.validate()?;
+    config.validate()
+}
+
+// Prices payoff of the spot after years_to_expiry years, with antithetic variates if the
+// config asks for them
+fn terminal_pricing<F: Float, P: Payoff<F::Lanes>>(
+    market: &MarketData<F>,
+    years_to_expiry: F,
+    config: &SimulationConfig,
+    payoff: &P
+) -> PriceEstimate<F> {
+    let pricer = if config.antithetic {
+        monte_carlo_av_pricing::<F::Lanes, P>
+    } else {
+        monte_carlo_pricing::<F::Lanes, P>
+    };
+    pricer(
+        market.spot.to_f64(),
+        market.volatility.to_f64(),
+        market.risk_free_rate.to_f64(),
+        years_to_expiry.to_f64(),
+        market.carry_yield().to_f64(),
+        config.steps,
+        config.num_trials,
+        config.rng_source,
+        payoff
+    )
+}
+
+// Calculates greek of payoff with central differences, pricing the bumped parameters on the
+// same draws in a single simulation
+fn terminal_greek<F: Float, P: Payoff<F::Lanes>>(
+    greek: Greek,
+    market: &MarketData<F>,
+    years_to_expiry: F,
+    config: &SimulationConfig,
+    payoff: &P
+) -> F {
+    let bumps = &config.bumps;
+    let value = match greek {
+        Greek::Delta | Greek::Gamma => {
+            let (price_minus, price, price_plus) = monte_carlo_spot_pricing::<F::Lanes, P>(
+                market.spot.to_f64(),
+                bumps.spot,
+                market.volatility.to_f64(),
+                market.risk_free_rate.to_f64(),
+                years_to_expiry.to_f64(),
+                market.carry_yield().to_f64(),
+                config.steps,
+                config.num_trials,
+                config.rng_source,
+                payoff
+            );
+            if greek == Greek::Delta {
+                (price_plus - price_minus) / (2.0 * bumps.spot)
+            } else {
+                (price_plus - 2.0 * price + price_minus) / (bumps.spot * bumps.spot)
+            }
+        }
+        Greek::Vega => {
+            let (price_minus, price_plus) = monte_carlo_volatility_pricing::<F::Lanes, P>(
+                market.spot.to_f64(),
+                market.volatility.to_f64(),
+                bumps.volatility,
+                market.risk_free_rate.to_f64(),
+                years_to_expiry.to_f64(),
+                market.carry_yield().to_f64(),
+                config.steps,
+                config.num_trials,
+                config.rng_source,
+                payoff
+            );
+            // Multiplied by 200.0 since we care about a change in 1% of the volatility
+            (price_plus - price_minus) / (200.0 * bumps.volatility)
+        }
+        // The futures price does not move with the rate, so only the discount factor does
+        Greek::Rho if market.underlying == Underlying::Futures =>
+            -0.01 * years_to_expiry.to_f64() * terminal_pricing(market, years_to_expiry, config, payoff).price.to_f64(),
+        Greek::Rho => {
+            let (price_minus, price_plus) = monte_carlo_interest_pricing::<F::Lanes, P>(
+                market.spot.to_f64(),
+                market.volatility.to_f64(),
+                market.risk_free_rate.to_f64(),
+                bumps.risk_free_rate,
+                years_to_expiry.to_f64(),
+                market.carry_yield().to_f64(),
+                config.steps,
+                config.num_trials,
+                config.rng_source,
+                payoff
+            );
+            // Multiplied by 200.0 since we care about a change in 1% of the interest rate
+            (price_plus - price_minus) / (200.0 * bumps.risk_free_rate)
+        }
+        Greek::Theta => {
+            let (price_minus, price_plus) = monte_carlo_time_pricing::<F::Lanes, P>(
+                market.spot.to_f64(),
+                market.volatility.to_f64(),
+                market.risk_free_rate.to_f64(),
+                years_to_expiry.to_f64(),
+                bumps.years_to_expiry,
+                market.carry_yield().to_f64(),
+                config.steps,
+                config.num_trials,
+                config.rng_source,
+                payoff
+            );
+            // price_minus comes first because it represents a future point in time compared to price_plus
+            (price_minus - price_plus) / (2.0 * bumps.years_to_expiry)
+        }
+    };
+    F::from_f64(value)
+}
+
+/// Price a European option, with the standard error and confidence interval of the price.
+/// The simulation runs on f32x8 lanes for `OptionContract<f32>` and on f64x4 lanes for
+/// `OptionContract<f64>`.
+pub fn price<F: Float>(
+    contract: &OptionContract<F>,
+    market: &MarketData<F>,
+    config: &SimulationConfig
+) -> Result<PriceEstimate<F>, PricingError> {
+    validate(contract, market, config)?;
+
+    let payoff = Vanilla::new(contract.strike.to_f64(), contract.option_type.call_mult());
+    Ok(terminal_pricing(market, contract.years_to_expiry, config, &payoff))
+}
+
+/// Calculate a Greek of a European option with the finite difference method, using the bump
+/// sizes in `config.bumps`. Vega and Rho are per 1% change in the volatility and the rate.
+pub fn greek<F: Float>(
+    greek: Greek,
+    contract: &OptionContract<F>,
+    market: &MarketData<F>,
+    config: &SimulationConfig
+) -> Result<F, PricingError> {
+    validate(contract, market, config)?;
+    config.bumps.validate(greek, contract.years_to_expiry, market)?;
+
+    let payoff = Vanilla::new(contract.strike.to_f64(), contract.option_type.call_mult());
+    Ok(terminal_greek(greek, market, contract.years_to_expiry, config, &payoff))
+}
+
+// Same as validate, for path-dependent options whose contract is not an OptionContract
+pub(crate) fn validate_path<F: Float>(
+    market: &MarketData<F>,
+    years_to_expiry: F,
+    config: &SimulationConfig
+) -> Result<(), PricingError> {
+    if !(years_to_expiry > F::from_f64(0.0) && years_to_expiry.is_finite()) {
+        return Err(PricingError::InvalidYearsToExpiry(years_to_expiry.to_f64()));
+    }
+    market.validate()?;
+    config.validate()
+}
+
+/// Price a European option with a custom [`Payoff`] of the spot at expiry in
+/// `years_to_expiry` years, with the standard error and confidence interval of the price
+pub fn price_payoff<F: Float, P: Payoff<F::Lanes>>(
+    market: &MarketData<F>,
+    years_to_expiry: F,
+    config: &SimulationConfig,
+    payoff: &P
+) -> Result<PriceEstimate<F>, PricingError> {
+    validate_path(market, years_to_expiry, config)?;
+
+    Ok(terminal_pricing(market, years_to_expiry, config, payoff))
+}
+
+/// Calculate a Greek of a European option with a custom [`Payoff`] as in [`greek`]
+pub fn payoff_greek<F: Float, P: Payoff<F::Lanes>>(
+    greek: Greek,
+    market: &MarketData<F>,
+    years_to_expiry: F,
+    config: &SimulationConfig,
+    payoff: &P
+) -> Result<F, PricingError> {
+    validate_path(market, years_to_expiry, config)?;
+    config.bumps.validate(greek, years_to_expiry, market)?;
+
+    Ok(terminal_greek(greek, market, years_to_expiry, config, payoff))
+}
+
+/// Price a path-dependent option expiring in `years_to_expiry` years, with the standard error
+/// and confidence interval of the price. The payoff is shown the spot after each of the
+/// `config.steps` steps, `F::Lanes::LANES` paths at a time, so the steps are the monitoring
+/// dates of the option.
+pub fn price_path<F: Float, P: PathPayoff<F::Lanes>>(
+    market: &MarketData<F>,
+    years_to_expiry: F,
+    config: &SimulationConfig,
+    payoff: &P
+) -> Result<PriceEstimate<F>, PricingError> {
+    validate_path(market, years_to_expiry, config)?;
+
+    Ok(monte_carlo_path_pricing::<F::Lanes, P, F>(
+        market.spot.to_f64(),
+        market.volatility.to_f64(),
+        market.risk_free_rate.to_f64(),
+        years_to_expiry.to_f64(),
+        market.carry_yield().to_f64(),
+        config.steps,
+        config.num_trials,
+        config.antithetic,
+        config.rng_source,
+        payoff
+    ))
+}
+
+/// Same as [`price_path`], for a payoff written against the whole path: `payoff` is passed
+/// the spots after every step, the first element being the spot after one step. Memory grows
+/// with the number of steps, which [`PathPayoff`] avoids.
+pub fn price_stored_path<F: Float, P: Fn(&[F::Lanes]) -> F::Lanes + Sync>(
+    market: &MarketData<F>,
+    years_to_expiry: F,
+    config: &SimulationConfig,
+    payoff: P
+) -> Result<PriceEstimate<F>, PricingError> {
+    price_path(market, years_to_expiry, config, &(StoredPath { steps: config.steps, payoff }))
+}
+
+/// Same as [`price_path`], using `control`, a second payoff of the same paths whose price
+/// `control_price` is known in closed form, as a control variate. The closer the two payoffs
+/// move together, the smaller the standard error.
+pub fn price_path_with_control<F: Float, P: PathPayoff<F::Lanes>, C: PathPayoff<F::Lanes>>(
+    market: &MarketData<F>,
+    years_to_expiry: F,
+    config: &SimulationConfig,
+    payoff: &P,
+    control: &C,
+    control_price: F
+) -> Result<PriceEstimate<F>, PricingError> {
+    validate_path(market, years_to_expiry, config)?;
+
+    Ok(monte_carlo_path_control_pricing::<F::Lanes, P, C>(
+        market.spot.to_f64(),
+        market.volatility.to_f64(),
+        market.risk_free_rate.to_f64(),
+        years_to_expiry.to_f64(),
+        market.carry_yield().to_f64(),
+        config.steps,
+        config.num_trials,
+        config.antithetic,
+        config.rng_source,
+        payoff,
+        control,
+        control_price.to_f64()
+    ))
+}
+
+/// Calculate a Greek of a path-dependent option with the finite difference method, using the
+/// bump sizes in `config.bumps`. Every bumped price is simulated on the same draws, so with
+/// [`RngSource::Entropy`] a random master seed is picked for all of them.
+pub fn path_greek<F: Float, P: PathPayoff<F::Lanes>>(
+    greek: Greek,
+    market: &MarketData<F>,
+    years_to_expiry: F,
+    config: &SimulationConfig,
+    payoff: &P
+) -> Result<F, PricingError> {
+    validate_path(market, years_to_expiry, config)?;
+    config.bumps.validate(greek, years_to_expiry, market)?;
+
+    let rng_source = match config.rng_source {
+        RngSource::Entropy => RngSource::Seeded(rand::random()),
+        rng_source => rng_source,
+    };
+    // Prices with the parameters moved by the given bumps. The prices are kept in f64, since
+    // their differences cancel most of their digits. The yield of a futures is the rate, so it
+    // moves with it.
+    let price = |spot_bump: f64, volatility_bump: f64, rate_bump: f64, time_bump: f64| {
+        let yield_bump = match market.underlying {
+            Underlying::Spot => 0.0,
+            Underlying::Futures => rate_bump,
+        };
+        monte_carlo_path_pricing::<F::Lanes, P, f64>(
+            market.spot.to_f64() + spot_bump,
+            market.volatility.to_f64() + volatility_bump,
+            market.risk_free_rate.to_f64() + rate_bump,
+            years_to_expiry.to_f64() + time_bump,
+            market.carry_yield().to_f64() + yield_bump,
+            config.steps,
+            config.num_trials,
+            config.antithetic,
+            rng_source,
+            payoff
+        ).price
+    };
+
+    let bumps = &config.bumps;
+    let value = match greek {
+        Greek::Delta =>
+            (price(bumps.spot, 0.0, 0.0, 0.0) - price(-bumps.spot, 0.0, 0.0, 0.0)) /
+            (2.0 * bumps.spot),
+        Greek::Gamma =>
+            (price(bumps.spot, 0.0, 0.0, 0.0) - 2.0 * price(0.0, 0.0, 0.0, 0.0) +
+                price(-bumps.spot, 0.0, 0.0, 0.0)) /
+            (bumps.spot * bumps.spot),
+        // Multiplied by 200.0 since we care about a change in 1% of the volatility and the rate
+        Greek::Vega =>
+            (price(0.0, bumps.volatility, 0.0, 0.0) - price(0.0, -bumps.volatility, 0.0, 0.0)) /
+            (200.0 * bumps.volatility),
+        Greek::Rho =>
+            (price(0.0, 0.0, bumps.risk_free_rate, 0.0) -
+                price(0.0, 0.0, -bumps.risk_free_rate, 0.0)) /
+            (200.0 * bumps.risk_free_rate),
+        // Less time to expiry is a later point in time
+        Greek::Theta =>
+            (price(0.0, 0.0, 0.0, -bumps.years_to_expiry) -
+                price(0.0, 0.0, 0.0, bumps.years_to_expiry)) /
+            (2.0 * bumps.years_to_expiry),
+    };
+    Ok(F::from_f64(value))
+}
+
+// Same as validate_path for options on several assets, returning the Cholesky factor of the
+// correlation matrix
+fn validate_multi_asset<F: Float>(
+    market: &MultiAssetMarket<F>,
+    years_to_expiry: F,
+    config: &SimulationConfig
+) -> Result<Vec<f64>, PricingError> {
+    if !(years_to_expiry > F::from_f64(0.0) && years_to_expiry.is_finite()) {
+        return Err(PricingError::InvalidYearsToExpiry(years_to_expiry.to_f64()));
+    }
+    market.validate()?;
+    config.validate()?;
+    market.correlation_factor()
+}
+
+/// Price an option on several correlated underlyings expiring in `years_to_expiry` years, with
+/// the standard error and confidence interval of the price. The correlation matrix of the
+/// market is Cholesky factored once, and the payoff is shown the spots of all the assets after
+/// each of the `config.steps` steps, `F::Lanes::LANES` paths at a time.
+pub fn price_multi_asset<F: Float, P: MultiAssetPayoff<F::Lanes>>(
+    market: &MultiAssetMarket<F>,
+    years_to_expiry: F,
+    config: &SimulationConfig,
+    payoff: &P
+) -> Result<PriceEstimate<F>, PricingError> {
+    let factor = validate_multi_asset(market, years_to_expiry, config)?;
+
+    Ok(monte_carlo_multi_asset_pricing::<F::Lanes, P>(
+        market,
+        &factor,
+        years_to_expiry.to_f64(),
+        config,
+        payoff
+    ))
+}
+
+/// Same as [`price_multi_asset`], using `control`, a second payoff of the same paths whose
+/// price `control_price` is known in closed form, as a control variate as in
+/// [`price_path_with_control`]
+pub fn price_multi_asset_with_control<
+    F: Float,
+    P: MultiAssetPayoff<F::Lanes>,
+    C: MultiAssetPayoff<F::Lanes>
+>(
+    market: &MultiAssetMarket<F>,
+    years_to_expiry: F,
+    config: &SimulationConfig,
+    payoff: &P,
+    control: &C,
+    control_price: F
+) -> Result<PriceEstimate<F>, PricingError> {
+    let factor = validate_multi_asset(market, years_to_expiry, config)?;
+
+    Ok(monte_carlo_multi_asset_control_pricing::<F::Lanes, P, C>(
+        market,
+        &factor,
+        years_to_expiry.to_f64(),
+        config,
+        payoff,
+        control,
+        control_price.to_f64()
+    ))
+}
+
+/// Price a call or put on the best or the worst of several assets, with the standard error
+/// and confidence interval of the price. Only the spots at expiry matter, so the paths are
+/// simulated in a single step whatever `config.steps` is.
+pub fn rainbow_price<F: Float>(
+    option: &RainbowOption<F>,
+    market: &MultiAssetMarket<F>,
+    config: &SimulationConfig
+) -> Result<PriceEstimate<F>, PricingError> {
+    OptionContract::new(option.option_type, option.strike, option.years_to_expiry).validate()?;
+    config.validate()?;
+
+    let payoff = RainbowPayoff {
+        call_mult: F::Lanes::splat(option.option_type.call_mult()),
+        strike: F::Lanes::splat(option.strike.to_f64()),
+        best: option.rainbow_type == RainbowType::BestOf,
+    };
+    price_multi_asset(market, option.years_to_expiry, &SimulationConfig { steps: 1, ..*config }, &payoff)
+}
+
+/// Price a lookback option, with the standard error and confidence interval of the price. The
+/// highest or lowest spot of a continuously monitored option is drawn between the steps, so
+/// any number of steps prices it without bias, while a discretely monitored option only looks
+/// at the spot at the end of each of the `config.steps` steps.
+pub fn lookback_price<F: Float>(
+    option: &LookbackOption<F>,
+    market: &MarketData<F>,
+    config: &SimulationConfig
+) -> Result<PriceEstimate<F>, PricingError> {
+    let strike = match option.strike {
+        LookbackStrike::Fixed(strike) => Some(strike),
+        LookbackStrike::Floating => None,
+    };
+    let contract = OptionContract::new(
+        option.option_type,
+        strike.unwrap_or(F::from_f64(0.0)),
+        option.years_to_expiry
+    );
+    validate(&contract, market, config)?;
+
+    Ok(monte_carlo_lookback_pricing::<F::Lanes>(
+        market.spot.to_f64(),
+        strike.map(|strike| strike.to_f64()),
+        market.volatility.to_f64(),
+        market.risk_free_rate.to_f64(),
+        option.years_to_expiry.to_f64(),
+        market.carry_yield().to_f64(),
+        option.monitoring == Monitoring::Continuous,
+        config,
+        option.option_type.call_mult()
+    ))
+}
+
+// Validates a digital option and prices it with the likelihood ratio weight of greek, or with
+// a weight of 1 for the price
+fn digital_pricing<F: Float>(
+    greek: Option<Greek>,
+    contract: &OptionContract<F>,
+    payoff: DigitalPayoff<F>,
+    market: &MarketData<F>,
+    config: &SimulationConfig
+) -> Result<PriceEstimate<F>, PricingError> {
+    validate(contract, market, config)?;
+
+    let call_mult = contract.option_type.call_mult();
+    let (asset_mult, cash) = match payoff {
+        DigitalPayoff::CashOrNothing(cash) => {
+            if !cash.is_finite() {
+                return Err(PricingError::InvalidPayoffAmount(cash.to_f64()));
+            }
+            (0.0, cash.to_f64())
+        }
+        DigitalPayoff::AssetOrNothing => (1.0, 0.0),
+        DigitalPayoff::Gap(payoff_strike) => {
+            if !(payoff_strike >= F::from_f64(0.0) && payoff_strike.is_finite()) {
+                return Err(PricingError::InvalidStrike(payoff_strike.to_f64()));
+            }
+            (call_mult, -call_mult * payoff_strike.to_f64())
+        }
+    };
+
+    let spot = market.spot.to_f64();
+    let volatility = market.volatility.to_f64();
+    let risk_free_rate = market.risk_free_rate.to_f64();
+    let years_to_expiry = contract.years_to_expiry.to_f64();
+    let drift = risk_free_rate - market.carry_yield().to_f64() - 0.5 * volatility * volatility;
+    let sqrt_t = years_to_expiry.sqrt();
+    // The derivatives of the log density of the spot at expiry, as a + b Z + c Z^2, and for
+    // Rho and Theta also of the discount factor. Vega and Rho are per 1% change.
+    let weight = match greek {
+        None => (1.0, 0.0, 0.0),
+        Some(Greek::Delta) => (0.0, 1.0 / (spot * volatility * sqrt_t), 0.0),
+        Some(Greek::Gamma) => {
+            let scale = 1.0 / (spot * spot * volatility * volatility * years_to_expiry);
+            (-scale, -scale * volatility * sqrt_t, scale)
+        }
+        Some(Greek::Vega) => (-0.01 / volatility, -0.01 * sqrt_t, 0.01 / volatility),
+        // The drift of a futures does not depend on the rate
+        Some(Greek::Rho) if market.underlying == Underlying::Futures => (-0.01 * years_to_expiry, 0.0, 0.0),
+        Some(Greek::Rho) => (-0.01 * years_to_expiry, (0.01 * sqrt_t) / volatility, 0.0),
+        // Theta is minus the derivative with respect to the time to expiry
+        Some(Greek::Theta) =>
+            (
+                0.5 / years_to_expiry + risk_free_rate,
+                -drift / (volatility * sqrt_t),
+                -0.5 / years_to_expiry,
+            ),
+    };
+
+    Ok(monte_carlo_digital_pricing::<F::Lanes>(
+        spot,
+        contract.strike.to_f64(),
+        asset_mult,
+        cash,
+        volatility,
+        risk_free_rate,
+        years_to_expiry,
+        market.carry_yield().to_f64(),
+        config,
+        call_mult,
+        weight
+    ))
+}
+
+/// Price a cash-or-nothing, asset-or-nothing or gap option, which pays `payoff` if it expires
+/// in the money against `contract.strike`, with the standard error and confidence interval
+/// of the price
+pub fn digital_price<F: Float>(
+    contract: &OptionContract<F>,
+    payoff: DigitalPayoff<F>,
+    market: &MarketData<F>,
+    config: &SimulationConfig
+) -> Result<PriceEstimate<F>, PricingError> {
+    digital_pricing(None, contract, payoff, market, config)
+}
+
+/// Calculate a Greek of a digital option with the likelihood ratio method. Finite differences
+/// of a payoff that jumps at the strike are dominated by the few paths that end up between the
+/// bumped strikes, so instead the payoff is weighted by the derivative of the log density of
+/// the spot at expiry, which is smooth. `config.bumps` is not used, and Vega and Rho are per
+/// 1% change in the volatility and the rate as in [`greek`].
+pub fn digital_greek<F: Float>(
+    greek: Greek,
+    contract: &OptionContract<F>,
+    payoff: DigitalPayoff<F>,
+    market: &MarketData<F>,
+    config: &SimulationConfig
+) -> Result<F, PricingError> {
+    Ok(digital_pricing(Some(greek), contract, payoff, market, config)?.price)
+}
+
+/* Below are the positional wrappers around price and greek, kept for compatibility.
+   They return NaN if the parameters are invalid, or if steps or num_trials is not a whole
+   number, use price and greek to get a PricingError instead. */
+
+// The config of the positional wrappers, which are given the counts as f32. None unless both
+// are whole numbers, since casting to usize would truncate fractions and turn NaN and negative
+// counts into 0.
+pub(crate) fn positional_config(steps: f32, num_trials: f32) -> Option<SimulationConfig> {
+    let count = |value: f32| (value >= 0.0 && value.fract() == 0.0).then_some(value as usize);
+    Some(SimulationConfig::new(count(steps)?, count(num_trials)?))
+}
+
+// What the positional wrappers return instead of an estimate for invalid parameters
+fn rejected_estimate() -> PriceEstimate {
+    PriceEstimate {
+        price: f32::NAN,
+        std_error: f32::NAN,
+        confidence_interval: (f32::NAN, f32::NAN),
+        num_paths: 0,
+    }
+}
+
+pub fn call_price(
+    spot: f32,
+    strike: f32,
+    volatility: f32,
+    risk_free_rate: f32,
+    years_to_expiry: f32,
+    dividend_yield: f32,
+    steps: f32,
+    num_trials: f32
+) -> f32 {
+    call_price_with_rng(
+        spot,
+        strike,
+        volatility,
+        risk_free_rate,
+        years_to_expiry,
+        dividend_yield,
+        steps,
+        num_trials,
+        RngSource::Entropy
+    )
+}
+
+/// Same as [`call_price`], drawing the random numbers from `rng_source`
+pub fn call_price_with_rng(
+    spot: f32,
+    strike: f32,
+    volatility: f32,
+    risk_free_rate: f32,
+    years_to_expiry: f32,
+    dividend_yield: f32,
+    steps: f32,
+    num_trials: f32,
+    rng_source: RngSource
+) -> f32 {
+    call_price_estimate_with_rng(
+        spot,
+        strike,
+        volatility,
+        risk_free_rate,
+        years_to_expiry,
+        dividend_yield,
+        steps,
+        num_trials,
+        rng_source
+    ).price
+}
+
+/// Same as [`call_price`], also returning the standard error and confidence interval of the price
+pub fn call_price_estimate(
+    spot: f32,
+    strike: f32,
+    volatility: f32,
+    risk_free_rate: f32,
+    years_to_expiry: f32,
+    dividend_yield: f32,
+    steps: f32,
+    num_trials: f32
+) -> PriceEstimate {
+    call_price_estimate_with_rng(
+        spot,
+        strike,
+        volatility,
+        risk_free_rate,
+        years_to_expiry,
+        dividend_yield,
+        steps,
+        num_trials,
+        RngSource::Entropy
+    )
+}
+
+/// Same as [`call_price_estimate`], drawing the random numbers from `rng_source`
+pub fn call_price_estimate_with_rng(
+    spot: f32,
+    strike: f32,
+    volatility: f32,
+    risk_free_rate: f32,
+    years_to_expiry: f32,
+    dividend_yield: f32,
+    steps: f32,
+    num_trials: f32,
+    rng_source: RngSource
+) -> PriceEstimate {
+    let Some(config) = positional_config(steps, num_trials) else {
+        return rejected_estimate();
+    };
+    price(
+        &OptionContract::new(OptionType::Call, strike, years_to_expiry),
+        &MarketData::new(spot, volatility, risk_free_rate, dividend_yield),
+        &SimulationConfig {
+            rng_source,
+            ..config
+        }
+    ).unwrap_or_else(|_| rejected_estimate())
 }
 
-/* Below are the monte carlo simulation pricers */
-
-fn monte_carlo_pricing(
+pub fn put_price(
     spot: f32,
     strike: f32,
     volatility: f32,
@@ -30,56 +2252,23 @@ fn monte_carlo_pricing(
     years_to_expiry: f32,
     dividend_yield: f32,
     steps: f32,
-    num_trials: f32,
-    call_mult: f32 // 1.0 if pricing a call option, -1.0 if pricing a put option
+    num_trials: f32
 ) -> f32 {
-    let dt: f32 = years_to_expiry / steps;
-    let nudt: f32 = (risk_free_rate - dividend_yield - 0.5 * (volatility * volatility)) * dt;
-    let sidt: f32 = volatility * dt.sqrt();
-
-    let two_pi: f32x8 = f32x8::splat(2.0 * std::f32::consts::PI);
-    let strike_f32x8 = f32x8::splat(call_mult * strike);
-    let spot_f32x8 = f32x8::splat(call_mult * spot);
-    let zeros: f32x8 = f32x8::splat(0.0);
-    let nudt_f32x8: f32x8 = f32x8::splat(steps * nudt); // multiply by steps since nudt appears n times in the inner most loop
-    let sidt_two_sqrt = f32x8::splat(std::f32::consts::SQRT_2 * sidt); // take the sqrt(2) out of the box muller transform
-
-    let half_steps: i32 = (steps as i32) / 2;
-
-    let total_prices: f32x8 = (0..(num_trials as i32) / 8)
-        .into_par_iter()
-        .map(|_| {
-            let mut seed: Xoshiro256PlusPlusX8Seed = Default::default();
-            rand::thread_rng().fill_bytes(&mut *seed);                                 // Generates the seed for the simd RNG
-            let mut rng: Xoshiro256PlusPlusX8 = Xoshiro256PlusPlusX8::from_seed(seed); // Get the random number generator for this thread
-
-            let mut stock_price_mult: f32x8 = f32x8::splat(0.0);
-
-            for _ in 0..half_steps {
-                stock_price_mult = speed_update(two_pi, stock_price_mult, &mut rng);
-            }
-
-            f32x8::fast_max(
-                f32x8::mul_sub(
-                    spot_f32x8,
-                    f32x8::mul_add(stock_price_mult, sidt_two_sqrt, nudt_f32x8).exp(),
-                    strike_f32x8
-                ),
-                zeros
-            )
-        })
-        .reduce(
-            || f32x8::splat(0.0),
-            |a, b| a + b
-        );
-
-    let call_option_price: f32 =
-        (total_prices.reduce_add() / num_trials) * (-risk_free_rate * years_to_expiry).exp();
-    call_option_price
+    put_price_with_rng(
+        spot,
+        strike,
+        volatility,
+        risk_free_rate,
+        years_to_expiry,
+        dividend_yield,
+        steps,
+        num_trials,
+        RngSource::Entropy
+    )
 }
 
-// Apply the antithetic variate method to reduce variance in simulated prices
-fn monte_carlo_av_pricing(
+/// Same as [`put_price`], drawing the random numbers from `rng_source`
+pub fn put_price_with_rng(
     spot: f32,
     strike: f32,
     volatility: f32,
@@ -88,70 +2277,48 @@ fn monte_carlo_av_pricing(
     dividend_yield: f32,
     steps: f32,
     num_trials: f32,
-    call_mult: f32 // 1.0 if pricing a call option, -1.0 if pricing a put option
+    rng_source: RngSource
 ) -> f32 {
-    let dt: f32 = years_to_expiry / steps;
-    let nudt: f32 = (risk_free_rate - dividend_yield - 0.5 * (volatility * volatility)) * dt;
-    let sidt: f32 = volatility * dt.sqrt();
-
-    let nudt_f32x8: f32x8 = f32x8::splat(steps * nudt);
-    let strike_f32x8 = f32x8::splat(call_mult * strike);
-    let spot_f32x8 = f32x8::splat(call_mult * spot);
-    let zeros: f32x8 = f32x8::splat(0.0);
-    let two_pi = f32x8::splat(2.0 * std::f32::consts::PI);
-    let sidt_two_sqrt_plus = f32x8::splat(std::f32::consts::SQRT_2 * sidt);
-    let sidt_two_sqrt_neg = f32x8::splat(std::f32::consts::SQRT_2 * -sidt);
-
-    let half_steps: i32 = (steps as i32) / 2;
-
-    let (total_prices_pos, total_prices_neg) = (0..(num_trials as i32) / 8)
-        .into_par_iter()
-        .map(|_| {
-            // Get the random number generator for this thread
-            let mut seed: Xoshiro256PlusPlusX8Seed = Default::default();
-            rand::thread_rng().fill_bytes(&mut *seed);
-            let mut rng: Xoshiro256PlusPlusX8 = Xoshiro256PlusPlusX8::from_seed(seed);
-
-            let mut stock_price_mult: f32x8 = f32x8::splat(0.0);
-
-            for _ in 0..half_steps {
-                stock_price_mult = speed_update(two_pi, stock_price_mult, &mut rng);
-            }
-
-            (
-                f32x8::fast_max(
-                    f32x8::mul_sub(
-                        spot_f32x8,
-                        f32x8::mul_add(stock_price_mult, sidt_two_sqrt_plus, nudt_f32x8).exp(),
-                        strike_f32x8
-                    ),
-                    zeros
-                ),
-                f32x8::fast_max(
-                    f32x8::mul_sub(
-                        spot_f32x8,
-                        f32x8::mul_add(stock_price_mult, sidt_two_sqrt_neg, nudt_f32x8).exp(),
-                        strike_f32x8
-                    ),
-                    zeros
-                ),
-            )
-        })
-        .reduce(
-            || (f32x8::splat(0.0), f32x8::splat(0.0)),
-            |(a, b), (c, d)| (a + c, b + d)
-        );
+    put_price_estimate_with_rng(
+        spot,
+        strike,
+        volatility,
+        risk_free_rate,
+        years_to_expiry,
+        dividend_yield,
+        steps,
+        num_trials,
+        rng_source
+    ).price
+}
 
-    let call_option_price: f32 =
-        ((0.5 * (total_prices_pos.reduce_add() + total_prices_neg.reduce_add())) / num_trials) *
-        (-risk_free_rate * years_to_expiry).exp();
-    call_option_price
+/// Same as [`put_price`], also returning the standard error and confidence interval of the price
+pub fn put_price_estimate(
+    spot: f32,
+    strike: f32,
+    volatility: f32,
+    risk_free_rate: f32,
+    years_to_expiry: f32,
+    dividend_yield: f32,
+    steps: f32,
+    num_trials: f32
+) -> PriceEstimate {
+    put_price_estimate_with_rng(
+        spot,
+        strike,
+        volatility,
+        risk_free_rate,
+        years_to_expiry,
+        dividend_yield,
+        steps,
+        num_trials,
+        RngSource::Entropy
+    )
 }
 
-// Price three options with spots of spot, spot - delta_spot, and spot + delta_spot
-fn monte_carlo_spot_pricing(
+/// Same as [`put_price_estimate`], drawing the random numbers from `rng_source`
+pub fn put_price_estimate_with_rng(
     spot: f32,
-    delta_spot: f32,
     strike: f32,
     volatility: f32,
     risk_free_rate: f32,
@@ -159,322 +2326,120 @@ fn monte_carlo_spot_pricing(
     dividend_yield: f32,
     steps: f32,
     num_trials: f32,
-    call_mult: f32 // 1.0 if pricing a call, -1.0 if pricing a put
-) -> (f32, f32, f32) {
-    let dt: f32 = years_to_expiry / steps;
-
-    let nudt: f32 = (risk_free_rate - dividend_yield - 0.5 * (volatility * volatility)) * dt;
-    let sidt: f32 = volatility * dt.sqrt();
-
-    let nudt_f32x8: f32x8 = f32x8::splat(steps * nudt);
-    let strike_f32x8 = f32x8::splat(strike * call_mult);
-    let spot_f32x8 = f32x8::splat(call_mult * spot);
-    let spot_minus_f32x8 = f32x8::splat(call_mult * (spot - delta_spot));
-    let spot_plus_f32x8 = f32x8::splat(call_mult * (spot + delta_spot));
-
-    let zeros: f32x8 = f32x8::splat(0.0);
-    let two_pi = f32x8::splat(2.0 * std::f32::consts::PI);
-    let sidt_two_sqrt = f32x8::splat(std::f32::consts::SQRT_2 * sidt);
-
-    let half_steps: i32 = (steps as i32) / 2;
-
-    // Calculate three different three stock paths to find the Greeks delta and gamma
-    let (total, total_plus, total_minus) = (0..(num_trials as i32) / 8)
-        .into_par_iter()
-        .map(|_| {
-            // Get the random number generator for this thread
-            let mut seed: Xoshiro256PlusPlusX8Seed = Default::default();
-            rand::thread_rng().fill_bytes(&mut *seed);
-            let mut rng: Xoshiro256PlusPlusX8 = Xoshiro256PlusPlusX8::from_seed(seed);
-
-            let mut stock_price_mult: f32x8 = f32x8::splat(0.0);
-
-            for _ in 0..half_steps {
-                stock_price_mult = speed_update(two_pi, stock_price_mult, &mut rng);
-            }
-
-            let stock_price_mult_exp = f32x8
-                ::mul_add(stock_price_mult, sidt_two_sqrt, nudt_f32x8)
-                .exp();
-
-            (
-                f32x8::fast_max(
-                    f32x8::mul_sub(spot_f32x8, stock_price_mult_exp, strike_f32x8),
-                    zeros
-                ),
-                f32x8::fast_max(
-                    f32x8::mul_sub(spot_plus_f32x8, stock_price_mult_exp, strike_f32x8),
-                    zeros
-                ),
-                f32x8::fast_max(
-                    f32x8::mul_sub(spot_minus_f32x8, stock_price_mult_exp, strike_f32x8),
-                    zeros
-                ),
-            )
-        })
-        .reduce(
-            || (f32x8::splat(0.0), f32x8::splat(0.0), f32x8::splat(0.0)),
-            |(a, b, c), (d, e, f)| (a + d, b + e, c + f)
-        );
-
-    let final_mult = (-risk_free_rate * years_to_expiry).exp() / num_trials;
-
-    (
-        total_minus.reduce_add() * final_mult,
-        total.reduce_add() * final_mult,
-        total_plus.reduce_add() * final_mult,
-    )
+    rng_source: RngSource
+) -> PriceEstimate {
+    let Some(config) = positional_config(steps, num_trials) else {
+        return rejected_estimate();
+    };
+    price(
+        &OptionContract::new(OptionType::Put, strike, years_to_expiry),
+        &MarketData::new(spot, volatility, risk_free_rate, dividend_yield),
+        &SimulationConfig {
+            rng_source,
+            ..config
+        }
+    ).unwrap_or_else(|_| rejected_estimate())
 }
 
-// Price two options with volatility+delta_volatility, volatility-delta_volatility
-fn monte_carlo_volatility_pricing(
+pub fn call_price_av(
     spot: f32,
     strike: f32,
     volatility: f32,
-    delta_volatility: f32,
     risk_free_rate: f32,
     years_to_expiry: f32,
     dividend_yield: f32,
     steps: f32,
     num_trials: f32
-) -> (f32, f32) {
-    let dt: f32 = years_to_expiry / steps;
-    let volatility_plus = volatility + delta_volatility;
-    let volatility_minus = volatility - delta_volatility;
-
-    let nudt_plus: f32 =
-        (risk_free_rate - dividend_yield - 0.5 * (volatility_plus * volatility_plus)) * dt;
-    let nudt_minus: f32 =
-        (risk_free_rate - dividend_yield - 0.5 * (volatility_minus * volatility_minus)) * dt;
-    let sidt_plus: f32 = volatility_plus * dt.sqrt();
-    let sidt_minus: f32 = volatility_minus * dt.sqrt();
-
-    let strike_f32x8 = f32x8::splat(strike);
-    let spot_f32x8 = f32x8::splat(spot);
-
-    let nudt_plus_f32x8: f32x8 = f32x8::splat(steps * nudt_plus);
-    let nudt_minus_f32x8: f32x8 = f32x8::splat(steps * nudt_minus);
-
-    let zeros: f32x8 = f32x8::splat(0.0);
-    let two_pi = f32x8::splat(2.0 * std::f32::consts::PI);
-    let sidt_two_sqrt_plus = f32x8::splat(std::f32::consts::SQRT_2 * sidt_plus);
-    let sidt_two_sqrt_minus = f32x8::splat(std::f32::consts::SQRT_2 * sidt_minus);
-
-    let half_steps: i32 = (steps as i32) / 2;
-
-    // Calculate two different three stock paths to find the Greek vega
-    let (total_plus, total_minus) = (0..(num_trials as i32) / 8)
-        .into_par_iter()
-        .map(|_| {
-            // Get the random number generator for this thread
-            let mut seed: Xoshiro256PlusPlusX8Seed = Default::default();
-            rand::thread_rng().fill_bytes(&mut *seed);
-            let mut rng: Xoshiro256PlusPlusX8 = Xoshiro256PlusPlusX8::from_seed(seed);
-
-            let mut stock_price_mult: f32x8 = f32x8::splat(0.0);
-
-            for _ in 0..half_steps {
-                stock_price_mult = speed_update(two_pi, stock_price_mult, &mut rng);
-            }
-
-            (
-                f32x8::fast_max(
-                    f32x8::mul_sub(
-                        spot_f32x8,
-                        f32x8::mul_add(stock_price_mult, sidt_two_sqrt_plus, nudt_plus_f32x8).exp(),
-                        strike_f32x8
-                    ),
-                    zeros
-                ),
-                f32x8::fast_max(
-                    f32x8::mul_sub(
-                        spot_f32x8,
-                        f32x8
-                            ::mul_add(stock_price_mult, sidt_two_sqrt_minus, nudt_minus_f32x8)
-                            .exp(),
-                        strike_f32x8
-                    ),
-                    zeros
-                ),
-            )
-        })
-        .reduce(
-            || (f32x8::splat(0.0), f32x8::splat(0.0)),
-            |(a, b), (c, d)| (a + c, b + d)
-        );
-
-    let final_mult = (-risk_free_rate * years_to_expiry).exp() / num_trials;
-
-    (total_minus.reduce_add() * final_mult, total_plus.reduce_add() * final_mult)
+) -> f32 {
+    call_price_av_with_rng(
+        spot,
+        strike,
+        volatility,
+        risk_free_rate,
+        years_to_expiry,
+        dividend_yield,
+        steps,
+        num_trials,
+        RngSource::Entropy
+    )
 }
 
-// Price two options with interest rates + and - delta_risk_free_rate
-fn monte_carlo_interest_pricing(
+/// Same as [`call_price_av`], drawing the random numbers from `rng_source`
+pub fn call_price_av_with_rng(
     spot: f32,
     strike: f32,
     volatility: f32,
     risk_free_rate: f32,
-    delta_risk_free_rate: f32,
     years_to_expiry: f32,
     dividend_yield: f32,
     steps: f32,
     num_trials: f32,
-    call_mult: f32 // 1.0 if pricing a call, -1.0 if pricing a put
-) -> (f32, f32) {
-    let dt: f32 = years_to_expiry / steps;
-    let rfr_plus = risk_free_rate + delta_risk_free_rate;
-    let rfr_minus = risk_free_rate - delta_risk_free_rate;
-
-    let nudt_plus: f32 = (rfr_plus - dividend_yield - 0.5 * (volatility * volatility)) * dt;
-    let nudt_minus: f32 = (rfr_minus - dividend_yield - 0.5 * (volatility * volatility)) * dt;
-    let sidt: f32 = volatility * dt.sqrt();
-
-    let nudt_plus_f32x8: f32x8 = f32x8::splat(steps * nudt_plus);
-    let nudt_minus_f32x8: f32x8 = f32x8::splat(steps * nudt_minus);
-
-    let strike_f32x8 = f32x8::splat(strike * call_mult);
-    let spot_f32x8 = f32x8::splat(spot * call_mult);
-
-    let zeros: f32x8 = f32x8::splat(0.0);
-    let two_pi = f32x8::splat(2.0 * std::f32::consts::PI);
-    let sidt_two_sqrt = f32x8::splat(std::f32::consts::SQRT_2 * sidt);
-
-    let half_steps: i32 = (steps as i32) / 2;
-
-    // Calculate two different three stock paths to find the Greek rho
-    let (total_plus, total_minus) = (0..(num_trials as i32) / 8)
-        .into_par_iter()
-        .map(|_| {
-            // Get the random number generator for this thread
-            let mut seed: Xoshiro256PlusPlusX8Seed = Default::default();
-            rand::thread_rng().fill_bytes(&mut *seed);
-            let mut rng: Xoshiro256PlusPlusX8 = Xoshiro256PlusPlusX8::from_seed(seed);
-
-            let mut stock_price_mult: f32x8 = f32x8::splat(0.0);
-
-            for _ in 0..half_steps {
-                stock_price_mult = speed_update(two_pi, stock_price_mult, &mut rng);
-            }
-
-            (
-                f32x8::fast_max(
-                    f32x8::mul_sub(
-                        spot_f32x8,
-                        f32x8::mul_add(stock_price_mult, sidt_two_sqrt, nudt_plus_f32x8).exp(),
-                        strike_f32x8
-                    ),
-                    zeros
-                ),
-                f32x8::fast_max(
-                    f32x8::mul_sub(
-                        spot_f32x8,
-                        f32x8::mul_add(stock_price_mult, sidt_two_sqrt, nudt_minus_f32x8).exp(),
-                        strike_f32x8
-                    ),
-                    zeros
-                ),
-            )
-        })
-        .reduce(
-            || (f32x8::splat(0.0), f32x8::splat(0.0)),
-            |(a, b), (c, d)| (a + c, b + d)
-        );
+    rng_source: RngSource
+) -> f32 {
+    call_price_av_estimate_with_rng(
+        spot,
+        strike,
+        volatility,
+        risk_free_rate,
+        years_to_expiry,
+        dividend_yield,
+        steps,
+        num_trials,
+        rng_source
+    ).price
+}
 
-    (
-        (total_minus.reduce_add() * (-rfr_minus * years_to_expiry).exp()) / num_trials,
-        (total_plus.reduce_add() * (-rfr_plus * years_to_expiry).exp()) / num_trials,
+/// Same as [`call_price_av`], also returning the standard error and confidence interval of the price
+pub fn call_price_av_estimate(
+    spot: f32,
+    strike: f32,
+    volatility: f32,
+    risk_free_rate: f32,
+    years_to_expiry: f32,
+    dividend_yield: f32,
+    steps: f32,
+    num_trials: f32
+) -> PriceEstimate {
+    call_price_av_estimate_with_rng(
+        spot,
+        strike,
+        volatility,
+        risk_free_rate,
+        years_to_expiry,
+        dividend_yield,
+        steps,
+        num_trials,
+        RngSource::Entropy
     )
 }
 
-// Price two options with years_to_expiry + and - delta_years_to_expiry
-fn monte_carlo_time_pricing(
+/// Same as [`call_price_av_estimate`], drawing the random numbers from `rng_source`
+pub fn call_price_av_estimate_with_rng(
     spot: f32,
     strike: f32,
     volatility: f32,
     risk_free_rate: f32,
     years_to_expiry: f32,
-    delta_years_to_expiry: f32,
     dividend_yield: f32,
     steps: f32,
     num_trials: f32,
-    call_mult: f32 // 1.0 if pricing a call, -1.0 if pricing a put
-) -> (f32, f32) {
-    let time_plus = years_to_expiry + delta_years_to_expiry;
-    let time_minus = years_to_expiry - delta_years_to_expiry;
-
-    let dt_plus: f32 = time_plus / steps;
-    let dt_minus: f32 = time_minus / steps;
-
-    let nudt_plus: f32 =
-        (risk_free_rate - dividend_yield - 0.5 * (volatility * volatility)) * dt_plus;
-    let nudt_minus: f32 =
-        (risk_free_rate - dividend_yield - 0.5 * (volatility * volatility)) * dt_minus;
-    let sidt_plus: f32 = volatility * dt_plus.sqrt();
-    let sidt_minus: f32 = volatility * dt_minus.sqrt();
-
-    let strike_f32x8 = f32x8::splat(strike * call_mult);
-    let spot_f32x8 = f32x8::splat(spot * call_mult);
-
-    let nudt_plus_f32x8: f32x8 = f32x8::splat(steps * nudt_plus);
-    let nudt_minus_f32x8: f32x8 = f32x8::splat(steps * nudt_minus);
-
-    let zeros: f32x8 = f32x8::splat(0.0);
-    let two_pi = f32x8::splat(2.0 * std::f32::consts::PI);
-    let sidt_two_sqrt_plus = f32x8::splat(std::f32::consts::SQRT_2 * sidt_plus);
-    let sidt_two_sqrt_minus = f32x8::splat(std::f32::consts::SQRT_2 * sidt_minus);
-
-    let half_steps: i32 = (steps as i32) / 2;
-
-    // Calculate two different three stock paths to find the Greek vega
-    let (total_plus, total_minus) = (0..(num_trials as i32) / 8)
-        .into_par_iter()
-        .map(|_| {
-            // Get the random number generator for this thread
-            let mut seed: Xoshiro256PlusPlusX8Seed = Default::default();
-            rand::thread_rng().fill_bytes(&mut *seed);
-            let mut rng: Xoshiro256PlusPlusX8 = Xoshiro256PlusPlusX8::from_seed(seed);
-
-            let mut stock_price_mult: f32x8 = f32x8::splat(0.0);
-
-            for _ in 0..half_steps {
-                stock_price_mult = speed_update(two_pi, stock_price_mult, &mut rng);
-            }
-
-            (
-                f32x8::fast_max(
-                    f32x8::mul_sub(
-                        spot_f32x8,
-                        f32x8::mul_add(stock_price_mult, sidt_two_sqrt_plus, nudt_plus_f32x8).exp(),
-                        strike_f32x8
-                    ),
-                    zeros
-                ),
-                f32x8::fast_max(
-                    f32x8::mul_sub(
-                        spot_f32x8,
-                        f32x8
-                            ::mul_add(stock_price_mult, sidt_two_sqrt_minus, nudt_minus_f32x8)
-                            .exp(),
-                        strike_f32x8
-                    ),
-                    zeros
-                ),
-            )
-        })
-        .reduce(
-            || (f32x8::splat(0.0), f32x8::splat(0.0)),
-            |(a, b), (c, d)| (a + c, b + d)
-        );
-
-    (
-        (total_minus.reduce_add() * (-risk_free_rate * time_minus).exp()) / num_trials,
-        (total_plus.reduce_add() * (-risk_free_rate * time_plus).exp()) / num_trials,
-    )
+    rng_source: RngSource
+) -> PriceEstimate {
+    let Some(config) = positional_config(steps, num_trials) else {
+        return rejected_estimate();
+    };
+    price(
+        &OptionContract::new(OptionType::Call, strike, years_to_expiry),
+        &MarketData::new(spot, volatility, risk_free_rate, dividend_yield),
+        &SimulationConfig {
+            antithetic: true,
+            rng_source,
+            ..config
+        }
+    ).unwrap_or_else(|_| rejected_estimate())
 }
 
-/* Below are the methods for getting prices and Greeks */
-
-pub fn call_price(
+pub fn put_price_av(
     spot: f32,
     strike: f32,
     volatility: f32,
@@ -484,7 +2449,7 @@ pub fn call_price(
     steps: f32,
     num_trials: f32
 ) -> f32 {
-    monte_carlo_pricing(
+    put_price_av_with_rng(
         spot,
         strike,
         volatility,
@@ -493,11 +2458,12 @@ pub fn call_price(
         dividend_yield,
         steps,
         num_trials,
-        1.0
+        RngSource::Entropy
     )
 }
 
-pub fn put_price(
+/// Same as [`put_price_av`], drawing the random numbers from `rng_source`
+pub fn put_price_av_with_rng(
     spot: f32,
     strike: f32,
     volatility: f32,
@@ -505,9 +2471,10 @@ pub fn put_price(
     years_to_expiry: f32,
     dividend_yield: f32,
     steps: f32,
-    num_trials: f32
+    num_trials: f32,
+    rng_source: RngSource
 ) -> f32 {
-    monte_carlo_pricing(
+    put_price_av_estimate_with_rng(
         spot,
         strike,
         volatility,
@@ -516,11 +2483,12 @@ pub fn put_price(
         dividend_yield,
         steps,
         num_trials,
-        -1.0
-    )
+        rng_source
+    ).price
 }
 
-pub fn call_price_av(
+/// Same as [`put_price_av`], also returning the standard error and confidence interval of the price
+pub fn put_price_av_estimate(
     spot: f32,
     strike: f32,
     volatility: f32,
@@ -529,8 +2497,8 @@ pub fn call_price_av(
     dividend_yield: f32,
     steps: f32,
     num_trials: f32
-) -> f32 {
-    monte_carlo_av_pricing(
+) -> PriceEstimate {
+    put_price_av_estimate_with_rng(
         spot,
         strike,
         volatility,
@@ -539,12 +2507,39 @@ pub fn call_price_av(
         dividend_yield,
         steps,
         num_trials,
-        1.0
+        RngSource::Entropy
     )
 }
 
-pub fn put_price_av(
+/// Same as [`put_price_av_estimate`], drawing the random numbers from `rng_source`
+pub fn put_price_av_estimate_with_rng(
+    spot: f32,
+    strike: f32,
+    volatility: f32,
+    risk_free_rate: f32,
+    years_to_expiry: f32,
+    dividend_yield: f32,
+    steps: f32,
+    num_trials: f32,
+    rng_source: RngSource
+) -> PriceEstimate {
+    let Some(config) = positional_config(steps, num_trials) else {
+        return rejected_estimate();
+    };
+    price(
+        &OptionContract::new(OptionType::Put, strike, years_to_expiry),
+        &MarketData::new(spot, volatility, risk_free_rate, dividend_yield),
+        &SimulationConfig {
+            antithetic: true,
+            rng_source,
+            ..config
+        }
+    ).unwrap_or_else(|_| rejected_estimate())
+}
+
+pub fn call_delta(
     spot: f32,
+    delta_spot: f32,
     strike: f32,
     volatility: f32,
     risk_free_rate: f32,
@@ -553,8 +2548,9 @@ pub fn put_price_av(
     steps: f32,
     num_trials: f32
 ) -> f32 {
-    monte_carlo_av_pricing(
+    call_delta_with_rng(
         spot,
+        delta_spot,
         strike,
         volatility,
         risk_free_rate,
@@ -562,11 +2558,39 @@ pub fn put_price_av(
         dividend_yield,
         steps,
         num_trials,
-        -1.0
+        RngSource::Entropy
     )
 }
 
-pub fn call_delta(
+/// Same as [`call_delta`], drawing the random numbers from `rng_source`
+pub fn call_delta_with_rng(
+    spot: f32,
+    delta_spot: f32,
+    strike: f32,
+    volatility: f32,
+    risk_free_rate: f32,
+    years_to_expiry: f32,
+    dividend_yield: f32,
+    steps: f32,
+    num_trials: f32,
+    rng_source: RngSource
+) -> f32 {
+    let Some(config) = positional_config(steps, num_trials) else {
+        return f32::NAN;
+    };
+    greek(
+        Greek::Delta,
+        &OptionContract::new(OptionType::Call, strike, years_to_expiry),
+        &MarketData::new(spot, volatility, risk_free_rate, dividend_yield),
+        &SimulationConfig {
+            rng_source,
+            bumps: GreekBumps { spot: delta_spot as f64, ..GreekBumps::default() },
+            ..config
+        }
+    ).unwrap_or(f32::NAN)
+}
+
+pub fn put_delta(
     spot: f32,
     delta_spot: f32,
     strike: f32,
@@ -577,7 +2601,7 @@ pub fn call_delta(
     steps: f32,
     num_trials: f32
 ) -> f32 {
-    let (price_minus, _, price_plus) = monte_carlo_spot_pricing(
+    put_delta_with_rng(
         spot,
         delta_spot,
         strike,
@@ -587,12 +2611,12 @@ pub fn call_delta(
         dividend_yield,
         steps,
         num_trials,
-        1.0
-    );
-    return (price_plus - price_minus) / (2.0 * delta_spot);
+        RngSource::Entropy
+    )
 }
 
-pub fn put_delta(
+/// Same as [`put_delta`], drawing the random numbers from `rng_source`
+pub fn put_delta_with_rng(
     spot: f32,
     delta_spot: f32,
     strike: f32,
@@ -601,21 +2625,22 @@ pub fn put_delta(
     years_to_expiry: f32,
     dividend_yield: f32,
     steps: f32,
-    num_trials: f32
+    num_trials: f32,
+    rng_source: RngSource
 ) -> f32 {
-    let (price_minus, _, price_plus) = monte_carlo_spot_pricing(
-        spot,
-        delta_spot,
-        strike,
-        volatility,
-        risk_free_rate,
-        years_to_expiry,
-        dividend_yield,
-        steps,
-        num_trials,
-        -1.0
-    );
-    return (price_plus - price_minus) / (2.0 * delta_spot);
+    let Some(config) = positional_config(steps, num_trials) else {
+        return f32::NAN;
+    };
+    greek(
+        Greek::Delta,
+        &OptionContract::new(OptionType::Put, strike, years_to_expiry),
+        &MarketData::new(spot, volatility, risk_free_rate, dividend_yield),
+        &SimulationConfig {
+            rng_source,
+            bumps: GreekBumps { spot: delta_spot as f64, ..GreekBumps::default() },
+            ..config
+        }
+    ).unwrap_or(f32::NAN)
 }
 
 pub fn gamma(
@@ -629,7 +2654,7 @@ pub fn gamma(
     steps: f32,
     num_trials: f32
 ) -> f32 {
-    let (price_minus, price, price_plus) = monte_carlo_spot_pricing(
+    gamma_with_rng(
         spot,
         delta_spot,
         strike,
@@ -639,9 +2664,36 @@ pub fn gamma(
         dividend_yield,
         steps,
         num_trials,
-        1.0
-    );
-    return (price_plus - 2.0 * price + price_minus) / (delta_spot * delta_spot);
+        RngSource::Entropy
+    )
+}
+
+/// Same as [`gamma`], drawing the random numbers from `rng_source`
+pub fn gamma_with_rng(
+    spot: f32,
+    delta_spot: f32,
+    strike: f32,
+    volatility: f32,
+    risk_free_rate: f32,
+    years_to_expiry: f32,
+    dividend_yield: f32,
+    steps: f32,
+    num_trials: f32,
+    rng_source: RngSource
+) -> f32 {
+    let Some(config) = positional_config(steps, num_trials) else {
+        return f32::NAN;
+    };
+    greek(
+        Greek::Gamma,
+        &OptionContract::new(OptionType::Call, strike, years_to_expiry),
+        &MarketData::new(spot, volatility, risk_free_rate, dividend_yield),
+        &SimulationConfig {
+            rng_source,
+            bumps: GreekBumps { spot: delta_spot as f64, ..GreekBumps::default() },
+            ..config
+        }
+    ).unwrap_or(f32::NAN)
 }
 
 pub fn vega(
@@ -655,7 +2707,7 @@ pub fn vega(
     steps: f32,
     num_trials: f32
 ) -> f32 {
-    let (price_minus, price_plus) = monte_carlo_volatility_pricing(
+    vega_with_rng(
         spot,
         strike,
         volatility,
@@ -664,10 +2716,37 @@ pub fn vega(
         years_to_expiry,
         dividend_yield,
         steps,
-        num_trials
-    );
-    // Multiplied by 200.0 since we care about a change in 1% of the volatility
-    (price_plus - price_minus) / (200.0 * delta_volatility)
+        num_trials,
+        RngSource::Entropy
+    )
+}
+
+/// Same as [`vega`], drawing the random numbers from `rng_source`
+pub fn vega_with_rng(
+    spot: f32,
+    strike: f32,
+    volatility: f32,
+    delta_volatility: f32,
+    risk_free_rate: f32,
+    years_to_expiry: f32,
+    dividend_yield: f32,
+    steps: f32,
+    num_trials: f32,
+    rng_source: RngSource
+) -> f32 {
+    let Some(config) = positional_config(steps, num_trials) else {
+        return f32::NAN;
+    };
+    greek(
+        Greek::Vega,
+        &OptionContract::new(OptionType::Call, strike, years_to_expiry),
+        &MarketData::new(spot, volatility, risk_free_rate, dividend_yield),
+        &SimulationConfig {
+            rng_source,
+            bumps: GreekBumps { volatility: delta_volatility as f64, ..GreekBumps::default() },
+            ..config
+        }
+    ).unwrap_or(f32::NAN)
 }
 
 pub fn call_rho(
@@ -681,7 +2760,7 @@ pub fn call_rho(
     steps: f32,
     num_trials: f32
 ) -> f32 {
-    let (price_minus, price_plus) = monte_carlo_interest_pricing(
+    call_rho_with_rng(
         spot,
         strike,
         volatility,
@@ -691,11 +2770,36 @@ pub fn call_rho(
         dividend_yield,
         steps,
         num_trials,
-        1.0
-    );
+        RngSource::Entropy
+    )
+}
 
-    // Multiplied by 200.0 since we care about a change in 1% of the interest rate
-    (price_plus - price_minus) / (200.0 * delta_risk_free_rate)
+/// Same as [`call_rho`], drawing the random numbers from `rng_source`
+pub fn call_rho_with_rng(
+    spot: f32,
+    strike: f32,
+    volatility: f32,
+    risk_free_rate: f32,
+    delta_risk_free_rate: f32,
+    years_to_expiry: f32,
+    dividend_yield: f32,
+    steps: f32,
+    num_trials: f32,
+    rng_source: RngSource
+) -> f32 {
+    let Some(config) = positional_config(steps, num_trials) else {
+        return f32::NAN;
+    };
+    greek(
+        Greek::Rho,
+        &OptionContract::new(OptionType::Call, strike, years_to_expiry),
+        &MarketData::new(spot, volatility, risk_free_rate, dividend_yield),
+        &SimulationConfig {
+            rng_source,
+            bumps: GreekBumps { risk_free_rate: delta_risk_free_rate as f64, ..GreekBumps::default() },
+            ..config
+        }
+    ).unwrap_or(f32::NAN)
 }
 
 pub fn put_rho(
@@ -709,7 +2813,7 @@ pub fn put_rho(
     steps: f32,
     num_trials: f32
 ) -> f32 {
-    let (price_minus, price_plus) = monte_carlo_interest_pricing(
+    put_rho_with_rng(
         spot,
         strike,
         volatility,
@@ -719,10 +2823,36 @@ pub fn put_rho(
         dividend_yield,
         steps,
         num_trials,
-        -1.0
-    );
-    // Multiplied by 200.0 since we care about a change in 1% of the interest rate
-    (price_plus - price_minus) / (200.0 * delta_risk_free_rate)
+        RngSource::Entropy
+    )
+}
+
+/// Same as [`put_rho`], drawing the random numbers from `rng_source`
+pub fn put_rho_with_rng(
+    spot: f32,
+    strike: f32,
+    volatility: f32,
+    risk_free_rate: f32,
+    delta_risk_free_rate: f32,
+    years_to_expiry: f32,
+    dividend_yield: f32,
+    steps: f32,
+    num_trials: f32,
+    rng_source: RngSource
+) -> f32 {
+    let Some(config) = positional_config(steps, num_trials) else {
+        return f32::NAN;
+    };
+    greek(
+        Greek::Rho,
+        &OptionContract::new(OptionType::Put, strike, years_to_expiry),
+        &MarketData::new(spot, volatility, risk_free_rate, dividend_yield),
+        &SimulationConfig {
+            rng_source,
+            bumps: GreekBumps { risk_free_rate: delta_risk_free_rate as f64, ..GreekBumps::default() },
+            ..config
+        }
+    ).unwrap_or(f32::NAN)
 }
 
 pub fn call_theta(
@@ -736,7 +2866,7 @@ pub fn call_theta(
     steps: f32,
     num_trials: f32
 ) -> f32 {
-    let (price_minus, price_plus) = monte_carlo_time_pricing(
+    call_theta_with_rng(
         spot,
         strike,
         volatility,
@@ -746,10 +2876,36 @@ pub fn call_theta(
         dividend_yield,
         steps,
         num_trials,
-        1.0
-    );
-    // price_minus comes first because it represents a future point in time compared to price_plus
-    (price_minus - price_plus) / (2.0 * delta_years_to_expiry)
+        RngSource::Entropy
+    )
+}
+
+/// Same as [`call_theta`], drawing the random numbers from `rng_source`
+pub fn call_theta_with_rng(
+    spot: f32,
+    strike: f32,
+    volatility: f32,
+    risk_free_rate: f32,
+    years_to_expiry: f32,
+    delta_years_to_expiry: f32,
+    dividend_yield: f32,
+    steps: f32,
+    num_trials: f32,
+    rng_source: RngSource
+) -> f32 {
+    let Some(config) = positional_config(steps, num_trials) else {
+        return f32::NAN;
+    };
+    greek(
+        Greek::Theta,
+        &OptionContract::new(OptionType::Call, strike, years_to_expiry),
+        &MarketData::new(spot, volatility, risk_free_rate, dividend_yield),
+        &SimulationConfig {
+            rng_source,
+            bumps: GreekBumps { years_to_expiry: delta_years_to_expiry as f64, ..GreekBumps::default() },
+            ..config
+        }
+    ).unwrap_or(f32::NAN)
 }
 
 pub fn put_theta(
@@ -763,7 +2919,7 @@ pub fn put_theta(
     steps: f32,
     num_trials: f32
 ) -> f32 {
-    let (price_minus, price_plus) = monte_carlo_time_pricing(
+    put_theta_with_rng(
         spot,
         strike,
         volatility,
@@ -773,9 +2929,36 @@ pub fn put_theta(
         dividend_yield,
         steps,
         num_trials,
-        -1.0
-    );
-    (price_minus - price_plus) / (2.0 * delta_years_to_expiry)
+        RngSource::Entropy
+    )
+}
+
+/// Same as [`put_theta`], drawing the random numbers from `rng_source`
+pub fn put_theta_with_rng(
+    spot: f32,
+    strike: f32,
+    volatility: f32,
+    risk_free_rate: f32,
+    years_to_expiry: f32,
+    delta_years_to_expiry: f32,
+    dividend_yield: f32,
+    steps: f32,
+    num_trials: f32,
+    rng_source: RngSource
+) -> f32 {
+    let Some(config) = positional_config(steps, num_trials) else {
+        return f32::NAN;
+    };
+    greek(
+        Greek::Theta,
+        &OptionContract::new(OptionType::Put, strike, years_to_expiry),
+        &MarketData::new(spot, volatility, risk_free_rate, dividend_yield),
+        &SimulationConfig {
+            rng_source,
+            bumps: GreekBumps { years_to_expiry: delta_years_to_expiry as f64, ..GreekBumps::default() },
+            ..config
+        }
+    ).unwrap_or(f32::NAN)
 }
 
 #[test]
@@ -905,3 +3088,769 @@ fn valid_put_theta2() {
     println!("mc_simd put theta 2 {} vs {}", theta, actual_theta);
     assert_eq!((theta - actual_theta).abs() < 0.1, true);
 }
+
+#[test]
+fn seeded_price_is_reproducible() {
+    let first = call_price_with_rng(100.0, 110.0, 0.25, 0.05, 0.5, 0.02, 100.0, 10000.0, RngSource::Seeded(7));
+    let second = call_price_with_rng(100.0, 110.0, 0.25, 0.05, 0.5, 0.02, 100.0, 10000.0, RngSource::Seeded(7));
+    let other_seed = call_price_with_rng(100.0, 110.0, 0.25, 0.05, 0.5, 0.02, 100.0, 10000.0, RngSource::Seeded(8));
+    println!("mc_simd seeded {} vs {} vs {}", first, second, other_seed);
+    assert_eq!(first.to_bits(), second.to_bits());
+    assert_ne!(first.to_bits(), other_seed.to_bits());
+}
+
+#[test]
+fn seeded_price_ignores_thread_count() {
+    let price_with_threads = |num_threads: usize| {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .unwrap()
+            .install(|| {
+                put_price_av_with_rng(112.0, 110.0, 0.14, 0.12, 1.0, 0.02, 100.0, 20000.0, RngSource::Seeded(11))
+            })
+    };
+    let single_thread = price_with_threads(1);
+    let multi_thread = price_with_threads(7);
+    println!("mc_simd seeded threads {} vs {}", single_thread, multi_thread);
+    assert_eq!(single_thread.to_bits(), multi_thread.to_bits());
+}
+
+#[test]
+fn seeded_greeks_are_reproducible() {
+    let first = gamma_with_rng(100.0, 0.01, 110.0, 0.25, 0.05, 0.5, 0.02, 100.0, 10000.0, RngSource::Seeded(3));
+    let second = gamma_with_rng(100.0, 0.01, 110.0, 0.25, 0.05, 0.5, 0.02, 100.0, 10000.0, RngSource::Seeded(3));
+    assert_eq!(first.to_bits(), second.to_bits());
+}
+
+#[test]
+fn rng_factory_is_used() {
+    fn factory(block: u64) -> Xoshiro256PlusPlusX8 {
+        seeded_rng(5, block)
+    }
+    let from_factory = call_price_with_rng(130.0, 120.0, 0.25, 0.05, 0.5, 0.02, 100.0, 1000.0, RngSource::Factory(factory));
+    let from_seed = call_price_with_rng(130.0, 120.0, 0.25, 0.05, 0.5, 0.02, 100.0, 1000.0, RngSource::Seeded(5));
+    assert_eq!(from_factory.to_bits(), from_seed.to_bits());
+}
+
+#[test]
+fn estimate_covers_bs_price() {
+    let actual_price = bs::call_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    let estimate = call_price_estimate_with_rng(100.0, 110.0, 0.25, 0.05, 0.5, 0.02, 100.0, 20000.0, RngSource::Seeded(1));
+    println!("mc_simd estimate {:?} vs {}", estimate, actual_price);
+    assert_eq!(estimate.num_paths, 20000);
+    assert!(estimate.std_error > 0.0 && estimate.std_error < 0.1);
+    assert!(estimate.confidence_interval.0 < estimate.price && estimate.price < estimate.confidence_interval.1);
+    // 4 standard errors keeps the test from failing on an unlucky seed
+    assert!((estimate.price - actual_price).abs() <= 4.0 * estimate.std_error);
+}
+
+#[test]
+fn estimate_matches_price() {
+    let price = put_price_with_rng(112.0, 110.0, 0.2, 0.05, 1.0, 0.02, 100.0, 10000.0, RngSource::Seeded(2));
+    let estimate = put_price_estimate_with_rng(112.0, 110.0, 0.2, 0.05, 1.0, 0.02, 100.0, 10000.0, RngSource::Seeded(2));
+    assert_eq!(price.to_bits(), estimate.price.to_bits());
+}
+
+#[test]
+fn av_estimate_reduces_std_error() {
+    let estimate = call_price_estimate_with_rng(112.0, 110.0, 0.2, 0.05, 1.0, 0.02, 100.0, 20000.0, RngSource::Seeded(3));
+    let av_estimate = call_price_av_estimate_with_rng(112.0, 110.0, 0.2, 0.05, 1.0, 0.02, 100.0, 20000.0, RngSource::Seeded(3));
+    println!("mc_simd av estimate {:?} vs {:?}", av_estimate, estimate);
+    assert_eq!(av_estimate.num_paths, 40000);
+    assert!(av_estimate.std_error < estimate.std_error);
+    let actual_price = bs::call_price(112.0, 110.0, 0.2, 0.05, 1.0, 0.02);
+    assert!((av_estimate.price - actual_price).abs() <= 4.0 * av_estimate.std_error);
+}
+
+#[test]
+fn valid_price_from_params() {
+    let contract = OptionContract::<f32>::builder().put().strike(110.0).years_to_expiry(1.0).build();
+    let market = MarketData::builder()
+        .spot(112.0)
+        .volatility(0.14)
+        .risk_free_rate(0.12)
+        .dividend_yield(0.02)
+        .build();
+    let config = SimulationConfig::builder()
+        .num_trials(10000)
+        .antithetic(true)
+        .seed(4)
+        .build()
+        .unwrap();
+    let actual_price = bs::put_price(112.0, 110.0, 0.14, 0.12, 1.0, 0.02);
+    let estimate = price(&contract.unwrap(), &market.unwrap(), &config).unwrap();
+    println!("mc_simd params {:?} vs {}", estimate, actual_price);
+    assert_eq!((actual_price - estimate.price).abs() <= 1.0, true);
+    assert_eq!(
+        estimate.price.to_bits(),
+        put_price_av_with_rng(112.0, 110.0, 0.14, 0.12, 1.0, 0.02, 100.0, 10000.0, RngSource::Seeded(4)).to_bits()
+    );
+}
+
+#[test]
+fn valid_greeks_from_params() {
+    let contract = OptionContract::new(OptionType::Call, 110.0, 0.5);
+    let market = MarketData::new(100.0, 0.25, 0.05, 0.02);
+    let config = SimulationConfig::builder().num_trials(10000).spot_bump(0.001).build().unwrap();
+    let delta = greek(Greek::Delta, &contract, &market, &config).unwrap();
+    let vega = greek(Greek::Vega, &contract, &market, &config).unwrap();
+    println!("mc_simd params delta {} vega {}", delta, vega);
+    assert_eq!((delta - bs::call_delta(100.0, 110.0, 0.25, 0.05, 0.5, 0.02)).abs() < 0.05, true);
+    assert_eq!((vega - bs::vega(100.0, 110.0, 0.25, 0.05, 0.5, 0.02)).abs() < 0.05, true);
+}
+
+#[test]
+fn invalid_parameters_are_rejected() {
+    let contract = OptionContract::new(OptionType::Call, 110.0, 0.5);
+    let market = MarketData::new(100.0, 0.25, 0.05, 0.02);
+    let config = SimulationConfig::new(100, 1000);
+
+    let bad_contract = OptionContract::new(OptionType::Call, 110.0, 0.0);
+    assert_eq!(price(&bad_contract, &market, &config), Err(PricingError::InvalidYearsToExpiry(0.0)));
+
+    let bad_market = MarketData::new(-100.0, 0.25, 0.05, 0.02);
+    assert_eq!(price(&contract, &bad_market, &config), Err(PricingError::InvalidSpot(-100.0)));
+
+    let bad_market = MarketData::new(100.0, 0.0, 0.05, 0.02);
+    assert_eq!(
+        greek(Greek::Delta, &contract, &bad_market, &config),
+        Err(PricingError::InvalidVolatility(0.0))
+    );
+
+    let bad_config = SimulationConfig::new(100, 0);
+    assert_eq!(
+        price(&contract, &market, &bad_config),
+        Err(PricingError::TooFewTrials { num_trials: 0, min_trials: 1 })
+    );
+
+    let bad_config = SimulationConfig::new(0, 1000);
+    assert_eq!(
+        price(&contract, &market, &bad_config),
+        Err(PricingError::TooFewSteps { steps: 0, min_steps: 1 })
+    );
+
+    let bad_bumps = SimulationConfig {
+        bumps: GreekBumps { volatility: 0.0, ..GreekBumps::default() },
+        ..config
+    };
+    assert_eq!(
+        greek(Greek::Vega, &contract, &market, &bad_bumps),
+        Err(PricingError::InvalidBump { greek: Greek::Vega, bump: 0.0 })
+    );
+    assert!(greek(Greek::Delta, &contract, &market, &bad_bumps).is_ok());
+}
+
+#[test]
+fn positional_wrappers_return_nan_on_invalid_parameters() {
+    assert!(call_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02, 100.0, 0.0).is_nan());
+    assert!(put_price(-100.0, 110.0, 0.25, 0.05, 0.5, 0.02, 100.0, 1000.0).is_nan());
+    assert!(call_delta(100.0, 0.0, 110.0, 0.25, 0.05, 0.5, 0.02, 100.0, 1000.0).is_nan());
+    // Counts that are not whole numbers are rejected rather than truncated
+    for steps in [10.5, -1.0, f32::NAN, f32::INFINITY] {
+        assert!(call_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02, steps, 1000.0).is_nan());
+    }
+    let estimate = call_price_av_estimate(100.0, 110.0, 0.25, 0.05, 0.5, 0.02, 100.0, 0.5);
+    assert!(estimate.price.is_nan() && estimate.std_error.is_nan());
+    assert_eq!(estimate.num_paths, 0);
+}
+
+#[test]
+fn unbiased_for_awkward_trial_counts() {
+    let actual_price = bs::call_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    for num_trials in [1, 7, 9, 101, 1001] {
+        // Average enough independent runs to get ~10000 paths, so that dropping or miscounting
+        // the trials of the last batch would show up as a bias
+        let num_runs = 10000 / num_trials + 1;
+        let mut total_price = 0.0;
+        for run in 0..num_runs {
+            let estimate = call_price_estimate_with_rng(
+                100.0, 110.0, 0.25, 0.05, 0.5, 0.02, 10.0, num_trials as f32, RngSource::Seeded(run as u64)
+            );
+            assert_eq!(estimate.num_paths, num_trials);
+            total_price += estimate.price;
+        }
+        let price = total_price / (num_runs as f32);
+        println!("mc_simd {} trials {} vs {}", num_trials, price, actual_price);
+        assert!((price - actual_price).abs() < 0.3);
+    }
+}
+
+#[test]
+fn unbiased_for_awkward_step_counts() {
+    let actual_price = bs::put_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    for steps in [1, 7, 9, 101, 1001] {
+        let estimate = put_price_av_estimate_with_rng(
+            100.0, 110.0, 0.25, 0.05, 0.5, 0.02, steps as f32, 4000.0, RngSource::Seeded(steps as u64)
+        );
+        println!("mc_simd {} steps {:?} vs {}", steps, estimate, actual_price);
+        assert!((estimate.price - actual_price).abs() <= 4.0 * estimate.std_error);
+    }
+}
+
+#[test]
+fn single_trial_has_no_std_error() {
+    // Deep in the money, so that the single path pays off whatever it draws
+    let estimate = call_price_estimate_with_rng(100.0, 40.0, 0.25, 0.05, 0.5, 0.02, 1.0, 1.0, RngSource::Seeded(9));
+    assert_eq!(estimate.num_paths, 1);
+    assert!(estimate.price > 0.0);
+    assert!(estimate.std_error.is_nan());
+}
+
+#[test]
+fn f64_price_matches_bs() {
+    let contract = OptionContract::<f64>::new(OptionType::Call, 110.0, 0.5);
+    let market = MarketData::<f64>::new(100.0, 0.25, 0.05, 0.02);
+    let config = SimulationConfig::builder()
+        .steps(1)
+        .num_trials(200000)
+        .antithetic(true)
+        .seed(6)
+        .build()
+        .unwrap();
+    let actual_price = bs::call_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    let estimate: PriceEstimate<f64> = price(&contract, &market, &config).unwrap();
+    println!("mc_simd f64 {:?} vs {}", estimate, actual_price);
+    assert!(estimate.std_error < 0.02);
+    assert!((estimate.price - actual_price).abs() <= 4.0 * estimate.std_error);
+}
+
+#[test]
+fn f64_deep_otm_price_matches_bs() {
+    let contract = OptionContract::<f64>::new(OptionType::Call, 150.0, 0.5);
+    let market = MarketData::<f64>::new(100.0, 0.2, 0.05, 0.02);
+    let config = SimulationConfig::builder()
+        .steps(1)
+        .num_trials(200000)
+        .antithetic(true)
+        .seed(7)
+        .build()
+        .unwrap();
+    let actual_price = bs::call_price(100.0, 150.0, 0.2, 0.05, 0.5, 0.02);
+    let estimate = price(&contract, &market, &config).unwrap();
+    println!("mc_simd f64 deep otm {:?} vs {}", estimate, actual_price);
+    assert!(estimate.price > 0.0);
+    assert!((estimate.price - actual_price).abs() <= 4.0 * estimate.std_error);
+}
+
+#[test]
+fn f64_greeks_match_bs() {
+    let contract = OptionContract::<f64>::new(OptionType::Put, 110.0, 0.5);
+    let market = MarketData::<f64>::new(100.0, 0.25, 0.05, 0.02);
+    let config = SimulationConfig::builder().steps(1).num_trials(100000).seed(8).build().unwrap();
+
+    let delta = greek(Greek::Delta, &contract, &market, &config).unwrap();
+    let actual_delta: f64 = bs::put_delta(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    println!("mc_simd f64 delta {} vs {}", delta, actual_delta);
+    assert!((delta - actual_delta).abs() < 0.01);
+
+    let rho = greek(Greek::Rho, &contract, &market, &config).unwrap();
+    let actual_rho: f64 = bs::put_rho(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    println!("mc_simd f64 rho {} vs {}", rho, actual_rho);
+    assert!((rho - actual_rho).abs() < 0.01);
+}
+
+#[test]
+fn f32_greeks_keep_digits_with_many_paths() {
+    // Gamma divides the second difference of three prices by the squared bump, so lanes summed
+    // in f32 over millions of paths leave nothing of it
+    let contract = OptionContract::new(OptionType::Call, 105.0f32, 1.0);
+    let market = MarketData::new(100.0f32, 0.2, 0.05, 0.01);
+    let config = SimulationConfig::builder().steps(1).num_trials(4000000).seed(87).build().unwrap();
+    let cases = [
+        (Greek::Delta, bs::call_delta(100.0f64, 105.0, 0.2, 0.05, 1.0, 0.01), 1e-3),
+        (Greek::Gamma, bs::gamma(100.0, 105.0, 0.2, 0.05, 1.0, 0.01), 5e-4),
+        (Greek::Theta, bs::call_theta(100.0, 105.0, 0.2, 0.05, 1.0, 0.01), 1e-2),
+    ];
+    for (greek_type, actual, tolerance) in cases {
+        let value = greek(greek_type, &contract, &market, &config).unwrap();
+        println!("mc_simd f32 {:?} {} vs {}", greek_type, value, actual);
+        assert!((f64::from(value) - actual).abs() < tolerance);
+    }
+}
+
+#[test]
+fn f64_and_f32_prices_agree() {
+    let config = SimulationConfig::builder().steps(1).num_trials(20000).seed(9).build().unwrap();
+    let estimate_f32 = price(
+        &OptionContract::<f32>::new(OptionType::Put, 110.0, 0.5),
+        &MarketData::<f32>::new(100.0, 0.25, 0.05, 0.02),
+        &config
+    ).unwrap();
+    let estimate_f64 = price(
+        &OptionContract::<f64>::new(OptionType::Put, 110.0, 0.5),
+        &MarketData::<f64>::new(100.0, 0.25, 0.05, 0.02),
+        &config
+    ).unwrap();
+    println!("mc_simd f32 {:?} vs f64 {:?}", estimate_f32, estimate_f64);
+    let std_error = ((estimate_f32.std_error as f64).powi(2) + estimate_f64.std_error.powi(2)).sqrt();
+    assert!(((estimate_f32.price as f64) - estimate_f64.price).abs() <= 4.0 * std_error);
+}
+
+
+#[cfg(test)]
+use wide::{ f32x8, f64x4 };
+#[cfg(test)]
+use crate::params::two_asset_market;
+
+// Average of the spots after every step, kept as a running sum
+#[cfg(test)]
+struct RunningAverageCall<V> {
+    strike: V,
+    steps: usize,
+}
+
+#[cfg(test)]
+impl<V: SimdFloat> PathPayoff<V> for RunningAverageCall<V> {
+    type State = V;
+
+    fn start(&self) -> V {
+        V::splat(0.0)
+    }
+
+    fn observe(&self, sum: &mut V, _step: usize, spot: V) {
+        *sum = *sum + spot;
+    }
+
+    fn payoff(&self, sum: V) -> V {
+        V::fast_max(V::mul_sub(sum, V::splat(1.0 / (self.steps as f64)), self.strike), V::splat(0.0))
+    }
+}
+
+#[test]
+fn stored_path_terminal_payoff_matches_bs() {
+    let market = MarketData::<f32>::new(100.0, 0.25, 0.05, 0.02);
+    for steps in [1, 7, 20] {
+        let config = SimulationConfig::builder().steps(steps).num_trials(100003).seed(20).build().unwrap();
+        let estimate = price_stored_path(&market, 0.5, &config, |spots: &[f32x8]| {
+            f32x8::fast_max(spots[spots.len() - 1] - f32x8::splat(110.0), f32x8::splat(0.0))
+        }).unwrap();
+        let actual_price = bs::call_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+        println!("path call with {} steps {:?} vs {}", steps, estimate, actual_price);
+        assert_eq!(estimate.num_paths, 100003);
+        assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+    }
+}
+
+#[test]
+fn streamed_and_stored_paths_agree() {
+    let market = MarketData::<f64>::new(100.0, 0.25, 0.05, 0.02);
+    let config = SimulationConfig::builder().steps(12).num_trials(1001).seed(21).build().unwrap();
+    let streamed = price_path(
+        &market,
+        1.0,
+        &config,
+        &(RunningAverageCall { strike: f64x4::splat(100.0), steps: 12 })
+    ).unwrap();
+    let stored = price_stored_path(&market, 1.0, &config, |spots: &[f64x4]| {
+        let sum = spots.iter().fold(f64x4::splat(0.0), |sum, &spot| sum + spot);
+        f64x4::fast_max(sum * f64x4::splat(1.0 / 12.0) - f64x4::splat(100.0), f64x4::splat(0.0))
+    }).unwrap();
+    println!("average call {:?} vs {:?}", streamed, stored);
+    assert!((streamed.price - stored.price).abs() < 1e-12);
+    assert!((streamed.std_error - stored.std_error).abs() < 1e-12);
+}
+
+#[test]
+fn antithetic_paths_reduce_std_error() {
+    let market = MarketData::<f64>::new(100.0, 0.25, 0.05, 0.02);
+    let payoff = RunningAverageCall { strike: f64x4::splat(100.0), steps: 10 };
+    let config = SimulationConfig::builder().steps(10).num_trials(20000).seed(22).build().unwrap();
+    let av_config = SimulationConfig { antithetic: true, ..config };
+    let estimate = price_path(&market, 1.0, &config, &payoff).unwrap();
+    let av_estimate = price_path(&market, 1.0, &av_config, &payoff).unwrap();
+    println!("average call {:?} vs antithetic {:?}", estimate, av_estimate);
+    assert_eq!(av_estimate.num_paths, 40000);
+    assert!(av_estimate.std_error < estimate.std_error);
+    assert!((estimate.price - av_estimate.price).abs() < 4.0 * estimate.std_error);
+}
+
+#[test]
+fn path_pricing_rejects_invalid_parameters() {
+    let market = MarketData::<f32>::new(100.0, 0.25, 0.05, 0.02);
+    let config = SimulationConfig::new(10, 100);
+    let payoff = RunningAverageCall { strike: f32x8::splat(100.0), steps: 10 };
+    assert_eq!(
+        price_path(&market, 0.0, &config, &payoff),
+        Err(PricingError::InvalidYearsToExpiry(0.0))
+    );
+    assert_eq!(
+        price_path(&MarketData::<f32>::new(-1.0, 0.25, 0.05, 0.02), 1.0, &config, &payoff),
+        Err(PricingError::InvalidSpot(-1.0))
+    );
+}
+
+#[cfg(test)]
+fn lookback_option<F: Float>(
+    option_type: OptionType,
+    strike: LookbackStrike<F>
+) -> LookbackOption<F> {
+    LookbackOption {
+        option_type,
+        strike,
+        years_to_expiry: F::from_f64(0.5),
+        monitoring: Monitoring::Continuous,
+    }
+}
+
+#[test]
+fn valid_floating_lookback_prices() {
+    let market = MarketData::new(100.0, 0.25, 0.05, 0.02);
+    let config = SimulationConfig::builder().steps(10).num_trials(40000).seed(50).build().unwrap();
+
+    let actual_price = bs::floating_lookback_call_price(100.0, 100.0, 0.25, 0.05, 0.5, 0.02);
+    let option = lookback_option(OptionType::Call, LookbackStrike::Floating);
+    let estimate = lookback_price(&option, &market, &config).unwrap();
+    println!("mc_simd floating lookback call {:?} vs {}", estimate, actual_price);
+    assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+
+    let actual_price = bs::floating_lookback_put_price(100.0, 100.0, 0.25, 0.05, 0.5, 0.02);
+    let option = lookback_option(OptionType::Put, LookbackStrike::Floating);
+    let estimate = lookback_price(&option, &market, &config).unwrap();
+    println!("mc_simd floating lookback put {:?} vs {}", estimate, actual_price);
+    assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+}
+
+#[test]
+fn valid_fixed_lookback_prices() {
+    let market = MarketData::<f64>::new(100.0, 0.25, 0.05, 0.02);
+    let config = SimulationConfig::builder().steps(7).num_trials(40000).seed(51).antithetic(true).build().unwrap();
+
+    let actual_price = bs::fixed_lookback_call_price(100.0, 110.0, 100.0, 0.25, 0.05, 0.5, 0.02);
+    let option = lookback_option(OptionType::Call, LookbackStrike::Fixed(110.0));
+    let estimate = lookback_price(&option, &market, &config).unwrap();
+    println!("mc_simd fixed lookback call {:?} vs {}", estimate, actual_price);
+    assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+
+    let actual_price = bs::fixed_lookback_put_price(100.0, 95.0, 100.0, 0.25, 0.05, 0.5, 0.02);
+    let option = lookback_option(OptionType::Put, LookbackStrike::Fixed(95.0));
+    let estimate = lookback_price(&option, &market, &config).unwrap();
+    println!("mc_simd fixed lookback put {:?} vs {}", estimate, actual_price);
+    assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+}
+
+#[test]
+fn discrete_lookback_misses_extremes_between_steps() {
+    let market = MarketData::<f64>::new(100.0, 0.25, 0.05, 0.02);
+    let config = SimulationConfig::builder().steps(10).num_trials(40000).seed(52).build().unwrap();
+    let option = lookback_option(OptionType::Put, LookbackStrike::Floating);
+    let continuous = lookback_price(&option, &market, &config).unwrap();
+    let discrete_option = LookbackOption { monitoring: Monitoring::Discrete, ..option };
+    let discrete = lookback_price(&discrete_option, &market, &config).unwrap();
+    println!("mc_simd discrete lookback put {:?} vs continuous {:?}", discrete, continuous);
+    // Both runs share their draws, so the gap is far larger than the noise
+    assert!(discrete.price < continuous.price - 1.0);
+
+    assert_eq!(
+        lookback_price(&lookback_option(OptionType::Call, LookbackStrike::Fixed(-1.0)), &market, &config),
+        Err(PricingError::InvalidStrike(-1.0))
+    );
+}
+
+
+#[test]
+fn valid_digital_prices() {
+    let market = MarketData::<f64>::new(100.0, 0.25, 0.05, 0.02);
+    let config = SimulationConfig::builder().steps(1).num_trials(100000).seed(60).build().unwrap();
+    let call = OptionContract::<f64>::new(OptionType::Call, 110.0, 0.5);
+    let put = OptionContract::<f64>::new(OptionType::Put, 110.0, 0.5);
+
+    let estimate = digital_price(&call, DigitalPayoff::CashOrNothing(10.0), &market, &config).unwrap();
+    let actual_price = bs::cash_or_nothing_call_price(100.0, 110.0, 10.0, 0.25, 0.05, 0.5, 0.02);
+    println!("mc_simd cash-or-nothing call {:?} vs {}", estimate, actual_price);
+    assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+
+    let estimate = digital_price(&put, DigitalPayoff::AssetOrNothing, &market, &config).unwrap();
+    let actual_price = bs::asset_or_nothing_put_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    println!("mc_simd asset-or-nothing put {:?} vs {}", estimate, actual_price);
+    assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+
+    let estimate = digital_price(&call, DigitalPayoff::Gap(120.0), &market, &config).unwrap();
+    let actual_price = bs::gap_call_price(100.0, 110.0, 120.0, 0.25, 0.05, 0.5, 0.02);
+    println!("mc_simd gap call {:?} vs {}", estimate, actual_price);
+    assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+
+    assert!(matches!(
+        digital_price(&call, DigitalPayoff::CashOrNothing(f64::NAN), &market, &config),
+        Err(PricingError::InvalidPayoffAmount(_))
+    ));
+    assert_eq!(
+        digital_price(&call, DigitalPayoff::Gap(-1.0), &market, &config),
+        Err(PricingError::InvalidStrike(-1.0))
+    );
+}
+
+#[test]
+fn digital_greeks_match_bs() {
+    let market = MarketData::<f64>::new(100.0, 0.25, 0.05, 0.02);
+    let config = SimulationConfig::builder().steps(1).num_trials(400000).seed(61).build().unwrap();
+    let contract = OptionContract::<f64>::new(OptionType::Call, 105.0, 0.5);
+    let payoff = DigitalPayoff::CashOrNothing(10.0);
+    let cash = |spot: f64, volatility: f64, rate: f64, years: f64| {
+        bs::cash_or_nothing_call_price(spot, 105.0, 10.0, volatility, rate, years, 0.02)
+    };
+
+    // Central differences of the closed form, with Vega and Rho per 1% change
+    let h = 1e-4;
+    let actual_greeks = [
+        (Greek::Delta, (cash(100.0 + h, 0.25, 0.05, 0.5) - cash(100.0 - h, 0.25, 0.05, 0.5)) / (2.0 * h)),
+        (
+            Greek::Gamma,
+            (cash(101.0, 0.25, 0.05, 0.5) - 2.0 * cash(100.0, 0.25, 0.05, 0.5) + cash(99.0, 0.25, 0.05, 0.5)),
+        ),
+        (Greek::Vega, (cash(100.0, 0.25 + h, 0.05, 0.5) - cash(100.0, 0.25 - h, 0.05, 0.5)) / (200.0 * h)),
+        (Greek::Rho, (cash(100.0, 0.25, 0.05 + h, 0.5) - cash(100.0, 0.25, 0.05 - h, 0.5)) / (200.0 * h)),
+        (Greek::Theta, (cash(100.0, 0.25, 0.05, 0.5 - h) - cash(100.0, 0.25, 0.05, 0.5 + h)) / (2.0 * h)),
+    ];
+    for (greek, actual_greek) in actual_greeks {
+        let estimate = digital_greek(greek, &contract, payoff, &market, &config).unwrap();
+        let tolerance = 0.03 * actual_greek.abs().max(0.01);
+        println!("mc_simd digital {:?} {} vs {}", greek, estimate, actual_greek);
+        assert!((estimate - actual_greek).abs() < tolerance);
+    }
+}
+
+#[test]
+fn vanilla_payoff_matches_price() {
+    let contract = OptionContract::<f32>::new(OptionType::Call, 110.0, 0.5);
+    let market = MarketData::<f32>::new(100.0, 0.25, 0.05, 0.02);
+    let config = SimulationConfig::builder().steps(20).num_trials(10000).seed(70).build().unwrap();
+    let call = |spot: f32x8| f32x8::fast_max(spot - f32x8::splat(110.0), f32x8::splat(0.0));
+    assert_eq!(price_payoff(&market, 0.5, &config, &call), price(&contract, &market, &config));
+    assert_eq!(
+        payoff_greek(Greek::Delta, &market, 0.5, &config, &call),
+        greek(Greek::Delta, &contract, &market, &config)
+    );
+}
+
+#[test]
+fn call_spread_payoff_matches_bs() {
+    let market = MarketData::<f64>::new(100.0, 0.25, 0.05, 0.02);
+    let config = SimulationConfig::builder().steps(1).num_trials(100000).seed(71).antithetic(true).build().unwrap();
+    let zeros = f64x4::splat(0.0);
+    let call_spread = |spot: f64x4| {
+        f64x4::fast_max(spot - f64x4::splat(100.0), zeros) - f64x4::fast_max(spot - f64x4::splat(110.0), zeros)
+    };
+
+    let estimate = price_payoff(&market, 0.5, &config, &call_spread).unwrap();
+    let actual_price: f64 =
+        bs::call_price(100.0, 100.0, 0.25, 0.05, 0.5, 0.02) - bs::call_price(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    println!("mc_simd call spread {:?} vs {}", estimate, actual_price);
+    assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+
+    let delta = payoff_greek(Greek::Delta, &market, 0.5, &config, &call_spread).unwrap();
+    let actual_delta: f64 =
+        bs::call_delta(100.0, 100.0, 0.25, 0.05, 0.5, 0.02) - bs::call_delta(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    println!("mc_simd call spread delta {} vs {}", delta, actual_delta);
+    assert!((delta - actual_delta).abs() < 0.005);
+
+    let vega = payoff_greek(Greek::Vega, &market, 0.5, &config, &call_spread).unwrap();
+    let actual_vega: f64 =
+        bs::vega(100.0, 100.0, 0.25, 0.05, 0.5, 0.02) - bs::vega(100.0, 110.0, 0.25, 0.05, 0.5, 0.02);
+    println!("mc_simd call spread vega {} vs {}", vega, actual_vega);
+    assert!((vega - actual_vega).abs() < 0.005);
+
+    assert_eq!(
+        payoff_greek(Greek::Theta, &market, 0.0005, &config, &call_spread),
+        Err(PricingError::InvalidBump { greek: Greek::Theta, bump: 0.001 })
+    );
+}
+
+#[test]
+fn path_greeks_match_bs() {
+    // Averaging over a single step leaves a European call
+    let market = MarketData::<f64>::new(100.0, 0.25, 0.05, 0.02);
+    let config = SimulationConfig::builder().steps(1).num_trials(100000).seed(72).build().unwrap();
+    let payoff = RunningAverageCall { strike: f64x4::splat(110.0), steps: 1 };
+    let actual_greeks: [(Greek, f64); 4] = [
+        (Greek::Delta, bs::call_delta(100.0, 110.0, 0.25, 0.05, 0.5, 0.02)),
+        (Greek::Vega, bs::vega(100.0, 110.0, 0.25, 0.05, 0.5, 0.02)),
+        (Greek::Rho, bs::call_rho(100.0, 110.0, 0.25, 0.05, 0.5, 0.02)),
+        (Greek::Theta, bs::call_theta(100.0, 110.0, 0.25, 0.05, 0.5, 0.02)),
+    ];
+    for (greek, actual_greek) in actual_greeks {
+        let estimate = path_greek(greek, &market, 0.5, &config, &payoff).unwrap();
+        println!("mc_simd path {:?} {} vs {}", greek, estimate, actual_greek);
+        assert!((estimate - actual_greek).abs() < 0.02 * actual_greek.abs());
+    }
+}
+
+#[test]
+fn multi_asset_marginals_match_bs() {
+    let market = two_asset_market::<f64>(-0.7);
+    let config = SimulationConfig::builder().steps(5).num_trials(100000).seed(80).build().unwrap();
+    for (asset, strike) in [(0, 110.0), (1, 85.0)] {
+        let call = |spots: &[f64x4]| f64x4::fast_max(spots[asset] - f64x4::splat(strike), f64x4::splat(0.0));
+        let estimate = price_multi_asset(&market, 0.5, &config, &call).unwrap();
+        let asset_market = market.asset(asset);
+        let actual_price = bs::call_price(
+            asset_market.spot,
+            strike,
+            asset_market.volatility,
+            asset_market.risk_free_rate,
+            0.5,
+            asset_market.dividend_yield
+        );
+        println!("multi asset call on asset {} {:?} vs {}", asset, estimate, actual_price);
+        assert_eq!(estimate.num_paths, 100000);
+        assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+    }
+}
+
+#[test]
+fn multi_asset_draws_are_correlated() {
+    // The discounted expectation of S1 S2 / (S1(0) S2(0)) is exp((r - q1 - q2 + rho s1 s2) T)
+    for correlation in [-0.6, 0.0, 0.8] {
+        let market = two_asset_market::<f64>(correlation);
+        let config = SimulationConfig::builder().steps(4).num_trials(100000).seed(81).antithetic(true).build().unwrap();
+        let product = |spots: &[f64x4]| spots[0] * spots[1] * f64x4::splat(1.0 / 9000.0);
+        let estimate = price_multi_asset(&market, 1.0, &config, &product).unwrap();
+        let actual_price: f64 = (0.05 - 0.02 + correlation * 0.25 * 0.3).exp();
+        println!("product with correlation {} {:?} vs {}", correlation, estimate, actual_price);
+        assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+    }
+}
+
+#[test]
+fn f32_multi_asset_is_reproducible() {
+    let market = two_asset_market::<f32>(0.5);
+    let config = SimulationConfig::builder().steps(3).num_trials(1001).seed(82).build().unwrap();
+    let spread = |spots: &[f32x8]| f32x8::fast_max(spots[0] - spots[1], f32x8::splat(0.0));
+    let first = price_multi_asset(&market, 0.5, &config, &spread).unwrap();
+    let second = price_multi_asset(&market, 0.5, &config, &spread).unwrap();
+    assert_eq!(first, second);
+    assert_eq!(first.num_paths, 1001);
+
+    assert_eq!(
+        price_multi_asset(&two_asset_market::<f32>(1.0), 0.5, &config, &spread),
+        Err(PricingError::CorrelationNotPositiveDefinite)
+    );
+    assert_eq!(
+        price_multi_asset(&market, -0.5, &config, &spread),
+        Err(PricingError::InvalidYearsToExpiry(-0.5))
+    );
+}
+
+#[test]
+fn two_asset_rainbow_matches_stulz() {
+    let market = MultiAssetMarket::<f64>::new(
+        vec![100.0, 95.0],
+        vec![0.25, 0.3],
+        vec![0.02, 0.01],
+        0.05,
+        vec![vec![1.0, 0.4], vec![0.4, 1.0]]
+    );
+    let config = SimulationConfig::builder().steps(1).num_trials(100000).seed(83).antithetic(true).build().unwrap();
+    for rainbow_type in [RainbowType::BestOf, RainbowType::WorstOf] {
+        for option_type in [OptionType::Call, OptionType::Put] {
+            let option = RainbowOption { option_type, rainbow_type, strike: 105.0, years_to_expiry: 1.0 };
+            let estimate = rainbow_price(&option, &market, &config).unwrap();
+            let actual_price = bs::rainbow_price(
+                option_type,
+                rainbow_type,
+                100.0,
+                95.0,
+                105.0,
+                0.25,
+                0.3,
+                0.4,
+                0.05,
+                1.0,
+                0.02,
+                0.01
+            );
+            println!("rainbow {:?} {:?} {:?} vs {}", rainbow_type, option_type, estimate, actual_price);
+            assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+        }
+    }
+    // The paths are simulated in one step however many steps are asked for
+    let option = RainbowOption {
+        option_type: OptionType::Call,
+        rainbow_type: RainbowType::BestOf,
+        strike: 105.0,
+        years_to_expiry: 1.0,
+    };
+    assert_eq!(
+        rainbow_price(&option, &market, &SimulationConfig { steps: 50, ..config }),
+        rainbow_price(&option, &market, &config)
+    );
+}
+
+#[test]
+fn rainbow_on_many_assets() {
+    // Worst-of puts on more assets are worth more, and best-of calls too
+    let num_assets = 5;
+    let correlation = (0..num_assets)
+        .map(|i| (0..num_assets).map(|j| if i == j { 1.0 } else { 0.5 }).collect())
+        .collect();
+    let market = MultiAssetMarket::<f32>::new(
+        vec![100.0; num_assets],
+        vec![0.3; num_assets],
+        vec![0.0; num_assets],
+        0.03,
+        correlation
+    );
+    let config = SimulationConfig::builder().steps(1).num_trials(20000).seed(84).build().unwrap();
+    let worst_of_put = RainbowOption {
+        option_type: OptionType::Put,
+        rainbow_type: RainbowType::WorstOf,
+        strike: 100.0,
+        years_to_expiry: 1.0,
+    };
+    let best_of_call = RainbowOption { option_type: OptionType::Call, rainbow_type: RainbowType::BestOf, ..worst_of_put };
+    let put = put_price_estimate_with_rng(100.0, 100.0, 0.3, 0.03, 1.0, 0.0, 1.0, 20000.0, RngSource::Seeded(84));
+    let call = call_price_estimate_with_rng(100.0, 100.0, 0.3, 0.03, 1.0, 0.0, 1.0, 20000.0, RngSource::Seeded(84));
+    let worst_of_put_estimate = rainbow_price(&worst_of_put, &market, &config).unwrap();
+    let best_of_call_estimate = rainbow_price(&best_of_call, &market, &config).unwrap();
+    println!(
+        "5 asset worst-of put {:?} vs {:?}, best-of call {:?} vs {:?}",
+        worst_of_put_estimate,
+        put,
+        best_of_call_estimate,
+        call
+    );
+    assert!(worst_of_put_estimate.price > 1.5 * put.price);
+    assert!(best_of_call_estimate.price > 1.5 * call.price);
+
+    assert_eq!(
+        rainbow_price(&RainbowOption { strike: -1.0, ..best_of_call }, &market, &config),
+        Err(PricingError::InvalidStrike(-1.0))
+    );
+}
+
+#[test]
+fn futures_match_black76() {
+    // The futures price has no drift, whatever the rate
+    let market = MarketData::<f64>::futures(95.0, 0.3, 0.04);
+    let config = SimulationConfig::builder().steps(1).num_trials(100000).seed(86).antithetic(true).build().unwrap();
+    for option_type in [OptionType::Call, OptionType::Put] {
+        let contract = OptionContract::new(option_type, 100.0, 1.5);
+        let estimate = price(&contract, &market, &config).unwrap();
+        let actual_price = match option_type {
+            OptionType::Call => bs::black76_call_price(95.0, 100.0, 0.3, 0.04, 1.5),
+            OptionType::Put => bs::black76_put_price(95.0, 100.0, 0.3, 0.04, 1.5),
+        };
+        println!("mc_simd futures {:?} {:?} vs {}", option_type, estimate, actual_price);
+        assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+    }
+
+    let contract = OptionContract::new(OptionType::Call, 100.0, 1.5);
+    let actual_greeks: [(Greek, f64); 3] = [
+        (Greek::Delta, bs::black76_call_delta(95.0, 100.0, 0.3, 0.04, 1.5)),
+        (Greek::Rho, bs::black76_call_rho(95.0, 100.0, 0.3, 0.04, 1.5)),
+        (Greek::Theta, bs::black76_call_theta(95.0, 100.0, 0.3, 0.04, 1.5)),
+    ];
+    let payoff = RunningAverageCall { strike: f64x4::splat(100.0), steps: 1 };
+    for (greek_type, actual_greek) in actual_greeks {
+        let estimate = greek(greek_type, &contract, &market, &config).unwrap();
+        let path_estimate = path_greek(greek_type, &market, 1.5, &config, &payoff).unwrap();
+        println!("mc_simd futures {:?} {} and {} vs {}", greek_type, estimate, path_estimate, actual_greek);
+        assert!((estimate - actual_greek).abs() < 0.03 * actual_greek.abs());
+        assert!((path_estimate - actual_greek).abs() < 0.03 * actual_greek.abs());
+    }
+
+    // Only the discount factor of a digital on a futures moves with the rate
+    let digital = digital_price(&contract, DigitalPayoff::CashOrNothing(1.0), &market, &config).unwrap();
+    let digital_rho = digital_greek(Greek::Rho, &contract, DigitalPayoff::CashOrNothing(1.0), &market, &config).unwrap();
+    assert!((digital_rho + 0.015 * digital.price).abs() < 1e-12);
+}
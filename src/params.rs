@@ -0,0 +1,752 @@
+// Parameter types shared by the pricers, so that options, market data and simulation
+// settings are passed by name instead of as a long list of positional f32s
+
+use crate::error::PricingError;
+use crate::float::Float;
+use crate::mc_simd::RngSource;
+
+pub(crate) const MIN_STEPS: usize = 1;
+pub(crate) const MIN_TRIALS: usize = 1;
+pub(crate) const MIN_ASSETS: usize = 1;
+
+/// Whether the option is a call or a put
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OptionType {
+    Call,
+    Put,
+}
+
+impl OptionType {
+    // 1.0 for calls and -1.0 for puts, so that the payoff is max(call_mult * (S - K), 0)
+    pub(crate) fn call_mult(&self) -> f64 {
+        match self {
+            OptionType::Call => 1.0,
+            OptionType::Put => -1.0,
+        }
+    }
+}
+
+/// Which side of the spot the barrier of a barrier option is on, and whether hitting it knocks
+/// the option in or out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BarrierType {
+    DownAndIn,
+    DownAndOut,
+    UpAndIn,
+    UpAndOut,
+}
+
+impl BarrierType {
+    pub(crate) fn is_up(&self) -> bool {
+        matches!(self, BarrierType::UpAndIn | BarrierType::UpAndOut)
+    }
+
+    pub(crate) fn is_knock_in(&self) -> bool {
+        matches!(self, BarrierType::DownAndIn | BarrierType::UpAndIn)
+    }
+}
+
+/// Whether a rainbow option is on the best or the worst performing of several assets
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RainbowType {
+    BestOf,
+    WorstOf,
+}
+
+/// When a path-dependent option looks at the spot, e.g. to check a barrier or to track the
+/// highest spot
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Monitoring {
+    /// At every moment until expiry
+    Continuous,
+    /// At the end of every step of the simulation
+    Discrete,
+}
+
+/// What the spot of a [`MarketData`] is the price of
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Underlying {
+    /// An asset that grows at the risk-free rate less its dividend yield
+    Spot,
+    /// A futures or forward price, which has no drift, as in Black's 1976 model. The dividend
+    /// yield is not used.
+    Futures,
+}
+
+/// The Greeks that can be calculated with the finite difference method
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Greek {
+    Delta,
+    Gamma,
+    Vega,
+    Rho,
+    Theta,
+}
+
+/// The terms of a European option. `F` is the precision it is priced at, f32 or f64.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OptionContract<F: Float = f32> {
+    pub option_type: OptionType,
+    pub strike: F,
+    pub years_to_expiry: F,
+}
+
+impl<F: Float> OptionContract<F> {
+    pub fn new(option_type: OptionType, strike: F, years_to_expiry: F) -> OptionContract<F> {
+        OptionContract { option_type, strike, years_to_expiry }
+    }
+
+    pub fn builder() -> OptionContractBuilder<F> {
+        OptionContractBuilder::default()
+    }
+
+    pub fn validate(&self) -> Result<(), PricingError> {
+        if !(self.strike >= F::from_f64(0.0) && self.strike.is_finite()) {
+            return Err(PricingError::InvalidStrike(self.strike.to_f64()));
+        }
+        if !(self.years_to_expiry > F::from_f64(0.0) && self.years_to_expiry.is_finite()) {
+            return Err(PricingError::InvalidYearsToExpiry(self.years_to_expiry.to_f64()));
+        }
+        Ok(())
+    }
+}
+
+/// Builds an [`OptionContract`]. The strike and the time to expiry are required, the option
+/// type defaults to a call.
+#[derive(Clone, Copy, Debug)]
+pub struct OptionContractBuilder<F: Float = f32> {
+    option_type: Option<OptionType>,
+    strike: Option<F>,
+    years_to_expiry: Option<F>,
+}
+
+impl<F: Float> Default for OptionContractBuilder<F> {
+    fn default() -> OptionContractBuilder<F> {
+        OptionContractBuilder { option_type: None, strike: None, years_to_expiry: None }
+    }
+}
+
+impl<F: Float> OptionContractBuilder<F> {
+    pub fn option_type(mut self, option_type: OptionType) -> OptionContractBuilder<F> {
+        self.option_type = Some(option_type);
+        self
+    }
+
+    pub fn call(self) -> OptionContractBuilder<F> {
+        self.option_type(OptionType::Call)
+    }
+
+    pub fn put(self) -> OptionContractBuilder<F> {
+        self.option_type(OptionType::Put)
+    }
+
+    pub fn strike(mut self, strike: F) -> OptionContractBuilder<F> {
+        self.strike = Some(strike);
+        self
+    }
+
+    pub fn years_to_expiry(mut self, years_to_expiry: F) -> OptionContractBuilder<F> {
+        self.years_to_expiry = Some(years_to_expiry);
+        self
+    }
+
+    pub fn build(self) -> Result<OptionContract<F>, PricingError> {
+        let contract = OptionContract {
+            option_type: self.option_type.unwrap_or(OptionType::Call),
+            strike: self.strike.ok_or(PricingError::MissingParameter("strike"))?,
+            years_to_expiry: self.years_to_expiry.ok_or(
+                PricingError::MissingParameter("years_to_expiry")
+            )?,
+        };
+        contract.validate()?;
+        Ok(contract)
+    }
+}
+
+/// The state of the market the option is priced in, at precision `F`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MarketData<F: Float = f32> {
+    pub spot: F,
+    pub volatility: F,
+    pub risk_free_rate: F,
+    pub dividend_yield: F,
+    pub underlying: Underlying,
+}
+
+impl<F: Float> MarketData<F> {
+    pub fn new(spot: F, volatility: F, risk_free_rate: F, dividend_yield: F) -> MarketData<F> {
+        MarketData { spot, volatility, risk_free_rate, dividend_yield, underlying: Underlying::Spot }
+    }
+
+    /// The market of a futures price, which the pricers simulate without drift
+    pub fn futures(futures_price: F, volatility: F, risk_free_rate: F) -> MarketData<F> {
+        MarketData {
+            spot: futures_price,
+            volatility,
+            risk_free_rate,
+            dividend_yield: F::from_f64(0.0),
+            underlying: Underlying::Futures,
+        }
+    }
+
+    // The yield q in the risk-neutral drift r - q of the underlying: the dividend yield of a
+    // spot, and the risk-free rate for a futures, whose drift is zero
+    pub(crate) fn carry_yield(&self) -> F {
+        match self.underlying {
+            Underlying::Spot => self.dividend_yield,
+            Underlying::Futures => self.risk_free_rate,
+        }
+    }
+
+    pub fn builder() -> MarketDataBuilder<F> {
+        MarketDataBuilder::default()
+    }
+
+    pub fn validate(&self) -> Result<(), PricingError> {
+        if !(self.spot > F::from_f64(0.0) && self.spot.is_finite()) {
+            return Err(PricingError::InvalidSpot(self.spot.to_f64()));
+        }
+        if !(self.volatility > F::from_f64(0.0) && self.volatility.is_finite()) {
+            return Err(PricingError::InvalidVolatility(self.volatility.to_f64()));
+        }
+        if !self.risk_free_rate.is_finite() {
+            return Err(PricingError::InvalidRiskFreeRate(self.risk_free_rate.to_f64()));
+        }
+        if !self.dividend_yield.is_finite() {
+            return Err(PricingError::InvalidDividendYield(self.dividend_yield.to_f64()));
+        }
+        Ok(())
+    }
+}
+
+/// Builds a [`MarketData`]. The spot and the volatility are required, the risk-free rate and
+/// the dividend yield default to 0, and the underlying to a spot.
+#[derive(Clone, Copy, Debug)]
+pub struct MarketDataBuilder<F: Float = f32> {
+    spot: Option<F>,
+    volatility: Option<F>,
+    risk_free_rate: Option<F>,
+    dividend_yield: Option<F>,
+    underlying: Underlying,
+}
+
+impl<F: Float> Default for MarketDataBuilder<F> {
+    fn default() -> MarketDataBuilder<F> {
+        MarketDataBuilder {
+            spot: None,
+            volatility: None,
+            risk_free_rate: None,
+            dividend_yield: None,
+            underlying: Underlying::Spot,
+        }
+    }
+}
+
+impl<F: Float> MarketDataBuilder<F> {
+    pub fn spot(mut self, spot: F) -> MarketDataBuilder<F> {
+        self.spot = Some(spot);
+        self
+    }
+
+    pub fn volatility(mut self, volatility: F) -> MarketDataBuilder<F> {
+        self.volatility = Some(volatility);
+        self
+    }
+
+    pub fn risk_free_rate(mut self, risk_free_rate: F) -> MarketDataBuilder<F> {
+        self.risk_free_rate = Some(risk_free_rate);
+        self
+    }
+
+    pub fn dividend_yield(mut self, dividend_yield: F) -> MarketDataBuilder<F> {
+        self.dividend_yield = Some(dividend_yield);
+        self
+    }
+
+    /// The spot is a futures price, with no drift
+    pub fn futures(mut self) -> MarketDataBuilder<F> {
+        self.underlying = Underlying::Futures;
+        self
+    }
+
+    pub fn build(self) -> Result<MarketData<F>, PricingError> {
+        let market = MarketData {
+            spot: self.spot.ok_or(PricingError::MissingParameter("spot"))?,
+            volatility: self.volatility.ok_or(PricingError::MissingParameter("volatility"))?,
+            risk_free_rate: self.risk_free_rate.unwrap_or(F::from_f64(0.0)),
+            dividend_yield: self.dividend_yield.unwrap_or(F::from_f64(0.0)),
+            underlying: self.underlying,
+        };
+        market.validate()?;
+        Ok(market)
+    }
+}
+
+/// The state of the market for options on several correlated underlyings, at precision `F`.
+/// Asset `i` has spot `spots[i]`, volatility `volatilities[i]` and dividend yield
+/// `dividend_yields[i]`, and `correlation[i][j]` is the correlation of the Brownian motions
+/// driving assets `i` and `j`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultiAssetMarket<F: Float = f32> {
+    pub spots: Vec<F>,
+    pub volatilities: Vec<F>,
+    pub dividend_yields: Vec<F>,
+    pub risk_free_rate: F,
+    pub correlation: Vec<Vec<F>>,
+}
+
+impl<F: Float> MultiAssetMarket<F> {
+    pub fn new(
+        spots: Vec<F>,
+        volatilities: Vec<F>,
+        dividend_yields: Vec<F>,
+        risk_free_rate: F,
+        correlation: Vec<Vec<F>>
+    ) -> MultiAssetMarket<F> {
+        MultiAssetMarket { spots, volatilities, dividend_yields, risk_free_rate, correlation }
+    }
+
+    pub fn num_assets(&self) -> usize {
+        self.spots.len()
+    }
+
+    /// The market of asset `asset` on its own
+    pub fn asset(&self, asset: usize) -> MarketData<F> {
+        MarketData::new(
+            self.spots[asset],
+            self.volatilities[asset],
+            self.risk_free_rate,
+            self.dividend_yields[asset]
+        )
+    }
+
+    /// Checks every asset as a [`MarketData`], and that the correlation matrix is a symmetric
+    /// positive-definite matrix with ones on the diagonal
+    pub fn validate(&self) -> Result<(), PricingError> {
+        let num_assets = self.num_assets();
+        if num_assets < MIN_ASSETS {
+            return Err(PricingError::TooFewAssets { num_assets, min_assets: MIN_ASSETS });
+        }
+        for actual in [self.volatilities.len(), self.dividend_yields.len(), self.correlation.len()] {
+            if actual != num_assets {
+                return Err(PricingError::AssetCountMismatch { expected: num_assets, actual });
+            }
+        }
+        for asset in 0..num_assets {
+            self.asset(asset).validate()?;
+        }
+        for (row, correlations) in self.correlation.iter().enumerate() {
+            if correlations.len() != num_assets {
+                return Err(PricingError::AssetCountMismatch {
+                    expected: num_assets,
+                    actual: correlations.len(),
+                });
+            }
+            for (column, &correlation) in correlations.iter().enumerate() {
+                let correlation = correlation.to_f64();
+                let valid = if row == column {
+                    correlation == 1.0
+                } else {
+                    correlation.abs() <= 1.0 && correlation == self.correlation[column][row].to_f64()
+                };
+                if !valid {
+                    return Err(PricingError::InvalidCorrelation { row, column, correlation });
+                }
+            }
+        }
+        self.correlation_factor().map(|_| ())
+    }
+
+    // The Cholesky factor L of the correlation matrix, with L L^T = correlation, as the rows
+    // of the lower triangle one after the other. Correlating independent normals with L gives
+    // normals with the correlation matrix. A pivot that is not clearly positive means the
+    // matrix is not positive-definite, e.g. because a correlation of 1 makes two assets move
+    // as one.
+    pub(crate) fn correlation_factor(&self) -> Result<Vec<f64>, PricingError> {
+        let num_assets = self.num_assets();
+        let mut factor = vec![0.0; (num_assets * (num_assets + 1)) / 2];
+        let row_start = |row: usize| (row * (row + 1)) / 2;
+
+        for row in 0..num_assets {
+            for column in 0..=row {
+                let dot: f64 = (0..column)
+                    .map(|k| factor[row_start(row) + k] * factor[row_start(column) + k])
+                    .sum();
+                let value = self.correlation[row][column].to_f64() - dot;
+                if row == column {
+                    if !(value > 1e-12) {
+                        return Err(PricingError::CorrelationNotPositiveDefinite);
+                    }
+                    factor[row_start(row) + column] = value.sqrt();
+                } else {
+                    factor[row_start(row) + column] = value / factor[row_start(column) + column];
+                }
+            }
+        }
+        Ok(factor)
+    }
+}
+
+/// Sizes of the bumps used by the finite difference Greeks, in f64 whatever the precision of
+/// the pricing
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GreekBumps {
+    pub spot: f64,
+    pub volatility: f64,
+    pub risk_free_rate: f64,
+    pub years_to_expiry: f64,
+}
+
+impl GreekBumps {
+    // Checks that the bump used for greek is positive and leaves the bumped parameters valid
+    pub(crate) fn validate<F: Float>(
+        &self,
+        greek: Greek,
+        years_to_expiry: F,
+        market: &MarketData<F>
+    ) -> Result<(), PricingError> {
+        let (bump, limit) = match greek {
+            Greek::Delta | Greek::Gamma => (self.spot, market.spot.to_f64()),
+            Greek::Vega => (self.volatility, market.volatility.to_f64()),
+            Greek::Rho => (self.risk_free_rate, f64::INFINITY),
+            Greek::Theta => (self.years_to_expiry, years_to_expiry.to_f64()),
+        };
+        if !(bump > 0.0 && bump < limit) {
+            return Err(PricingError::InvalidBump { greek, bump });
+        }
+        Ok(())
+    }
+}
+
+impl Default for GreekBumps {
+    fn default() -> GreekBumps {
+        GreekBumps {
+            spot: 0.01,
+            volatility: 0.01,
+            risk_free_rate: 0.01,
+            years_to_expiry: 0.001,
+        }
+    }
+}
+
+/// How the Monte Carlo simulation is run
+#[derive(Clone, Copy, Debug)]
+pub struct SimulationConfig {
+    pub steps: usize,
+    pub num_trials: usize,
+    /// Use the antithetic variate method to reduce the variance of the price
+    pub antithetic: bool,
+    pub rng_source: RngSource,
+    pub bumps: GreekBumps,
+}
+
+impl SimulationConfig {
+    pub fn new(steps: usize, num_trials: usize) -> SimulationConfig {
+        SimulationConfig {
+            steps,
+            num_trials,
+            antithetic: false,
+            rng_source: RngSource::Entropy,
+            bumps: GreekBumps::default(),
+        }
+    }
+
+    pub fn builder() -> SimulationConfigBuilder {
+        SimulationConfigBuilder { config: SimulationConfig::default() }
+    }
+
+    pub fn validate(&self) -> Result<(), PricingError> {
+        if self.steps < MIN_STEPS {
+            return Err(PricingError::TooFewSteps { steps: self.steps, min_steps: MIN_STEPS });
+        }
+        if self.num_trials < MIN_TRIALS {
+            return Err(PricingError::TooFewTrials {
+                num_trials: self.num_trials,
+                min_trials: MIN_TRIALS,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for SimulationConfig {
+    /// 100 steps and 10000 trials, without antithetic variates and with unseeded RNGs
+    fn default() -> SimulationConfig {
+        SimulationConfig::new(100, 10000)
+    }
+}
+
+/// Builds a [`SimulationConfig`], starting from [`SimulationConfig::default`]
+#[derive(Clone, Copy, Debug)]
+pub struct SimulationConfigBuilder {
+    config: SimulationConfig,
+}
+
+impl SimulationConfigBuilder {
+    pub fn steps(mut self, steps: usize) -> SimulationConfigBuilder {
+        self.config.steps = steps;
+        self
+    }
+
+    pub fn num_trials(mut self, num_trials: usize) -> SimulationConfigBuilder {
+        self.config.num_trials = num_trials;
+        self
+    }
+
+    pub fn antithetic(mut self, antithetic: bool) -> SimulationConfigBuilder {
+        self.config.antithetic = antithetic;
+        self
+    }
+
+    pub fn rng_source(mut self, rng_source: RngSource) -> SimulationConfigBuilder {
+        self.config.rng_source = rng_source;
+        self
+    }
+
+    /// Shorthand for `rng_source(RngSource::Seeded(seed))`
+    pub fn seed(self, seed: u64) -> SimulationConfigBuilder {
+        self.rng_source(RngSource::Seeded(seed))
+    }
+
+    pub fn spot_bump(mut self, spot_bump: f64) -> SimulationConfigBuilder {
+        self.config.bumps.spot = spot_bump;
+        self
+    }
+
+    pub fn volatility_bump(mut self, volatility_bump: f64) -> SimulationConfigBuilder {
+        self.config.bumps.volatility = volatility_bump;
+        self
+    }
+
+    pub fn risk_free_rate_bump(mut self, risk_free_rate_bump: f64) -> SimulationConfigBuilder {
+        self.config.bumps.risk_free_rate = risk_free_rate_bump;
+        self
+    }
+
+    pub fn years_to_expiry_bump(mut self, years_to_expiry_bump: f64) -> SimulationConfigBuilder {
+        self.config.bumps.years_to_expiry = years_to_expiry_bump;
+        self
+    }
+
+    pub fn build(self) -> Result<SimulationConfig, PricingError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+#[test]
+fn builders_match_constructors() {
+    let contract = OptionContract::builder().put().strike(110.0).years_to_expiry(0.5).build();
+    assert_eq!(contract, Ok(OptionContract::new(OptionType::Put, 110.0, 0.5)));
+
+    let market = MarketData::builder().spot(100.0).volatility(0.25).risk_free_rate(0.05).build();
+    assert_eq!(market, Ok(MarketData::new(100.0, 0.25, 0.05, 0.0)));
+    let futures = MarketData::builder().spot(100.0).volatility(0.25).risk_free_rate(0.05).futures().build();
+    assert_eq!(futures, Ok(MarketData::futures(100.0, 0.25, 0.05)));
+
+    let config = SimulationConfig::builder()
+        .steps(50)
+        .num_trials(1000)
+        .spot_bump(0.1)
+        .build()
+        .unwrap();
+    assert_eq!((config.steps, config.num_trials, config.antithetic), (50, 1000, false));
+    assert_eq!(config.bumps.spot, 0.1);
+    assert_eq!(config.bumps.volatility, GreekBumps::default().volatility);
+}
+
+#[test]
+fn builders_require_parameters() {
+    assert_eq!(
+        OptionContract::builder().call().years_to_expiry(0.5).build(),
+        Err(PricingError::MissingParameter("strike"))
+    );
+    assert_eq!(
+        OptionContract::builder().strike(110.0).build(),
+        Err(PricingError::MissingParameter("years_to_expiry"))
+    );
+    assert_eq!(
+        MarketData::builder().volatility(0.25).build(),
+        Err(PricingError::MissingParameter("spot"))
+    );
+    assert_eq!(
+        MarketData::builder().spot(100.0).build(),
+        Err(PricingError::MissingParameter("volatility"))
+    );
+}
+
+#[test]
+fn invalid_contract() {
+    assert_eq!(
+        OptionContract::new(OptionType::Call, -1.0, 0.5).validate(),
+        Err(PricingError::InvalidStrike(-1.0))
+    );
+    assert!(matches!(
+        OptionContract::new(OptionType::Call, f32::NAN, 0.5).validate(),
+        Err(PricingError::InvalidStrike(_))
+    ));
+    assert_eq!(
+        OptionContract::new(OptionType::Call, 110.0, 0.0).validate(),
+        Err(PricingError::InvalidYearsToExpiry(0.0))
+    );
+    assert_eq!(
+        OptionContract::builder().strike(110.0).years_to_expiry(-0.5).build(),
+        Err(PricingError::InvalidYearsToExpiry(-0.5))
+    );
+    assert_eq!(OptionContract::new(OptionType::Put, 0.0, 0.5).validate(), Ok(()));
+}
+
+#[test]
+fn invalid_market() {
+    assert_eq!(
+        MarketData::new(-100.0, 0.25, 0.05, 0.02).validate(),
+        Err(PricingError::InvalidSpot(-100.0))
+    );
+    assert_eq!(
+        MarketData::new(0.0, 0.25, 0.05, 0.02).validate(),
+        Err(PricingError::InvalidSpot(0.0))
+    );
+    assert_eq!(
+        MarketData::new(100.0, 0.0, 0.05, 0.02).validate(),
+        Err(PricingError::InvalidVolatility(0.0))
+    );
+    assert_eq!(
+        MarketData::builder().spot(100.0).volatility(-0.25).build(),
+        Err(PricingError::InvalidVolatility(-0.25))
+    );
+    assert_eq!(
+        MarketData::new(100.0, 0.25, f32::INFINITY, 0.02).validate(),
+        Err(PricingError::InvalidRiskFreeRate(f64::INFINITY))
+    );
+    assert!(matches!(
+        MarketData::new(100.0, 0.25, 0.05, f32::NAN).validate(),
+        Err(PricingError::InvalidDividendYield(_))
+    ));
+    // Negative rates and dividend yields are allowed
+    assert_eq!(MarketData::new(100.0, 0.25, -0.01, -0.02).validate(), Ok(()));
+}
+
+// Two assets with different spots, volatilities and dividend yields, shared by the tests of
+// the multi-asset pricers
+#[cfg(test)]
+pub(crate) fn two_asset_market<F: Float>(correlation: f64) -> MultiAssetMarket<F> {
+    MultiAssetMarket::new(
+        vec![F::from_f64(100.0), F::from_f64(90.0)],
+        vec![F::from_f64(0.25), F::from_f64(0.3)],
+        vec![F::from_f64(0.02), F::from_f64(0.0)],
+        F::from_f64(0.05),
+        vec![
+            vec![F::from_f64(1.0), F::from_f64(correlation)],
+            vec![F::from_f64(correlation), F::from_f64(1.0)]
+        ]
+    )
+}
+
+#[test]
+fn correlation_factor_is_cholesky() {
+    let factor = two_asset_market::<f64>(0.6).correlation_factor().unwrap();
+    for (value, expected) in factor.iter().zip([1.0, 0.6, 0.8]) {
+        assert!((value - expected).abs() < 1e-12);
+    }
+
+    let market = MultiAssetMarket::new(
+        vec![100.0; 3],
+        vec![0.2; 3],
+        vec![0.0; 3],
+        0.05,
+        vec![vec![1.0, 0.5, 0.2], vec![0.5, 1.0, -0.3], vec![0.2, -0.3, 1.0]]
+    );
+    let factor = market.correlation_factor().unwrap();
+    let row_start = |row: usize| (row * (row + 1)) / 2;
+    for row in 0..3 {
+        for column in 0..=row {
+            let product: f64 = (0..=column)
+                .map(|k| factor[row_start(row) + k] * factor[row_start(column) + k])
+                .sum();
+            assert!((product - market.correlation[row][column]).abs() < 1e-12);
+        }
+    }
+}
+
+#[test]
+fn invalid_multi_asset_market() {
+    assert_eq!(two_asset_market::<f64>(0.6).validate(), Ok(()));
+    assert_eq!(
+        two_asset_market::<f64>(1.0).validate(),
+        Err(PricingError::CorrelationNotPositiveDefinite)
+    );
+    assert_eq!(
+        two_asset_market::<f64>(1.5).validate(),
+        Err(PricingError::InvalidCorrelation { row: 0, column: 1, correlation: 1.5 })
+    );
+    // Pairwise valid correlations that no three assets can have together
+    let market = MultiAssetMarket::new(
+        vec![100.0; 3],
+        vec![0.2; 3],
+        vec![0.0; 3],
+        0.05,
+        vec![vec![1.0, 0.9, -0.9], vec![0.9, 1.0, 0.9], vec![-0.9, 0.9, 1.0]]
+    );
+    assert_eq!(market.validate(), Err(PricingError::CorrelationNotPositiveDefinite));
+
+    let mut asymmetric = two_asset_market::<f64>(0.6);
+    asymmetric.correlation[1][0] = 0.5;
+    assert_eq!(
+        asymmetric.validate(),
+        Err(PricingError::InvalidCorrelation { row: 0, column: 1, correlation: 0.6 })
+    );
+    let mut missing_volatility = two_asset_market::<f64>(0.6);
+    missing_volatility.volatilities.pop();
+    assert_eq!(
+        missing_volatility.validate(),
+        Err(PricingError::AssetCountMismatch { expected: 2, actual: 1 })
+    );
+    let mut negative_spot = two_asset_market::<f64>(0.6);
+    negative_spot.spots[1] = -90.0;
+    assert_eq!(negative_spot.validate(), Err(PricingError::InvalidSpot(-90.0)));
+    assert_eq!(
+        MultiAssetMarket::<f64>::new(vec![], vec![], vec![], 0.05, vec![]).validate(),
+        Err(PricingError::TooFewAssets { num_assets: 0, min_assets: MIN_ASSETS })
+    );
+}
+
+#[test]
+fn invalid_config() {
+    assert_eq!(
+        SimulationConfig::builder().steps(0).build().map(|config| config.steps),
+        Err(PricingError::TooFewSteps { steps: 0, min_steps: MIN_STEPS })
+    );
+    assert_eq!(
+        SimulationConfig::builder().num_trials(0).build().map(|config| config.num_trials),
+        Err(PricingError::TooFewTrials { num_trials: 0, min_trials: MIN_TRIALS })
+    );
+    assert!(SimulationConfig::new(1, 1).validate().is_ok());
+}
+
+#[test]
+fn invalid_bumps() {
+    let contract = OptionContract::new(OptionType::Call, 110.0, 0.5);
+    let market = MarketData::new(100.0, 0.25, 0.05, 0.02);
+    let bumps = GreekBumps {
+        spot: 0.0,
+        volatility: 0.5,
+        risk_free_rate: -0.01,
+        years_to_expiry: 0.5,
+    };
+    for greek in [Greek::Delta, Greek::Gamma, Greek::Vega, Greek::Rho, Greek::Theta] {
+        assert!(matches!(
+            bumps.validate(greek, contract.years_to_expiry, &market),
+            Err(PricingError::InvalidBump { greek: g, .. }) if g == greek
+        ));
+        assert_eq!(GreekBumps::default().validate(greek, contract.years_to_expiry, &market), Ok(()));
+    }
+}
+
+#[test]
+fn errors_are_descriptive() {
+    assert_eq!(
+        PricingError::TooFewTrials { num_trials: 4, min_trials: 8 }.to_string(),
+        "at least 8 trials are required, got 4"
+    );
+    assert_eq!(PricingError::InvalidSpot(-1.0).to_string(), "spot must be positive, got -1");
+}
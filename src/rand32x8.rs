@@ -1,6 +1,8 @@
 use wide::*;
 use simd_rand::portable::*;
 use rand_core::{RngCore, SeedableRng};
+use std::cell::Cell;
+use std::sync::OnceLock;
 
 pub fn get_rand_uniform_f32x8(rng: &mut Xoshiro256PlusPlusX8) -> f32x8 {
     let rand_f64x8: [f64; 8] = rng.next_f64x8().to_array();
@@ -20,10 +22,195 @@ pub fn get_rand_uniform_f32x8(rng: &mut Xoshiro256PlusPlusX8) -> f32x8 {
     random_mult
 }
 
-fn test_uniform_distribution(samples: usize, diff_limit: f32) {
+// Splits one draw of 8 f64s into two f64x4, for simulations running on f64x4 lanes
+pub fn get_rand_uniform_f64x4_pair(rng: &mut Xoshiro256PlusPlusX8) -> (f64x4, f64x4) {
+    let rand_f64x8: [f64; 8] = rng.next_f64x8().to_array();
+
+    (
+        f64x4::from([rand_f64x8[0], rand_f64x8[1], rand_f64x8[2], rand_f64x8[3]]),
+        f64x4::from([rand_f64x8[4], rand_f64x8[5], rand_f64x8[6], rand_f64x8[7]]),
+    )
+}
+
+// Seeds a SIMD RNG from the thread-local RNG, so every call gets a fresh, unrelated stream
+pub fn thread_seeded_rng() -> Xoshiro256PlusPlusX8 {
     let mut seed: Xoshiro256PlusPlusX8Seed = Default::default();
     rand::thread_rng().fill_bytes(&mut *seed);
-    let mut rng: Xoshiro256PlusPlusX8 = Xoshiro256PlusPlusX8::from_seed(seed);
+    Xoshiro256PlusPlusX8::from_seed(seed)
+}
+
+// SplitMix64 output function, a bijective 64-bit mixer
+#[inline(always)]
+fn mix_64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[inline(always)]
+fn split_mix_64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    mix_64(*state)
+}
+
+// The characteristic polynomial of the xoshiro256 state transition over GF(2), without its
+// x^256 term. Jumping n steps ahead is multiplying the state by x^n mod this polynomial.
+const CHARACTERISTIC_POLYNOMIAL: [u64; 4] = [
+    0x9d116f2bb0f0f001,
+    0x0280002bcefd1a5e,
+    0x04b4edcf26259f85,
+    0x0003c03c3f3ecb19,
+];
+
+// The lanes of a seeded generator start 2^125 steps apart, so the 8 lanes of a stream span
+// 2^128 steps. The streams of a master seed follow each other, as do the master seeds, which
+// tiles the whole 2^256 - 1 period.
+const LANE_SPACING_LOG2: usize = 125;
+const STREAM_BIT: usize = 3;
+const SEED_BIT: usize = STREAM_BIT + 64;
+
+// x^(2^(125 + k)) mod the characteristic polynomial for every bit k of the index of a lane, the
+// jump polynomials by 2^(125 + k) steps. The xoshiro authors' jump() is k = 3 and long_jump()
+// is k = 67.
+fn jump_polynomials() -> &'static [[u64; 4]; SEED_BIT + 64] {
+    static POLYNOMIALS: OnceLock<[[u64; 4]; SEED_BIT + 64]> = OnceLock::new();
+    POLYNOMIALS.get_or_init(|| {
+        // x^(2^125), by squaring x
+        let mut polynomial = [2, 0, 0, 0];
+        for _ in 0..LANE_SPACING_LOG2 {
+            polynomial = square_mod(&polynomial);
+        }
+        let mut polynomials = [[0; 4]; SEED_BIT + 64];
+        for jump in polynomials.iter_mut() {
+            *jump = polynomial;
+            polynomial = square_mod(&polynomial);
+        }
+        polynomials
+    })
+}
+
+// The square of a polynomial of degree below 256 over GF(2), mod the characteristic polynomial
+fn square_mod(polynomial: &[u64; 4]) -> [u64; 4] {
+    // Squaring over GF(2) moves the coefficient of x^i to x^(2i)
+    let mut square = [0u64; 8];
+    for bit in 0..256 {
+        if (polynomial[bit / 64] >> (bit % 64)) & 1 == 1 {
+            square[bit / 32] |= 1 << ((2 * bit) % 64);
+        }
+    }
+    // x^i = x^(i - 256) (characteristic polynomial - x^256), from the top down
+    for bit in (256..512).rev() {
+        if (square[bit / 64] >> (bit % 64)) & 1 == 1 {
+            square[bit / 64] ^= 1 << (bit % 64);
+            let (word, shift) = ((bit - 256) / 64, (bit - 256) % 64);
+            for (i, &coefficients) in CHARACTERISTIC_POLYNOMIAL.iter().enumerate() {
+                square[word + i] ^= coefficients << shift;
+                if shift > 0 && word + i + 1 < 8 {
+                    square[word + i + 1] ^= coefficients >> (64 - shift);
+                }
+            }
+        }
+    }
+    [square[0], square[1], square[2], square[3]]
+}
+
+// One step of the xoshiro256 states of N lanes, laid out word by word as in
+// Xoshiro256PlusPlusX8Seed
+#[inline(always)]
+fn step_lanes<const N: usize>([s0, s1, s2, s3]: &mut [[u64; N]; 4]) {
+    let words = s0.iter_mut().zip(s1.iter_mut()).zip(s2.iter_mut()).zip(s3.iter_mut());
+    for (((s0, s1), s2), s3) in words {
+        let t = *s1 << 17;
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+    }
+}
+
+// Moves the states of N lanes ahead by the steps of a jump polynomial, as in jump()
+fn jump_lanes<const N: usize>(state: &mut [[u64; N]; 4], polynomial: &[u64; 4]) {
+    let mut jumped = [[0; N]; 4];
+    for bit in 0..256 {
+        if (polynomial[bit / 64] >> (bit % 64)) & 1 == 1 {
+            for (jumped_word, word) in jumped.iter_mut().zip(state.iter()) {
+                for (jumped_lane, lane) in jumped_word.iter_mut().zip(word) {
+                    *jumped_lane ^= lane;
+                }
+            }
+        }
+        step_lanes(state);
+    }
+    *state = jumped;
+}
+
+// The states of the 8 lanes of stream 0 of a master seed. Every pricing call seeds all of its
+// streams from the same master seed, so the last one is kept for each thread.
+fn seed_lanes(master_seed: u64) -> [[u64; 8]; 4] {
+    thread_local! {
+        static LAST_SEED: Cell<Option<(u64, [[u64; 8]; 4])>> = const { Cell::new(None) };
+    }
+    if let Some((seed, lanes)) = LAST_SEED.get() {
+        if seed == master_seed {
+            return lanes;
+        }
+    }
+
+    let polynomials = jump_polynomials();
+    // Lane 0 of stream 0 of master seed 0 is an arbitrary nonzero state
+    let mut origin = 0;
+    let mut lane = [[0; 1]; 4];
+    for word in lane.iter_mut() {
+        word[0] = split_mix_64(&mut origin);
+    }
+    let mut lanes = [[0; 8]; 4];
+    for i in 0..8 {
+        for (word, lane_word) in lanes.iter_mut().zip(lane.iter()) {
+            word[i] = lane_word[0];
+        }
+        jump_lanes(&mut lane, &polynomials[0]);
+    }
+    for bit in 0..64 {
+        if (master_seed >> bit) & 1 == 1 {
+            jump_lanes(&mut lanes, &polynomials[SEED_BIT + bit]);
+        }
+    }
+    LAST_SEED.set(Some((master_seed, lanes)));
+    lanes
+}
+
+// The xoshiro256 states of the 8 lanes of one stream of a seeded simulation. Lane i of stream s
+// of master seed m is (2^67 m + 8 s + i) 2^125 steps past a fixed origin, so every lane has
+// 2^125 draws to itself and no two lanes of any streams or seeds overlap.
+fn seeded_lanes(master_seed: u64, stream: u64) -> [[u64; 8]; 4] {
+    let polynomials = jump_polynomials();
+    let mut lanes = seed_lanes(master_seed);
+    for bit in 0..64 {
+        if (stream >> bit) & 1 == 1 {
+            jump_lanes(&mut lanes, &polynomials[STREAM_BIT + bit]);
+        }
+    }
+    lanes
+}
+
+// Seeds the SIMD RNG for one stream of a seeded simulation, from the lanes of seeded_lanes
+// jumped to from one origin, as the xoshiro authors recommend for parallel streams. Jumping
+// costs a few hundred steps for every set bit of the stream index, so a simulation should
+// seed a stream for a block of batches rather than for every batch.
+pub fn seeded_rng(master_seed: u64, stream: u64) -> Xoshiro256PlusPlusX8 {
+    let lanes = seeded_lanes(master_seed, stream);
+
+    let mut seed: Xoshiro256PlusPlusX8Seed = Default::default();
+    for (chunk, value) in seed.chunks_exact_mut(8).zip(lanes.iter().flatten()) {
+        chunk.copy_from_slice(&value.to_le_bytes());
+    }
+    Xoshiro256PlusPlusX8::from_seed(seed)
+}
+
+fn test_uniform_distribution(samples: usize, diff_limit: f32) {
+    let mut rng: Xoshiro256PlusPlusX8 = thread_seeded_rng();
 
     let mut dist: Vec<f32> = Vec::with_capacity(samples);
 
@@ -78,4 +265,76 @@ fn test_uniform_distribution_1000() {
 #[test]
 fn test_uniform_distribution_100000() {
     test_uniform_distribution(100000, 0.01);
-}
\ No newline at end of file
+}
+
+#[test]
+fn seeded_rng_is_reproducible() {
+    let first = seeded_rng(42, 3).next_u64x8().to_array();
+    let second = seeded_rng(42, 3).next_u64x8().to_array();
+    assert_eq!(first, second);
+}
+
+#[test]
+fn seeded_rng_streams_differ() {
+    let stream_0 = seeded_rng(42, 0).next_u64x8().to_array();
+    let stream_1 = seeded_rng(42, 1).next_u64x8().to_array();
+    let other_seed = seeded_rng(43, 0).next_u64x8().to_array();
+    assert_ne!(stream_0, stream_1);
+    assert_ne!(stream_0, other_seed);
+}
+
+#[test]
+fn jump_polynomials_match_jump_and_long_jump() {
+    // The JUMP and LONG_JUMP constants of the xoshiro256++ reference implementation
+    let polynomials = jump_polynomials();
+    assert_eq!(polynomials[STREAM_BIT], [0x180ec6d33cfd0aba, 0xd5a61266f0c9392c, 0xa9582618e03fc9aa, 0x39abdc4529b1661c]);
+    assert_eq!(polynomials[SEED_BIT], [0x76e15d3efefdcbbf, 0xc5004e441c522fb3, 0x77710069854ee241, 0x39109bb02acbe635]);
+
+    // Jumping by the polynomial of 2^125 steps twice is jumping by that of 2^126
+    let mut twice = [[1, 2], [3, 4], [5, 6], [7, 8]];
+    let mut once = twice;
+    jump_lanes(&mut twice, &polynomials[0]);
+    jump_lanes(&mut twice, &polynomials[0]);
+    jump_lanes(&mut once, &polynomials[1]);
+    assert_eq!(twice, once);
+}
+
+#[test]
+fn seeded_rng_starts_from_the_jumped_lanes() {
+    let lanes = seeded_lanes(7, 5);
+    let expected: Vec<u64> = (0..8)
+        .map(|lane| lanes[0][lane].wrapping_add(lanes[3][lane]).rotate_left(23).wrapping_add(lanes[0][lane]))
+        .collect();
+    assert_eq!(seeded_rng(7, 5).next_u64x8().to_array().to_vec(), expected);
+}
+
+#[test]
+fn seeded_streams_never_share_a_state() {
+    // Every lane ends where the next one starts, across streams and master seeds, so the lanes
+    // of all the streams of all the seeds follow each other along the period
+    let polynomials = jump_polynomials();
+    let next_lane = |lanes: &[[u64; 8]; 4], lane: usize| {
+        let mut state = [[lanes[0][lane]], [lanes[1][lane]], [lanes[2][lane]], [lanes[3][lane]]];
+        jump_lanes(&mut state, &polynomials[0]);
+        state.map(|word| word[0])
+    };
+    let lane_state = |lanes: &[[u64; 8]; 4], lane: usize| lanes.map(|word| word[lane]);
+    for (seed, stream, next_seed, next_stream) in [(42, 0, 42, 1), (42, 1022, 42, 1023), (42, u64::MAX, 43, 0)] {
+        let lanes = seeded_lanes(seed, stream);
+        for lane in 0..7 {
+            assert_eq!(next_lane(&lanes, lane), lane_state(&lanes, lane + 1));
+        }
+        assert_eq!(next_lane(&lanes, 7), lane_state(&seeded_lanes(next_seed, next_stream), 0));
+    }
+
+    // So no two pairs of a seed and a stream share a lane state
+    let mut states = std::collections::HashSet::new();
+    for seed in [0, 1, 2, 42, 43, u64::MAX] {
+        for stream in [0, 1, 2, 1023, u64::MAX] {
+            let lanes = seeded_lanes(seed, stream);
+            for lane in 0..8 {
+                assert!(states.insert(lane_state(&lanes, lane)));
+            }
+        }
+    }
+}
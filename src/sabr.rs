@@ -0,0 +1,295 @@
+// The SABR model of Hagan et al., in which the forward follows dF = alpha F^beta dW and its
+// volatility alpha is lognormal with volatility nu, correlated with the forward. beta sets the
+// backbone, lognormal at 1 and normal at 0, rho the skew and nu the curvature of the smile. The
+// forward can reach zero for beta < 1, where it is absorbed. Hagan's expansion of the implied
+// volatility, in bs, prices vanillas in closed form.
+
+use crate::bs;
+use crate::error::PricingError;
+use crate::float::{ Float, SimdFloat };
+use crate::mc_simd::{ self, PathPayoff, PriceEstimate, SpotModel, Terminal, Vanilla };
+use crate::params::{ MarketData, OptionContract, SimulationConfig };
+
+/// The parameters of the SABR model of the forward to the expiry of the option
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SabrModel<F: Float = f32> {
+    /// The volatility of the forward today, alpha, in units of forward^(1 - beta) a year
+    pub initial_volatility: F,
+    /// The exponent of the forward in its volatility, beta, in [0, 1]
+    pub beta: F,
+    /// The correlation of the volatility with the forward, rho
+    pub correlation: F,
+    /// The volatility of the volatility, nu
+    pub vol_of_vol: F,
+}
+
+impl<F: Float> SabrModel<F> {
+    pub fn new(initial_volatility: F, beta: F, correlation: F, vol_of_vol: F) -> SabrModel<F> {
+        SabrModel { initial_volatility, beta, correlation, vol_of_vol }
+    }
+
+    pub fn validate(&self) -> Result<(), PricingError> {
+        if !(self.initial_volatility > F::from_f64(0.0) && self.initial_volatility.is_finite()) {
+            return Err(PricingError::InvalidVolatility(self.initial_volatility.to_f64()));
+        }
+        if !(self.beta >= F::from_f64(0.0) && self.beta <= F::from_f64(1.0)) {
+            return Err(PricingError::InvalidBeta(self.beta.to_f64()));
+        }
+        // Hagan's expansion divides by 1 - rho, so the correlation cannot be 1
+        if !(self.correlation > F::from_f64(-1.0) && self.correlation < F::from_f64(1.0)) {
+            return Err(PricingError::InvalidVolCorrelation(self.correlation.to_f64()));
+        }
+        if !(self.vol_of_vol > F::from_f64(0.0) && self.vol_of_vol.is_finite()) {
+            return Err(PricingError::InvalidVolOfVol(self.vol_of_vol.to_f64()));
+        }
+        Ok(())
+    }
+}
+
+// Lower bound on the forward raised to beta, so that the log of an absorbed forward is finite
+const MIN_FORWARD: f64 = 1e-30;
+
+// The constants of a step of the SABR model, splatted to V once for all the bundles. The state of
+// every path is the forward and its volatility, both zero once the forward has been absorbed.
+struct SabrSteps<V> {
+    forward: V,
+    initial_volatility: V,
+    beta: V,
+    sqrt_dt: V,
+    correlation: V,
+    // sqrt(1 - rho^2), the weight of the normal of the forward independent of the volatility
+    complement: V,
+    // nu sqrt(dt) and -nu^2 dt / 2, the exact lognormal step of the volatility
+    vol_of_vol_sqrt_dt: V,
+    vol_drift: V,
+    // exp(-(r - q)(T - t)) after every step, which turns the forward into the spot
+    spot_factors: Vec<V>,
+}
+
+impl<V: SimdFloat> SpotModel<V> for SabrSteps<V> {
+    type State = (V, V);
+
+    fn draws_per_step(&self) -> (usize, usize) {
+        (2, 0)
+    }
+
+    fn start(&self) -> (V, V) {
+        (self.forward, self.initial_volatility)
+    }
+
+    fn step(&self, (forward, volatility): &mut (V, V), step: usize, normals: &[V], _uniforms: &[V]) -> V {
+        let zero = V::splat(0.0);
+        let spot_normal = V::mul_add(self.correlation, normals[0], self.complement * normals[1]);
+        let backbone = (self.beta * forward.fast_max(V::splat(MIN_FORWARD)).ln()).exp();
+        let next_forward = V::mul_add(*volatility * backbone * self.sqrt_dt, spot_normal, *forward);
+        let next_volatility = *volatility * V::mul_add(self.vol_of_vol_sqrt_dt, normals[0], self.vol_drift).exp();
+
+        // A forward that crosses zero is absorbed there, its volatility zeroed to keep it there
+        let alive = zero.cmp_lt(next_forward);
+        *forward = alive.blend(next_forward, zero);
+        *volatility = alive.blend(next_volatility, zero);
+        *forward * self.spot_factors[step]
+    }
+}
+
+/// Price a path-dependent option expiring in `years_to_expiry` years in the SABR model of the
+/// forward to expiry, with the standard error and confidence interval of the price. The forward
+/// is stepped with Euler steps and absorbed at zero, and the volatility with exact lognormal
+/// steps. The volatility of the market is not used. The payoff is shown the spot after each of
+/// the `config.steps` steps, the forward discounted by the carry to expiry, as in
+/// [`mc_simd::price_path`].
+pub fn price_path<F: Float, P: PathPayoff<F::Lanes>>(
+    model: &SabrModel<F>,
+    market: &MarketData<F>,
+    years_to_expiry: F,
+    config: &SimulationConfig,
+    payoff: &P
+) -> Result<PriceEstimate<F>, PricingError> {
+    model.validate()?;
+    mc_simd::validate_path(market, years_to_expiry, config)?;
+
+    let risk_free_rate = market.risk_free_rate.to_f64();
+    let carry = risk_free_rate - market.carry_yield().to_f64();
+    let years_to_expiry = years_to_expiry.to_f64();
+    let dt = years_to_expiry / (config.steps as f64);
+    let correlation = model.correlation.to_f64();
+    let vol_of_vol = model.vol_of_vol.to_f64();
+    let steps = SabrSteps {
+        forward: F::Lanes::splat(market.spot.to_f64() * (carry * years_to_expiry).exp()),
+        initial_volatility: F::Lanes::splat(model.initial_volatility.to_f64()),
+        beta: F::Lanes::splat(model.beta.to_f64()),
+        sqrt_dt: F::Lanes::splat(dt.sqrt()),
+        correlation: F::Lanes::splat(correlation),
+        complement: F::Lanes::splat((1.0 - correlation * correlation).sqrt()),
+        vol_of_vol_sqrt_dt: F::Lanes::splat(vol_of_vol * dt.sqrt()),
+        vol_drift: F::Lanes::splat(-0.5 * vol_of_vol * vol_of_vol * dt),
+        spot_factors: (1..=config.steps)
+            .map(|step| F::Lanes::splat((-carry * (years_to_expiry - (step as f64) * dt)).exp()))
+            .collect(),
+    };
+    Ok(mc_simd::monte_carlo_model_pricing(
+        &steps,
+        risk_free_rate,
+        years_to_expiry,
+        config,
+        payoff
+    ))
+}
+
+/// Price a European option in the SABR model, with the standard error and confidence interval
+/// of the price
+pub fn price<F: Float>(
+    contract: &OptionContract<F>,
+    model: &SabrModel<F>,
+    market: &MarketData<F>,
+    config: &SimulationConfig
+) -> Result<PriceEstimate<F>, PricingError> {
+    contract.validate()?;
+
+    let payoff = Vanilla::<F::Lanes>::new(contract.strike.to_f64(), contract.option_type.call_mult());
+    price_path(model, market, contract.years_to_expiry, config, &Terminal(payoff))
+}
+
+/// The Black-Scholes implied volatility of a European option in the SABR model, by Hagan's
+/// expansion [`bs::sabr_implied_volatility`] at the forward to expiry
+pub fn implied_volatility<F: Float>(
+    contract: &OptionContract<F>,
+    model: &SabrModel<F>,
+    market: &MarketData<F>
+) -> Result<F, PricingError> {
+    contract.validate()?;
+    model.validate()?;
+    market.validate()?;
+    if contract.strike == F::from_f64(0.0) {
+        return Err(PricingError::InvalidStrike(contract.strike.to_f64()));
+    }
+
+    let carry = market.risk_free_rate - market.carry_yield();
+    Ok(bs::sabr_implied_volatility(
+        market.spot * (carry * contract.years_to_expiry).exp(),
+        contract.strike,
+        contract.years_to_expiry,
+        model.initial_volatility,
+        model.beta,
+        model.correlation,
+        model.vol_of_vol
+    ))
+}
+
+/// Price a European option in the SABR model in closed form, the Black-Scholes price at the
+/// implied volatility of [`implied_volatility`]
+pub fn analytic_price<F: Float>(
+    contract: &OptionContract<F>,
+    model: &SabrModel<F>,
+    market: &MarketData<F>
+) -> Result<F, PricingError> {
+    let volatility = implied_volatility(contract, model, market)?;
+
+    Ok(bs::price(
+        contract.option_type,
+        market.spot,
+        contract.strike,
+        volatility,
+        market.risk_free_rate,
+        contract.years_to_expiry,
+        market.carry_yield()
+    ))
+}
+
+#[cfg(test)]
+use wide::{ f32x8, f64x4 };
+#[cfg(test)]
+use crate::params::OptionType;
+
+#[test]
+fn sabr_matches_hagan_price() {
+    // A 20% lognormal volatility at the money for beta = 1/2, and nu^2 T small enough that
+    // Hagan's expansion is off by less than the noise on the wings
+    let market = MarketData::new(100.0, 0.2, 0.04, 0.01);
+    let model = SabrModel::new(2.0, 0.5, -0.3, 0.2);
+    let config = SimulationConfig::builder().steps(64).num_trials(100000).seed(131).antithetic(true).build().unwrap();
+    for (option_type, strike) in [(OptionType::Put, 80.0), (OptionType::Call, 125.0)] {
+        let contract = OptionContract::new(option_type, strike, 1.0);
+        let estimate = price(&contract, &model, &market, &config).unwrap();
+        let actual_price = analytic_price(&contract, &model, &market).unwrap();
+        println!("sabr {:?} {} {:?} vs {}", option_type, strike, estimate, actual_price);
+        assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+    }
+}
+
+#[test]
+fn sabr_scheme_bias_shrinks_with_steps() {
+    // The Euler steps of the forward underprice the call above the spot by about 0.03 on 4 steps,
+    // and the bias falls below the noise by 64
+    let market = MarketData::new(100.0, 0.2, 0.04, 0.01);
+    let model = SabrModel::new(2.0, 0.5, -0.3, 0.2);
+    let contract = OptionContract::new(OptionType::Call, 125.0, 1.0);
+    let actual_price = analytic_price(&contract, &model, &market).unwrap();
+    let bias = |steps: usize| {
+        let config = SimulationConfig::builder().steps(steps).num_trials(400000).seed(134).antithetic(true).build().unwrap();
+        let estimate = price(&contract, &model, &market, &config).unwrap();
+        println!("sabr {} steps {:?} vs {}", steps, estimate, actual_price);
+        (estimate.price - actual_price, estimate.std_error)
+    };
+    let (coarse_bias, coarse_std_error) = bias(4);
+    let (medium_bias, _) = bias(16);
+    let (fine_bias, fine_std_error) = bias(64);
+    assert!(coarse_bias < -4.0 * coarse_std_error);
+    assert!(medium_bias.abs() < 0.5 * coarse_bias.abs());
+    assert!(fine_bias.abs() < 4.0 * fine_std_error);
+}
+
+#[test]
+fn f32_sabr_lognormal_backbone() {
+    let market = MarketData::new(100.0f32, 0.2, 0.05, 0.0);
+    let model = SabrModel::new(0.25f32, 1.0, 0.2, 0.3);
+    let config = SimulationConfig::builder().steps(50).num_trials(100000).seed(132).build().unwrap();
+    let contract = OptionContract::new(OptionType::Call, 110.0f32, 0.5);
+    let estimate = price(&contract, &model, &market, &config).unwrap();
+    let actual_price = analytic_price(&contract, &model, &market).unwrap();
+    println!("f32 sabr {:?} vs {}", estimate, actual_price);
+    assert!((estimate.price - actual_price).abs() < 4.0 * estimate.std_error);
+}
+
+#[test]
+fn sabr_forward_is_absorbed_at_zero() {
+    // A normal backbone with a volatility of half the forward a year, so many paths reach zero
+    let market = MarketData::new(1.0f32, 0.2, 0.0, 0.0);
+    let model = SabrModel::new(0.5f32, 0.0, 0.0, 0.5);
+    let config = SimulationConfig::builder().steps(50).num_trials(20000).seed(133).build().unwrap();
+    let negative = price_path(&model, &market, 4.0, &config, &Terminal(|spot: f32x8| spot.fast_min(f32x8::splat(0.0))))
+        .unwrap();
+    assert_eq!(negative.price, 0.0);
+    let absorbed = price_path(&model, &market, 4.0, &config, &Terminal(|spot: f32x8| {
+        f32x8::splat(0.0).cmp_lt(spot).blend(f32x8::splat(0.0), f32x8::splat(1.0))
+    })).unwrap();
+    println!("sabr absorbed {:?}", absorbed);
+    assert!(absorbed.price > 0.1);
+
+    // The spot is the forward discounted by the carry, so its mean at expiry is the forward
+    let market = MarketData::new(100.0, 0.2, 0.05, 0.02);
+    let model = SabrModel::new(0.3, 1.0, -0.5, 0.6);
+    let estimate = price_path(&model, &market, 2.0, &config, &Terminal(|spot: f64x4| spot)).unwrap();
+    let forward_value = 100.0 * (-0.02f64 * 2.0).exp();
+    assert!((estimate.price - forward_value).abs() < 4.0 * estimate.std_error);
+}
+
+#[test]
+fn invalid_sabr_parameters() {
+    let market = MarketData::new(100.0, 0.2, 0.05, 0.0);
+    let contract = OptionContract::new(OptionType::Call, 100.0, 1.0);
+    let config = SimulationConfig::new(10, 100);
+    let model = SabrModel::new(2.0, 0.5, -0.3, 0.4);
+    let cases = [
+        (SabrModel { initial_volatility: 0.0, ..model }, PricingError::InvalidVolatility(0.0)),
+        (SabrModel { beta: 1.5, ..model }, PricingError::InvalidBeta(1.5)),
+        (SabrModel { correlation: 1.0, ..model }, PricingError::InvalidVolCorrelation(1.0)),
+        (SabrModel { vol_of_vol: -0.4, ..model }, PricingError::InvalidVolOfVol(-0.4)),
+    ];
+    for (model, error) in cases {
+        assert_eq!(price(&contract, &model, &market, &config), Err(error));
+        assert_eq!(analytic_price(&contract, &model, &market), Err(error));
+    }
+    let zero_strike = OptionContract::new(OptionType::Call, 0.0, 1.0);
+    assert_eq!(implied_volatility(&zero_strike, &model, &market), Err(PricingError::InvalidStrike(0.0)));
+}